@@ -0,0 +1,23 @@
+//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+    endpoints.push(crate::event::push_events::v1::METADATA);
+    endpoints.push(crate::query::query_room_alias::v1::METADATA);
+    endpoints.push(crate::query::query_user_id::v1::METADATA);
+    endpoints.push(crate::thirdparty::get_location_for_protocol::v1::METADATA);
+    endpoints.push(crate::thirdparty::get_location_for_room_alias::v1::METADATA);
+    endpoints.push(crate::thirdparty::get_protocol::v1::METADATA);
+    endpoints.push(crate::thirdparty::get_user_for_protocol::v1::METADATA);
+    endpoints.push(crate::thirdparty::get_user_for_user_id::v1::METADATA);
+    endpoints
+}
@@ -7,8 +7,13 @@
 
 #![warn(missing_docs)]
 
+use ruma_common::{
+    user_id::{decode_localpart, encode_localpart, localpart_is_fully_conforming},
+    IdParseError, OwnedServerName, OwnedUserId, UserId,
+};
 use serde::{Deserialize, Serialize};
 
+pub mod endpoints;
 pub mod event;
 pub mod query;
 pub mod thirdparty;
@@ -60,6 +65,94 @@ impl Namespaces {
     }
 }
 
+/// A mapping between the names of the users of a remote network and the IDs of the Matrix users
+/// that represent them, in the user namespace of an application service.
+///
+/// The user IDs have a fixed localpart prefix followed by the name of the remote user, encoded with
+/// [`encode_localpart`](ruma_common::user_id::encode_localpart) so that any name can be mapped to
+/// a fully conforming user ID and back.
+///
+/// ```
+/// use ruma_appservice_api::UserIdMapping;
+/// use ruma_common::server_name;
+///
+/// let mapping = UserIdMapping::new("_irc_", server_name!("example.org").to_owned()).unwrap();
+///
+/// let user_id = mapping.user_id("Alice[m]").unwrap();
+/// assert_eq!(user_id, "@_irc__alice=5bm=5d:example.org");
+/// assert_eq!(mapping.name(&user_id).as_deref(), Some("Alice[m]"));
+/// assert_eq!(mapping.namespace(true).regex, r"@_irc_.*:example\.org");
+/// ```
+#[derive(Clone, Debug)]
+pub struct UserIdMapping {
+    prefix: String,
+    server_name: OwnedServerName,
+}
+
+impl UserIdMapping {
+    /// Creates a new `UserIdMapping` for the user IDs with the given localpart prefix on the given
+    /// server.
+    ///
+    /// Returns an error if the prefix is not a fully conforming localpart.
+    pub fn new(
+        prefix: impl Into<String>,
+        server_name: OwnedServerName,
+    ) -> Result<Self, IdParseError> {
+        let prefix = prefix.into();
+
+        if !localpart_is_fully_conforming(&prefix)? {
+            return Err(IdParseError::InvalidCharacters);
+        }
+
+        Ok(Self { prefix, server_name })
+    }
+
+    /// The ID of the Matrix user that represents the remote user with the given name.
+    ///
+    /// Returns an error if the user ID would be too long.
+    pub fn user_id(&self, name: &str) -> Result<OwnedUserId, IdParseError> {
+        UserId::parse(format!("@{}{}:{}", self.prefix, encode_localpart(name), self.server_name))
+    }
+
+    /// The name of the remote user that the given Matrix user represents.
+    ///
+    /// Returns `None` if the user ID is not in the namespace of this mapping, or if its localpart
+    /// is not a valid encoding of a name.
+    pub fn name(&self, user_id: &UserId) -> Option<String> {
+        if user_id.server_name() != self.server_name {
+            return None;
+        }
+
+        let encoded_name = user_id.localpart().strip_prefix(&self.prefix)?;
+        decode_localpart(encoded_name).ok()
+    }
+
+    /// The user [`Namespace`] that contains all the user IDs of this mapping, to use in the
+    /// [`Registration`] of the application service.
+    pub fn namespace(&self, exclusive: bool) -> Namespace {
+        let regex = format!(
+            "@{}.*:{}",
+            escape_regex(&self.prefix),
+            escape_regex(self.server_name.as_str())
+        );
+        Namespace::new(exclusive, regex)
+    }
+}
+
+/// Escapes the characters of the given string that have a special meaning in regular expressions.
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        if r"\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
 /// Information required in the registration yaml file that a homeserver needs.
 ///
 /// To create an instance of this type, first create a `RegistrationInit` and convert it via
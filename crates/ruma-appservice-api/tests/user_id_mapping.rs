@@ -0,0 +1,42 @@
+use ruma_appservice_api::UserIdMapping;
+use ruma_common::{server_name, user_id, IdParseError};
+
+#[test]
+fn user_id_mapping_roundtrip() {
+    let mapping = UserIdMapping::new("_slack_", server_name!("example.org").to_owned()).unwrap();
+
+    for name in ["alice", "Bob Smith", "carl_2", "Ωmega", "user@other.example"] {
+        let user_id = mapping.user_id(name).unwrap();
+        assert!(!user_id.is_historical());
+        assert_eq!(mapping.name(&user_id).as_deref(), Some(name));
+    }
+}
+
+#[test]
+fn user_id_mapping_outside_namespace() {
+    let mapping = UserIdMapping::new("_slack_", server_name!("example.org").to_owned()).unwrap();
+
+    assert_eq!(mapping.name(user_id!("@alice:example.org")), None);
+    assert_eq!(mapping.name(user_id!("@_slack_alice:other.example")), None);
+    assert_eq!(mapping.name(user_id!("@_slack__1:example.org")), None);
+}
+
+#[test]
+fn user_id_mapping_invalid() {
+    assert_eq!(
+        UserIdMapping::new("Slack", server_name!("example.org").to_owned()).unwrap_err(),
+        IdParseError::InvalidCharacters
+    );
+
+    let mapping = UserIdMapping::new("_slack_", server_name!("example.org").to_owned()).unwrap();
+    assert_eq!(mapping.user_id(&"a".repeat(255)).unwrap_err(), IdParseError::MaximumLengthExceeded);
+}
+
+#[test]
+fn user_id_mapping_namespace() {
+    let mapping = UserIdMapping::new("_irc_", server_name!("[::1]:8448").to_owned()).unwrap();
+    let namespace = mapping.namespace(false);
+
+    assert!(!namespace.exclusive);
+    assert_eq!(namespace.regex, r"@_irc_.*:\[::1\]:8448");
+}
@@ -0,0 +1,151 @@
+//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+    endpoints.push(crate::account::add_3pid::v3::METADATA);
+    endpoints.push(crate::account::bind_3pid::v3::METADATA);
+    endpoints.push(crate::account::change_password::v3::METADATA);
+    endpoints.push(crate::account::check_registration_token_validity::v1::METADATA);
+    endpoints.push(crate::account::deactivate::v3::METADATA);
+    endpoints.push(crate::account::delete_3pid::v3::METADATA);
+    endpoints.push(crate::account::get_3pids::v3::METADATA);
+    endpoints.push(crate::account::get_username_availability::v3::METADATA);
+    endpoints.push(crate::account::register::v3::METADATA);
+    endpoints.push(crate::account::request_3pid_management_token_via_email::v3::METADATA);
+    endpoints.push(crate::account::request_3pid_management_token_via_msisdn::v3::METADATA);
+    endpoints.push(crate::account::request_openid_token::v3::METADATA);
+    endpoints.push(crate::account::request_password_change_token_via_email::v3::METADATA);
+    endpoints.push(crate::account::request_password_change_token_via_msisdn::v3::METADATA);
+    endpoints.push(crate::account::request_registration_token_via_email::v3::METADATA);
+    endpoints.push(crate::account::request_registration_token_via_msisdn::v3::METADATA);
+    endpoints.push(crate::account::unbind_3pid::v3::METADATA);
+    endpoints.push(crate::account::whoami::v3::METADATA);
+    endpoints.push(crate::alias::create_alias::v3::METADATA);
+    endpoints.push(crate::alias::delete_alias::v3::METADATA);
+    endpoints.push(crate::alias::get_alias::v3::METADATA);
+    endpoints.push(crate::appservice::set_room_visibility::v3::METADATA);
+    endpoints.push(crate::backup::add_backup_keys::v3::METADATA);
+    endpoints.push(crate::backup::add_backup_keys_for_room::v3::METADATA);
+    endpoints.push(crate::backup::add_backup_keys_for_session::v3::METADATA);
+    endpoints.push(crate::backup::create_backup_version::v3::METADATA);
+    endpoints.push(crate::backup::delete_backup_keys::v3::METADATA);
+    endpoints.push(crate::backup::delete_backup_keys_for_room::v3::METADATA);
+    endpoints.push(crate::backup::delete_backup_keys_for_session::v3::METADATA);
+    endpoints.push(crate::backup::delete_backup_version::v3::METADATA);
+    endpoints.push(crate::backup::get_backup_info::v3::METADATA);
+    endpoints.push(crate::backup::get_backup_keys::v3::METADATA);
+    endpoints.push(crate::backup::get_backup_keys_for_room::v3::METADATA);
+    endpoints.push(crate::backup::get_backup_keys_for_session::v3::METADATA);
+    endpoints.push(crate::backup::get_latest_backup_info::v3::METADATA);
+    endpoints.push(crate::backup::update_backup_version::v3::METADATA);
+    endpoints.push(crate::config::get_global_account_data::v3::METADATA);
+    endpoints.push(crate::config::get_room_account_data::v3::METADATA);
+    endpoints.push(crate::config::set_global_account_data::v3::METADATA);
+    endpoints.push(crate::config::set_room_account_data::v3::METADATA);
+    endpoints.push(crate::context::get_context::v3::METADATA);
+    endpoints.push(crate::device::delete_device::v3::METADATA);
+    endpoints.push(crate::device::delete_devices::v3::METADATA);
+    endpoints.push(crate::device::get_device::v3::METADATA);
+    endpoints.push(crate::device::get_devices::v3::METADATA);
+    endpoints.push(crate::device::update_device::v3::METADATA);
+    endpoints.push(crate::directory::get_public_rooms::v3::METADATA);
+    endpoints.push(crate::directory::get_public_rooms_filtered::v3::METADATA);
+    endpoints.push(crate::directory::get_room_visibility::v3::METADATA);
+    endpoints.push(crate::directory::set_room_visibility::v3::METADATA);
+    endpoints.push(crate::discovery::discover_homeserver::METADATA);
+    endpoints.push(crate::discovery::get_capabilities::v3::METADATA);
+    endpoints.push(crate::discovery::get_supported_versions::METADATA);
+    endpoints.push(crate::filter::create_filter::v3::METADATA);
+    endpoints.push(crate::filter::get_filter::v3::METADATA);
+    endpoints.push(crate::keys::claim_keys::v3::METADATA);
+    endpoints.push(crate::keys::get_key_changes::v3::METADATA);
+    endpoints.push(crate::keys::get_keys::v3::METADATA);
+    endpoints.push(crate::keys::upload_keys::v3::METADATA);
+    endpoints.push(crate::keys::upload_signatures::v3::METADATA);
+    endpoints.push(crate::keys::upload_signing_keys::v3::METADATA);
+    endpoints.push(crate::knock::knock_room::v3::METADATA);
+    endpoints.push(crate::media::create_content::v3::METADATA);
+    endpoints.push(crate::media::get_content::v3::METADATA);
+    endpoints.push(crate::media::get_content_as_filename::v3::METADATA);
+    endpoints.push(crate::media::get_content_thumbnail::v3::METADATA);
+    endpoints.push(crate::media::get_media_config::v3::METADATA);
+    endpoints.push(crate::media::get_media_preview::v3::METADATA);
+    endpoints.push(crate::membership::ban_user::v3::METADATA);
+    endpoints.push(crate::membership::forget_room::v3::METADATA);
+    endpoints.push(crate::membership::get_member_events::v3::METADATA);
+    endpoints.push(crate::membership::invite_user::v3::METADATA);
+    endpoints.push(crate::membership::join_room_by_id::v3::METADATA);
+    endpoints.push(crate::membership::join_room_by_id_or_alias::v3::METADATA);
+    endpoints.push(crate::membership::joined_members::v3::METADATA);
+    endpoints.push(crate::membership::joined_rooms::v3::METADATA);
+    endpoints.push(crate::membership::kick_user::v3::METADATA);
+    endpoints.push(crate::membership::leave_room::v3::METADATA);
+    endpoints.push(crate::membership::unban_user::v3::METADATA);
+    endpoints.push(crate::message::get_message_events::v3::METADATA);
+    endpoints.push(crate::message::send_message_event::v3::METADATA);
+    endpoints.push(crate::presence::get_presence::v3::METADATA);
+    endpoints.push(crate::presence::set_presence::v3::METADATA);
+    endpoints.push(crate::profile::get_avatar_url::v3::METADATA);
+    endpoints.push(crate::profile::get_display_name::v3::METADATA);
+    endpoints.push(crate::profile::get_profile::v3::METADATA);
+    endpoints.push(crate::profile::set_avatar_url::v3::METADATA);
+    endpoints.push(crate::profile::set_display_name::v3::METADATA);
+    endpoints.push(crate::push::delete_pushrule::v3::METADATA);
+    endpoints.push(crate::push::get_notifications::v3::METADATA);
+    endpoints.push(crate::push::get_pushers::v3::METADATA);
+    endpoints.push(crate::push::get_pushrule::v3::METADATA);
+    endpoints.push(crate::push::get_pushrule_actions::v3::METADATA);
+    endpoints.push(crate::push::get_pushrule_enabled::v3::METADATA);
+    endpoints.push(crate::push::get_pushrules_all::v3::METADATA);
+    endpoints.push(crate::push::get_pushrules_global_scope::v3::METADATA);
+    endpoints.push(crate::push::set_pusher::v3::METADATA);
+    endpoints.push(crate::push::set_pushrule::v3::METADATA);
+    endpoints.push(crate::push::set_pushrule_actions::v3::METADATA);
+    endpoints.push(crate::push::set_pushrule_enabled::v3::METADATA);
+    endpoints.push(crate::read_marker::set_read_marker::v3::METADATA);
+    endpoints.push(crate::receipt::create_receipt::v3::METADATA);
+    endpoints.push(crate::redact::redact_event::v3::METADATA);
+    endpoints.push(crate::room::aliases::v3::METADATA);
+    endpoints.push(crate::room::create_room::v3::METADATA);
+    endpoints.push(crate::room::get_room_event::v3::METADATA);
+    endpoints.push(crate::room::report_content::v3::METADATA);
+    endpoints.push(crate::room::upgrade_room::v3::METADATA);
+    endpoints.push(crate::search::search_events::v3::METADATA);
+    endpoints.push(crate::server::get_user_info::v3::METADATA);
+    endpoints.push(crate::session::get_login_types::v3::METADATA);
+    endpoints.push(crate::session::login::v3::METADATA);
+    endpoints.push(crate::session::login_fallback::METADATA);
+    endpoints.push(crate::session::logout::v3::METADATA);
+    endpoints.push(crate::session::logout_all::v3::METADATA);
+    endpoints.push(crate::session::sso_login::v3::METADATA);
+    endpoints.push(crate::session::sso_login_with_provider::v3::METADATA);
+    endpoints.push(crate::space::get_hierarchy::v1::METADATA);
+    endpoints.push(crate::state::get_state_events::v3::METADATA);
+    endpoints.push(crate::state::get_state_events_for_key::v3::METADATA);
+    endpoints.push(crate::state::send_state_event::v3::METADATA);
+    endpoints.push(crate::sync::sync_events::v3::METADATA);
+    endpoints.push(crate::tag::create_tag::v3::METADATA);
+    endpoints.push(crate::tag::delete_tag::v3::METADATA);
+    endpoints.push(crate::tag::get_tags::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_location_for_protocol::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_location_for_room_alias::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_protocol::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_protocols::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_user_for_protocol::v3::METADATA);
+    endpoints.push(crate::thirdparty::get_user_for_user_id::v3::METADATA);
+    endpoints.push(crate::to_device::send_event_to_device::v3::METADATA);
+    endpoints.push(crate::typing::create_typing_event::v3::METADATA);
+    endpoints.push(crate::uiaa::get_uiaa_fallback_page::v3::METADATA);
+    endpoints.push(crate::user_directory::search_users::v3::METADATA);
+    endpoints.push(crate::voip::get_turn_server_info::v3::METADATA);
+    endpoints
+}
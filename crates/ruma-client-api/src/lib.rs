@@ -17,6 +17,7 @@ pub mod context;
 pub mod device;
 pub mod directory;
 pub mod discovery;
+pub mod endpoints;
 pub mod error;
 pub mod filter;
 pub mod keys;
@@ -5,7 +5,10 @@ pub mod v3 {
     //!
     //! [spec]: https://spec.matrix.org/v1.2/client-server-api/#post_matrixmediav3upload
 
-    use ruma_common::{api::ruma_api, OwnedMxcUri};
+    use ruma_common::{
+        api::{ruma_api, RawBody},
+        OwnedMxcUri,
+    };
 
     ruma_api! {
         metadata: {
@@ -21,8 +24,10 @@ pub mod v3 {
 
         request: {
             /// The file contents to upload.
+            ///
+            /// It can be a stream, to upload the file without holding it in memory.
             #[ruma_api(raw_body)]
-            pub file: &'a [u8],
+            pub file: RawBody,
 
             /// The name of the file being uploaded.
             #[ruma_api(query)]
@@ -70,7 +75,7 @@ pub mod v3 {
 
     impl<'a> Request<'a> {
         /// Creates a new `Request` with the given file contents.
-        pub fn new(file: &'a [u8]) -> Self {
+        pub fn new(file: RawBody) -> Self {
             Self {
                 file,
                 filename: None,
@@ -5,7 +5,10 @@ pub mod v3 {
     //!
     //! [spec]: https://spec.matrix.org/v1.2/client-server-api/#get_matrixmediav3downloadservernamemediaid
 
-    use ruma_common::{api::ruma_api, IdParseError, MxcUri, ServerName};
+    use ruma_common::{
+        api::{ruma_api, RawBody},
+        IdParseError, MxcUri, ServerName,
+    };
 
     ruma_api! {
         metadata: {
@@ -38,8 +41,11 @@ pub mod v3 {
 
         response: {
             /// The content that was previously uploaded.
+            ///
+            /// It is a stream when the response is received with a streaming body, so the file is
+            /// not held in memory.
             #[ruma_api(raw_body)]
-            pub file: Vec<u8>,
+            pub file: RawBody,
 
             /// The content type of the file that was previously uploaded.
             #[ruma_api(header = CONTENT_TYPE)]
@@ -74,7 +80,7 @@ pub mod v3 {
 
     impl Response {
         /// Creates a new `Response` with the given file contents.
-        pub fn new(file: Vec<u8>) -> Self {
+        pub fn new(file: RawBody) -> Self {
             Self { file, content_type: None, content_disposition: None }
         }
     }
@@ -6,7 +6,11 @@ pub mod v3 {
     //! [spec]: https://spec.matrix.org/v1.2/client-server-api/#get_matrixmediav3thumbnailservernamemediaid
 
     use js_int::UInt;
-    use ruma_common::{api::ruma_api, serde::StringEnum, IdParseError, MxcUri, ServerName};
+    use ruma_common::{
+        api::{ruma_api, RawBody},
+        serde::StringEnum,
+        IdParseError, MxcUri, ServerName,
+    };
 
     use crate::PrivOwnedStr;
 
@@ -58,8 +62,11 @@ pub mod v3 {
 
         response: {
             /// A thumbnail of the requested content.
+            ///
+            /// It is a stream when the response is received with a streaming body, so the file is
+            /// not held in memory.
             #[ruma_api(raw_body)]
-            pub file: Vec<u8>,
+            pub file: RawBody,
 
             /// The content type of the thumbnail.
             #[ruma_api(header = CONTENT_TYPE)]
@@ -92,7 +99,7 @@ pub mod v3 {
 
     impl Response {
         /// Creates a new `Response` with the given thumbnail.
-        pub fn new(file: Vec<u8>) -> Self {
+        pub fn new(file: RawBody) -> Self {
             Self { file, content_type: None }
         }
     }
@@ -12,6 +12,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::PrivOwnedStr;
 
+pub use ruma_common::push::RuleKind;
+
 pub mod delete_pushrule;
 pub mod get_notifications;
 pub mod get_pushers;
@@ -164,38 +166,6 @@ impl TryFrom<PushRule> for ConditionalPushRule {
     }
 }
 
-/// The kinds of push rules that are available.
-#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/doc/string_enum.md"))]
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, StringEnum)]
-#[ruma_enum(rename_all = "snake_case")]
-#[non_exhaustive]
-pub enum RuleKind {
-    /// User-configured rules that override all other kinds.
-    Override,
-
-    /// Lowest priority user-defined rules.
-    Underride,
-
-    /// Sender-specific rules.
-    Sender,
-
-    /// Room-specific rules.
-    Room,
-
-    /// Content-specific rules.
-    Content,
-
-    #[doc(hidden)]
-    _Custom(PrivOwnedStr),
-}
-
-impl RuleKind {
-    /// Creates a string slice from this `RuleKind`.
-    pub fn as_str(&self) -> &str {
-        self.as_ref()
-    }
-}
-
 /// Which kind a pusher is.
 ///
 /// This type can hold an arbitrary string. To build this with a custom value, convert it from a
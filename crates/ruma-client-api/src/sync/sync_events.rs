@@ -16,6 +16,7 @@ pub mod v3 {
             AnyToDeviceEvent,
         },
         presence::PresenceState,
+        push::UnreadCounts,
         serde::{Incoming, Raw},
         DeviceKeyAlgorithm, OwnedRoomId, OwnedUserId,
     };
@@ -346,6 +347,15 @@ pub mod v3 {
         }
     }
 
+    impl From<UnreadCounts> for UnreadNotificationsCount {
+        fn from(counts: UnreadCounts) -> Self {
+            Self {
+                highlight_count: Some(counts.highlight_count),
+                notification_count: Some(counts.notification_count),
+            }
+        }
+    }
+
     /// Events in the room.
     #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     #[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
@@ -10,7 +10,7 @@ use ruma_common::{
         error::{DeserializationError, IntoHttpError},
         EndpointError, OutgoingResponse,
     },
-    serde::{from_raw_json_value, Incoming, JsonObject, StringEnum},
+    serde::{from_raw_json_value, Incoming, JsonObject},
     thirdparty::Medium,
     ClientSecret, OwnedSessionId, OwnedUserId, UserId,
 };
@@ -22,14 +22,13 @@ use serde_json::{
     from_slice as from_json_slice, value::RawValue as RawJsonValue, Value as JsonValue,
 };
 
-use crate::{
-    error::{Error as MatrixError, ErrorBody},
-    PrivOwnedStr,
-};
+use crate::error::Error as MatrixError;
 
 pub mod get_uiaa_fallback_page;
 mod user_serde;
 
+pub use ruma_common::api::error::{AuthFlow, AuthType, UiaaInfo};
+
 /// Information for one authentication stage.
 ///
 /// To construct the custom `AuthData` variant you first have to construct [`IncomingAuthData::new`]
@@ -80,7 +79,7 @@ impl<'a> AuthData<'a> {
             Self::Dummy(_) => Some(AuthType::Dummy),
             Self::RegistrationToken(_) => Some(AuthType::RegistrationToken),
             Self::FallbackAcknowledgement(_) => None,
-            Self::_Custom(c) => Some(AuthType::_Custom(PrivOwnedStr(c.auth_type.into()))),
+            Self::_Custom(c) => Some(AuthType::from(c.auth_type)),
         }
     }
 
@@ -191,7 +190,7 @@ impl IncomingAuthData {
             Self::Dummy(_) => Some(AuthType::Dummy),
             Self::RegistrationToken(_) => Some(AuthType::RegistrationToken),
             Self::FallbackAcknowledgement(_) => None,
-            Self::_Custom(c) => Some(AuthType::_Custom(PrivOwnedStr(c.auth_type.as_str().into()))),
+            Self::_Custom(c) => Some(AuthType::from(c.auth_type.as_str())),
         }
     }
 
@@ -301,43 +300,6 @@ impl<'de> Deserialize<'de> for IncomingAuthData {
     }
 }
 
-/// The type of an authentication stage.
-#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/doc/string_enum.md"))]
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, StringEnum)]
-#[non_exhaustive]
-pub enum AuthType {
-    /// Password-based authentication (`m.login.password`).
-    #[ruma_enum(rename = "m.login.password")]
-    Password,
-
-    /// Google ReCaptcha 2.0 authentication (`m.login.recaptcha`).
-    #[ruma_enum(rename = "m.login.recaptcha")]
-    ReCaptcha,
-
-    /// Email-based authentication (`m.login.email.identity`).
-    #[ruma_enum(rename = "m.login.email.identity")]
-    EmailIdentity,
-
-    /// Phone number-based authentication (`m.login.msisdn`).
-    #[ruma_enum(rename = "m.login.msisdn")]
-    Msisdn,
-
-    /// SSO-based authentication (`m.login.sso`).
-    #[ruma_enum(rename = "m.login.sso")]
-    Sso,
-
-    /// Dummy authentication (`m.login.dummy`).
-    #[ruma_enum(rename = "m.login.dummy")]
-    Dummy,
-
-    /// Registration token-based authentication (`m.login.registration_token`).
-    #[ruma_enum(rename = "m.login.registration_token")]
-    RegistrationToken,
-
-    #[doc(hidden)]
-    _Custom(PrivOwnedStr),
-}
-
 /// Data for password-based UIAA flow.
 ///
 /// See [the spec] for how to use this.
@@ -651,57 +613,6 @@ impl ThirdpartyIdCredentials {
     }
 }
 
-/// Information about available authentication flows and status for User-Interactive Authenticiation
-/// API.
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
-pub struct UiaaInfo {
-    /// List of authentication flows available for this endpoint.
-    pub flows: Vec<AuthFlow>,
-
-    /// List of stages in the current flow completed by the client.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub completed: Vec<AuthType>,
-
-    /// Authentication parameters required for the client to complete authentication.
-    ///
-    /// To create a `Box<RawJsonValue>`, use `serde_json::value::to_raw_value`.
-    pub params: Box<RawJsonValue>,
-
-    /// Session key for client to use to complete authentication.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub session: Option<String>,
-
-    /// Authentication-related errors for previous request returned by homeserver.
-    #[serde(flatten, skip_serializing_if = "Option::is_none")]
-    pub auth_error: Option<ErrorBody>,
-}
-
-impl UiaaInfo {
-    /// Creates a new `UiaaInfo` with the given flows and parameters.
-    pub fn new(flows: Vec<AuthFlow>, params: Box<RawJsonValue>) -> Self {
-        Self { flows, completed: Vec::new(), params, session: None, auth_error: None }
-    }
-}
-
-/// Description of steps required to authenticate via the User-Interactive Authentication API.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
-pub struct AuthFlow {
-    /// Ordered list of stages required to complete authentication.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub stages: Vec<AuthType>,
-}
-
-impl AuthFlow {
-    /// Creates a new `AuthFlow` with the given stages.
-    ///
-    /// To create an empty `AuthFlow`, use `AuthFlow::default()`.
-    pub fn new(stages: Vec<AuthType>) -> Self {
-        Self { stages }
-    }
-}
-
 /// Contains either a User-Interactive Authentication API response body or a Matrix error.
 #[derive(Clone, Debug)]
 #[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
@@ -0,0 +1,16 @@
+#![cfg(feature = "client")]
+
+use ruma_client_api::{endpoints, sync::sync_events};
+
+#[test]
+fn registry_contains_endpoints() {
+    let endpoints = endpoints::all();
+
+    assert!(endpoints.iter().any(|metadata| metadata.name == sync_events::v3::METADATA.name));
+    assert!(endpoints.iter().all(|metadata| {
+        [metadata.unstable_path, metadata.r0_path, metadata.stable_path]
+            .into_iter()
+            .flatten()
+            .all(|path| path.starts_with('/'))
+    }));
+}
@@ -132,6 +132,7 @@ fn deserialize_uiaa_info() {
             auth_error: Some(ErrorBody {
                 kind: ErrorKind::Forbidden,
                 message: error_message,
+                ..
             }),
             completed,
             flows,
@@ -231,6 +232,7 @@ fn try_uiaa_response_from_http_response() {
             auth_error: Some(ErrorBody {
                 kind: ErrorKind::Forbidden,
                 message: error_message,
+                ..
             }),
             completed,
             flows,
@@ -13,7 +13,7 @@ use ruma_client_api::{
     uiaa::UserIdentifier,
 };
 use ruma_common::{
-    api::{MatrixVersion, OutgoingRequest, SendAccessToken},
+    api::{MatrixVersion, OutgoingRequest, RawBody, SendAccessToken},
     presence::PresenceState,
     DeviceId, UserId,
 };
@@ -76,7 +76,7 @@ impl<C: HttpClient> Client<C> {
     ) -> ResponseResult<C, R>
     where
         R: OutgoingRequest,
-        F: FnOnce(&mut http::Request<C::RequestBody>) -> Result<(), ResponseError<C, R>>,
+        F: FnOnce(&mut http::Request<RawBody>) -> Result<(), ResponseError<C, R>>,
     {
         let access_token = self.access_token();
         let send_access_token = match access_token.as_deref() {
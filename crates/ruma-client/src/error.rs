@@ -20,6 +20,9 @@ pub enum Error<E, F> {
     /// Couldn't obtain an HTTP response (e.g. due to network or DNS issues).
     Response(E),
 
+    /// Couldn't read the body of an HTTP response that needs to be deserialized.
+    ResponseBody(Box<dyn std::error::Error + Send + Sync>),
+
     /// Converting the HTTP response to one of ruma's types failed.
     FromHttpResponse(FromHttpResponseError<F>),
 }
@@ -33,6 +36,7 @@ impl<E: Display, F: Display> Display for Error<E, F> {
             Self::IntoHttp(err) => write!(f, "HTTP request construction failed: {}", err),
             Self::Url(err) => write!(f, "Invalid URL: {}", err),
             Self::Response(err) => write!(f, "Couldn't obtain a response: {}", err),
+            Self::ResponseBody(err) => write!(f, "Couldn't read the response body: {}", err),
             Self::FromHttpResponse(err) => write!(f, "HTTP response conversion failed: {}", err),
         }
     }
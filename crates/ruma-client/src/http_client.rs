@@ -4,9 +4,8 @@
 use std::{future::Future, pin::Pin};
 
 use async_trait::async_trait;
-use bytes::BufMut;
 use ruma_common::{
-    api::{MatrixVersion, OutgoingRequest, SendAccessToken},
+    api::{MatrixVersion, OutgoingRequest, RawBody, SendAccessToken},
     UserId,
 };
 
@@ -16,6 +15,8 @@ use crate::{add_user_id_to_query, ResponseError, ResponseResult};
 mod hyper;
 #[cfg(feature = "isahc")]
 mod isahc;
+#[cfg(feature = "mock")]
+mod mock;
 #[cfg(feature = "reqwest")]
 mod reqwest;
 
@@ -27,26 +28,27 @@ pub use self::hyper::HyperNativeTls;
 pub use self::hyper::HyperRustls;
 #[cfg(feature = "isahc")]
 pub use self::isahc::Isahc;
+#[cfg(feature = "mock")]
+pub use self::mock::{MockError, MockHttpClient};
 #[cfg(feature = "reqwest")]
 pub use self::reqwest::Reqwest;
 
 /// An HTTP client that can be used to send requests to a Matrix homeserver.
+///
+/// The bodies of the requests and responses are [`RawBody`]s, which can be streams, so the
+/// content of the media endpoints doesn't need to be held in memory.
 #[async_trait]
 pub trait HttpClient: Sync {
-    /// The type to use for `try_into_http_request`.
-    type RequestBody: Default + BufMut + Send;
-
-    /// The type to use for `try_from_http_response`.
-    type ResponseBody: AsRef<[u8]>;
-
     /// The error type for the `send_request` function.
     type Error: Send + Unpin;
 
     /// Send an `http::Request` to get back an `http::Response`.
+    ///
+    /// The body of the response should be a stream, unless it was already received entirely.
     async fn send_http_request(
         &self,
-        req: http::Request<Self::RequestBody>,
-    ) -> Result<http::Response<Self::ResponseBody>, Self::Error>;
+        req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, Self::Error>;
 }
 
 /// An HTTP client that has a default configuration.
@@ -91,7 +93,7 @@ pub trait HttpClientExt: HttpClient {
     ) -> Pin<Box<dyn Future<Output = ResponseResult<Self, R>> + 'a>>
     where
         R: OutgoingRequest + 'a,
-        F: FnOnce(&mut http::Request<Self::RequestBody>) -> Result<(), ResponseError<Self, R>> + 'a,
+        F: FnOnce(&mut http::Request<RawBody>) -> Result<(), ResponseError<Self, R>> + 'a,
     {
         Box::pin(crate::send_customized_request(
             self,
@@ -136,14 +138,12 @@ pub struct Dummy;
 
 #[async_trait]
 impl HttpClient for Dummy {
-    type RequestBody = Vec<u8>;
-    type ResponseBody = Vec<u8>;
     type Error = ();
 
     async fn send_http_request(
         &self,
-        _req: http::Request<Self::RequestBody>,
-    ) -> Result<http::Response<Self::ResponseBody>, Self::Error> {
+        _req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, Self::Error> {
         unimplemented!("this client only exists to allow doctests to compile")
     }
 }
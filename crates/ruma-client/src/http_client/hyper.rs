@@ -1,6 +1,9 @@
 use async_trait::async_trait;
-use bytes::{Bytes, BytesMut};
-use hyper::client::{connect::Connect, HttpConnector};
+use hyper::{
+    body::HttpBody,
+    client::{connect::Connect, HttpConnector},
+};
+use ruma_common::api::RawBody;
 
 use super::{DefaultConstructibleHttpClient, HttpClient};
 
@@ -25,23 +28,27 @@ impl<C> HttpClient for hyper::Client<C>
 where
     C: Connect + Clone + Send + Sync + 'static,
 {
-    type RequestBody = BytesMut;
-    type ResponseBody = Bytes;
     type Error = hyper::Error;
 
     async fn send_http_request(
         &self,
-        req: http::Request<BytesMut>,
-    ) -> Result<http::Response<Bytes>, hyper::Error> {
-        let (head, body) = self
-            .request(req.map(|body| hyper::body::Body::from(body.freeze())))
-            .await?
-            .into_parts();
-
-        // FIXME: Use aggregate instead of to_bytes once serde_json can parse from a reader at a
-        // comparable speed as reading from a slice: https://github.com/serde-rs/json/issues/160
-        let body = hyper::body::to_bytes(body).await?;
-        Ok(http::Response::from_parts(head, body))
+        req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, hyper::Error> {
+        let req = req.map(|body| match body.into_bytes() {
+            Ok(bytes) => hyper::Body::from(bytes),
+            Err(body) => hyper::Body::wrap_stream(body),
+        });
+
+        let res = self.request(req).await?;
+        Ok(res.map(|body| {
+            let content_length = body.size_hint().exact();
+            let body = RawBody::from_stream(body);
+
+            match content_length {
+                Some(content_length) => body.with_content_length(content_length),
+                None => body,
+            }
+        }))
     }
 }
 
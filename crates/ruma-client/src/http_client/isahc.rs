@@ -1,24 +1,61 @@
+use async_stream::try_stream;
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures_lite::AsyncReadExt;
+use futures_util::TryStreamExt;
+use isahc::AsyncBody;
+use ruma_common::api::RawBody;
 
 use super::HttpClient;
 
 /// The `isahc` crate's `HttpClient`.
 pub type Isahc = isahc::HttpClient;
 
+/// The size of the chunks of the bodies of the responses.
+const CHUNK_SIZE: usize = 8 * 1024;
+
 #[async_trait]
 impl HttpClient for Isahc {
-    type RequestBody = Vec<u8>;
-    type ResponseBody = Vec<u8>;
     type Error = isahc::Error;
 
     async fn send_http_request(
         &self,
-        req: http::Request<Vec<u8>>,
-    ) -> Result<http::Response<Vec<u8>>, isahc::Error> {
+        req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, isahc::Error> {
+        let req = req.map(|body| match body.into_bytes() {
+            Ok(bytes) => AsyncBody::from_bytes_static(bytes),
+            Err(body) => {
+                let content_length = body.content_length();
+                let reader = body
+                    .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+                    .into_async_read();
+
+                match content_length {
+                    Some(content_length) => AsyncBody::from_reader_sized(reader, content_length),
+                    None => AsyncBody::from_reader(reader),
+                }
+            }
+        });
+
         let (head, mut body) = self.send_async(req).await?.into_parts();
-        let mut full_body = Vec::new();
-        body.read_to_end(&mut full_body).await?;
-        Ok(http::Response::from_parts(head, full_body))
+        let content_length = body.len();
+
+        let stream = try_stream! {
+            let mut buf = vec![0; CHUNK_SIZE];
+            loop {
+                let read = body.read(&mut buf).await?;
+                if read == 0 {
+                    break;
+                }
+
+                yield Bytes::copy_from_slice(&buf[..read]);
+            }
+        };
+        let mut body = RawBody::from_stream::<_, std::io::Error>(stream);
+        if let Some(content_length) = content_length {
+            body = body.with_content_length(content_length);
+        }
+
+        Ok(http::Response::from_parts(head, body))
     }
 }
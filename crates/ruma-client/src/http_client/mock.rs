@@ -0,0 +1,294 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, VecDeque},
+    error::Error as StdError,
+    fmt, future, mem,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use http::StatusCode;
+use ruma_common::api::{
+    error::{FromHttpRequestError, IntoHttpError, RouteError},
+    IncomingRequest, OutgoingResponse, RawBody, Router,
+};
+use serde_json::json;
+
+use super::HttpClient;
+
+type Handler<R> = dyn Fn(R) -> Result<<R as IncomingRequest>::OutgoingResponse, <R as IncomingRequest>::EndpointError>
+    + Send
+    + Sync;
+
+type HandleResult = Result<http::Response<RawBody>, MockError>;
+
+/// A request that was routed to its endpoint, and that is handled once the lock on the
+/// [`MockHttpClient`] is released.
+type RoutedRequest = Box<dyn FnOnce() -> HandleResult>;
+
+/// An HTTP client that answers the requests itself, as a homeserver would, without using the
+/// network.
+///
+/// This is meant to test code built on top of the `Client` or [`HttpClientExt`] offline. The
+/// requests are converted to the [`IncomingRequest`] types of the endpoints they match, so the
+/// request types need to implement it, which is the case with the `server` feature of the API
+/// crates.
+///
+/// For every endpoint, the responses queued with [`push_response`](Self::push_response) are
+/// returned first, in order, then the handler set with [`set_handler`](Self::set_handler) is
+/// called. Requests to endpoints without any of them get a `404` response with the
+/// `M_UNRECOGNIZED` error code.
+///
+/// Cloning a `MockHttpClient` returns a handle to the same mock, so it can still be configured and
+/// inspected after having been given to a `Client`.
+///
+/// [`HttpClientExt`]: super::HttpClientExt
+///
+/// # Example
+///
+/// ```
+/// use ruma_client::{http_client::MockHttpClient, Client};
+/// use ruma_client_api::{
+///     discovery::get_supported_versions,
+///     error::{Error, ErrorKind},
+///     sync::sync_events,
+/// };
+///
+/// # async {
+/// let mock = MockHttpClient::new();
+/// mock.set_handler(|_: get_supported_versions::Request| {
+///     Ok(get_supported_versions::Response::new(vec!["v1.2".to_owned()]))
+/// });
+/// mock.push_response::<sync_events::v3::IncomingRequest>(Err(Error::new(
+///     http::StatusCode::TOO_MANY_REQUESTS,
+///     ErrorKind::LimitExceeded { retry_after_ms: None },
+///     "Too many requests".to_owned(),
+/// )));
+///
+/// let client = Client::builder()
+///     .homeserver_url("https://example.com".to_owned())
+///     .http_client(mock.clone())
+///     .await?;
+///
+/// // Test the sync loop...
+///
+/// assert_eq!(mock.take_requests().len(), 2);
+/// # Result::<(), ruma_client::Error<_, _>>::Ok(())
+/// # };
+/// ```
+#[derive(Clone, Default)]
+pub struct MockHttpClient {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    router: Router<RoutedRequest>,
+    endpoints: BTreeMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    requests: Vec<http::Request<Vec<u8>>>,
+}
+
+/// The responses of an endpoint of a [`MockHttpClient`].
+struct MockEndpoint<R: IncomingRequest> {
+    name: &'static str,
+    responses: Mutex<VecDeque<Result<http::Response<RawBody>, IntoHttpError>>>,
+    handler: Mutex<Option<Box<Handler<R>>>>,
+}
+
+impl<R: IncomingRequest> MockEndpoint<R> {
+    fn handle(&self, request: R) -> HandleResult {
+        if let Some(response) = self.responses.lock().unwrap().pop_front() {
+            return Ok(response?);
+        }
+
+        match &*self.handler.lock().unwrap() {
+            Some(handler) => Ok(match handler(request) {
+                Ok(response) => response.try_into_streaming_http_response()?,
+                Err(error) => error.try_into_http_response::<Vec<u8>>()?.map(RawBody::from),
+            }),
+            None => Err(MockError::NoResponse(self.name)),
+        }
+    }
+}
+
+impl MockHttpClient {
+    /// Creates a new `MockHttpClient` without any endpoint.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the function that answers the requests of the endpoint of the request type `R`, when
+    /// no queued response is left.
+    ///
+    /// This replaces the previous handler of the endpoint, if any.
+    pub fn set_handler<R, F>(&self, handler: F)
+    where
+        R: IncomingRequest + 'static,
+        F: Fn(R) -> Result<R::OutgoingResponse, R::EndpointError> + Send + Sync + 'static,
+    {
+        *self.endpoint::<R>().handler.lock().unwrap() = Some(Box::new(handler));
+    }
+
+    /// Queues a response, or an error like `M_LIMIT_EXCEEDED`, to the next request of the
+    /// endpoint of the request type `R`.
+    pub fn push_response<R>(&self, response: Result<R::OutgoingResponse, R::EndpointError>)
+    where
+        R: IncomingRequest + 'static,
+    {
+        let response = match response {
+            Ok(response) => response.try_into_streaming_http_response(),
+            Err(error) => {
+                error.try_into_http_response::<Vec<u8>>().map(|res| res.map(RawBody::from))
+            }
+        };
+
+        self.endpoint::<R>().responses.lock().unwrap().push_back(response);
+    }
+
+    /// Queues an `http::Response` to the next request of the endpoint of the request type `R`.
+    ///
+    /// This can be used to test how invalid responses are handled.
+    pub fn push_http_response<R>(&self, response: http::Response<Vec<u8>>)
+    where
+        R: IncomingRequest + 'static,
+    {
+        self.endpoint::<R>().responses.lock().unwrap().push_back(Ok(response.map(RawBody::from)));
+    }
+
+    /// Takes the requests that were sent with this client, in the order they were sent.
+    ///
+    /// The requests that were not answered because of an error are included.
+    pub fn take_requests(&self) -> Vec<http::Request<Vec<u8>>> {
+        mem::take(&mut self.inner.lock().unwrap().requests)
+    }
+
+    /// The endpoint of the request type `R`, which is registered if necessary.
+    fn endpoint<R: IncomingRequest + 'static>(&self) -> Arc<MockEndpoint<R>> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(endpoint) = inner.endpoints.get(&TypeId::of::<R>()) {
+            return endpoint.clone().downcast().expect("endpoints are stored with their TypeId");
+        }
+
+        let endpoint = Arc::new(MockEndpoint::<R> {
+            name: R::METADATA.name,
+            responses: Mutex::new(VecDeque::new()),
+            handler: Mutex::new(None),
+        });
+
+        let handle_endpoint = endpoint.clone();
+        inner.router =
+            mem::take(&mut inner.router).with_endpoint(move |request: R| -> RoutedRequest {
+                let endpoint = handle_endpoint.clone();
+                Box::new(move || endpoint.handle(request))
+            });
+        inner.endpoints.insert(TypeId::of::<R>(), endpoint.clone());
+
+        endpoint
+    }
+
+    fn handle(&self, request: http::Request<Vec<u8>>) -> HandleResult {
+        let mut inner = self.inner.lock().unwrap();
+        let result = inner.router.route(copy_request(&request));
+        inner.requests.push(request);
+        // Release the lock before the handler is called, so it can use this client.
+        drop(inner);
+
+        match result {
+            Ok(routed_request) => routed_request(),
+            Err(RouteError::NotFound) => Ok(unrecognized(StatusCode::NOT_FOUND)),
+            Err(RouteError::MethodNotAllowed) => Ok(unrecognized(StatusCode::METHOD_NOT_ALLOWED)),
+            Err(RouteError::Request(e)) => Err(MockError::InvalidRequest(e)),
+            Err(e) => Err(MockError::Route(e)),
+        }
+    }
+}
+
+impl fmt::Debug for MockHttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockHttpClient").finish_non_exhaustive()
+    }
+}
+
+/// A copy of the given request, borrowing its body.
+fn copy_request(request: &http::Request<Vec<u8>>) -> http::Request<&[u8]> {
+    let mut copy = http::Request::new(request.body().as_slice());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+/// The response of a homeserver to a request that doesn't match any endpoint.
+fn unrecognized(status: StatusCode) -> http::Response<RawBody> {
+    let body = json!({ "errcode": "M_UNRECOGNIZED", "error": "Unrecognized request" });
+
+    http::Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&body).expect("JSON values can be serialized").into())
+        .expect("the response is valid")
+}
+
+#[async_trait]
+impl HttpClient for MockHttpClient {
+    type Error = MockError;
+
+    async fn send_http_request(
+        &self,
+        req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, MockError> {
+        let (parts, body) = req.into_parts();
+        let body = body
+            .try_fold(Vec::new(), |mut bytes, chunk| {
+                bytes.extend_from_slice(&chunk);
+                future::ready(Ok(bytes))
+            })
+            .await
+            .map_err(MockError::RequestBody)?;
+
+        self.handle(http::Request::from_parts(parts, body))
+    }
+}
+
+/// An error returned by a [`MockHttpClient`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MockError {
+    /// The request could not be converted to the request type of the endpoint it matched.
+    InvalidRequest(FromHttpRequestError),
+
+    /// The request could not be routed to an endpoint for another reason.
+    Route(RouteError),
+
+    /// The response could not be converted to an `http::Response`.
+    InvalidResponse(IntoHttpError),
+
+    /// The streaming body of the request returned an error.
+    RequestBody(Box<dyn StdError + Send + Sync>),
+
+    /// No queued response is left and no handler is set for the endpoint with the given name.
+    NoResponse(&'static str),
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRequest(err) => write!(f, "Invalid request: {}", err),
+            Self::Route(err) => write!(f, "Failed to route the request: {}", err),
+            Self::InvalidResponse(err) => write!(f, "Invalid response: {}", err),
+            Self::RequestBody(err) => write!(f, "Failed to read the request body: {}", err),
+            Self::NoResponse(name) => write!(f, "No response is left for the `{}` endpoint", name),
+        }
+    }
+}
+
+impl From<IntoHttpError> for MockError {
+    fn from(err: IntoHttpError) -> Self {
+        Self::InvalidResponse(err)
+    }
+}
+
+impl StdError for MockError {}
@@ -1,7 +1,7 @@
 use std::mem;
 
 use async_trait::async_trait;
-use bytes::{Bytes, BytesMut};
+use ruma_common::api::RawBody;
 
 use super::{DefaultConstructibleHttpClient, HttpClient};
 
@@ -10,15 +10,18 @@ pub type Reqwest = reqwest::Client;
 
 #[async_trait]
 impl HttpClient for Reqwest {
-    type RequestBody = BytesMut;
-    type ResponseBody = Bytes;
     type Error = reqwest::Error;
 
     async fn send_http_request(
         &self,
-        req: http::Request<BytesMut>,
-    ) -> Result<http::Response<Bytes>, reqwest::Error> {
-        let req = req.map(|body| body.freeze()).try_into()?;
+        req: http::Request<RawBody>,
+    ) -> Result<http::Response<RawBody>, reqwest::Error> {
+        let req = req
+            .map(|body| match body.into_bytes() {
+                Ok(bytes) => reqwest::Body::from(bytes),
+                Err(body) => reqwest::Body::wrap_stream(body),
+            })
+            .try_into()?;
         let mut res = self.execute(req).await?;
 
         let mut http_builder =
@@ -28,7 +31,13 @@ impl HttpClient for Reqwest {
             res.headers_mut(),
         );
 
-        Ok(http_builder.body(res.bytes().await?).expect("http::Response construction to work"))
+        let content_length = res.content_length();
+        let mut body = RawBody::from_stream(res.bytes_stream());
+        if let Some(content_length) = content_length {
+            body = body.with_content_length(content_length);
+        }
+
+        Ok(http_builder.body(body).expect("http::Response construction to work"))
     }
 }
 
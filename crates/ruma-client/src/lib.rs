@@ -102,8 +102,9 @@
 
 use std::{any::type_name, future::Future};
 
+use futures_util::{future, TryStreamExt};
 use ruma_common::{
-    api::{MatrixVersion, OutgoingRequest, SendAccessToken},
+    api::{IncomingResponse, MatrixVersion, OutgoingRequest, RawBody, SendAccessToken},
     UserId,
 };
 use tracing::{info_span, Instrument};
@@ -146,12 +147,12 @@ fn send_customized_request<'a, C, R, F>(
 where
     C: HttpClient + ?Sized,
     R: OutgoingRequest,
-    F: FnOnce(&mut http::Request<C::RequestBody>) -> Result<(), ResponseError<C, R>>,
+    F: FnOnce(&mut http::Request<RawBody>) -> Result<(), ResponseError<C, R>>,
 {
     let http_req =
         info_span!("serialize_request", request_type = type_name::<R>()).in_scope(move || {
             request
-                .try_into_http_request(homeserver_url, send_access_token, for_versions)
+                .try_into_streaming_http_request(homeserver_url, send_access_token, for_versions)
                 .map_err(ResponseError::<C, R>::from)
                 .and_then(|mut req| {
                     customize(&mut req)?;
@@ -173,19 +174,51 @@ where
             .await
             .map_err(Error::Response)?;
 
+        // Only the successful responses of endpoints with a streaming raw body keep their body as
+        // a stream, the others need to be in memory to be deserialized.
+        let (parts, body) = http_res.into_parts();
+        let body = if R::IncomingResponse::STREAMING_BODY
+            && !parts.status.is_client_error()
+            && !parts.status.is_server_error()
+        {
+            body
+        } else {
+            collect_body(body).await.map_err(Error::ResponseBody)?
+        };
+
         let res =
             info_span!("deserialize_response", response_type = type_name::<R::IncomingResponse>())
                 .in_scope(move || {
-                    ruma_common::api::IncomingResponse::try_from_http_response(http_res)
+                    R::IncomingResponse::try_from_streaming_http_response(
+                        http::Response::from_parts(parts, body),
+                    )
                 })?;
 
         Ok(res)
     }
 }
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Reads the whole given body in memory.
+async fn collect_body(body: RawBody) -> Result<RawBody, BoxError> {
+    if !body.is_stream() {
+        return Ok(body);
+    }
+
+    let bytes = body
+        .try_fold(Vec::new(), |mut bytes, chunk| {
+            bytes.extend_from_slice(&chunk);
+            future::ready(Ok(bytes))
+        })
+        .await?;
+
+    Ok(bytes.into())
+}
+
 fn add_user_id_to_query<C: HttpClient + ?Sized, R: OutgoingRequest>(
     user_id: &UserId,
-) -> impl FnOnce(&mut http::Request<C::RequestBody>) -> Result<(), ResponseError<C, R>> + '_ {
+) -> impl FnOnce(&mut http::Request<RawBody>) -> Result<(), ResponseError<C, R>> + '_ {
     use assign::assign;
     use http::uri::Uri;
     use ruma_common::serde::urlencoded;
@@ -0,0 +1,139 @@
+#![cfg(all(feature = "client-api", feature = "mock"))]
+
+use std::time::Duration;
+
+use http::{Method, StatusCode};
+use ruma_client::{
+    http_client::{MockError, MockHttpClient},
+    Client, Error,
+};
+use ruma_client_api::{
+    account::whoami,
+    discovery::get_supported_versions,
+    error::{Error as MatrixError, ErrorKind},
+};
+use ruma_common::{
+    api::{
+        error::{FromHttpResponseError, ServerError},
+        MatrixVersion,
+    },
+    user_id,
+};
+
+async fn client(mock: &MockHttpClient) -> Client<MockHttpClient> {
+    Client::builder()
+        .homeserver_url("https://example.com".to_owned())
+        .access_token(Some("secret".to_owned()))
+        .supported_matrix_versions(vec![MatrixVersion::V1_1])
+        .http_client(mock.clone())
+        .await
+        .unwrap()
+}
+
+fn whoami_response(localpart: &str) -> whoami::v3::Response {
+    whoami::v3::Response::new(format!("@{localpart}:example.com").try_into().unwrap(), false)
+}
+
+#[tokio::test]
+async fn queued_responses_before_handler() {
+    let mock = MockHttpClient::new();
+    mock.push_response::<whoami::v3::IncomingRequest>(Ok(whoami_response("first")));
+    mock.push_response::<whoami::v3::IncomingRequest>(Ok(whoami_response("second")));
+    mock.set_handler(|_: whoami::v3::IncomingRequest| Ok(whoami_response("handler")));
+    let client = client(&mock).await;
+
+    for localpart in ["first", "second", "handler", "handler"] {
+        let response = client.send_request(whoami::v3::Request::new()).await.unwrap();
+        assert_eq!(response.user_id, format!("@{localpart}:example.com"));
+    }
+}
+
+#[tokio::test]
+async fn handler_replaced() {
+    let mock = MockHttpClient::new();
+    mock.set_handler(|_: whoami::v3::IncomingRequest| Ok(whoami_response("old")));
+    mock.set_handler(|_: whoami::v3::IncomingRequest| Ok(whoami_response("new")));
+    let client = client(&mock).await;
+
+    let response = client.send_request(whoami::v3::Request::new()).await.unwrap();
+    assert_eq!(response.user_id, user_id!("@new:example.com"));
+}
+
+#[tokio::test]
+async fn take_requests() {
+    let mock = MockHttpClient::new();
+    mock.set_handler(|_: whoami::v3::IncomingRequest| Ok(whoami_response("alice")));
+    let client = client(&mock).await;
+
+    client.send_request(whoami::v3::Request::new()).await.unwrap();
+    client.send_request(get_supported_versions::Request::new()).await.unwrap_err();
+
+    let requests = mock.take_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].method(), Method::GET);
+    assert_eq!(requests[0].uri().path(), "/_matrix/client/v3/account/whoami");
+    assert_eq!(requests[0].headers()[http::header::AUTHORIZATION], "Bearer secret");
+    assert_eq!(requests[1].uri().path(), "/_matrix/client/versions");
+
+    assert_eq!(mock.take_requests().len(), 0);
+}
+
+#[tokio::test]
+async fn no_response_left() {
+    let mock = MockHttpClient::new();
+    mock.push_response::<whoami::v3::IncomingRequest>(Ok(whoami_response("alice")));
+    let client = client(&mock).await;
+
+    client.send_request(whoami::v3::Request::new()).await.unwrap();
+    let error = client.send_request(whoami::v3::Request::new()).await.unwrap_err();
+
+    match error {
+        Error::Response(MockError::NoResponse(name)) => assert_eq!(name, "whoami"),
+        error => panic!("unexpected error: {error:?}"),
+    }
+}
+
+#[tokio::test]
+async fn unknown_endpoint() {
+    let mock = MockHttpClient::new();
+    let client = client(&mock).await;
+
+    let error = client.send_request(whoami::v3::Request::new()).await.unwrap_err();
+
+    match error {
+        Error::FromHttpResponse(FromHttpResponseError::Server(ServerError::Known(error))) => {
+            assert_eq!(error.status_code, StatusCode::NOT_FOUND);
+            assert_eq!(error.kind, ErrorKind::Unrecognized);
+        }
+        error => panic!("unexpected error: {error:?}"),
+    }
+}
+
+#[tokio::test]
+async fn scripted_rate_limit() {
+    let mock = MockHttpClient::new();
+    mock.push_response::<whoami::v3::IncomingRequest>(Err(MatrixError::new(
+        StatusCode::TOO_MANY_REQUESTS,
+        ErrorKind::LimitExceeded { retry_after_ms: Some(Duration::from_millis(2000)) },
+        "Too many requests".to_owned(),
+    )));
+    mock.set_handler(|_: whoami::v3::IncomingRequest| Ok(whoami_response("alice")));
+    let client = client(&mock).await;
+
+    let error = client.send_request(whoami::v3::Request::new()).await.unwrap_err();
+    match error {
+        Error::FromHttpResponse(FromHttpResponseError::Server(ServerError::Known(error))) => {
+            assert_eq!(error.status_code, StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(
+                error.kind,
+                ErrorKind::LimitExceeded { retry_after_ms: Some(Duration::from_millis(2000)) }
+            );
+            assert_eq!(error.message, "Too many requests");
+        }
+        error => panic!("unexpected error: {error:?}"),
+    }
+
+    // The request is retried after the rate limit.
+    let response = client.send_request(whoami::v3::Request::new()).await.unwrap();
+    assert_eq!(response.user_id, user_id!("@alice:example.com"));
+}
@@ -0,0 +1,208 @@
+#![cfg(all(
+    feature = "client-api",
+    any(feature = "hyper", feature = "isahc", feature = "reqwest")
+))]
+
+use std::{convert::Infallible, fmt::Debug, net::SocketAddr};
+
+use bytes::Bytes;
+use futures_util::{stream, TryStreamExt};
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use ruma_client::{Client, Error, HttpClient};
+use ruma_client_api::{
+    error::ErrorKind,
+    media::{create_content, get_content, get_content_thumbnail},
+};
+use ruma_common::{
+    api::{
+        error::{FromHttpResponseError, ServerError},
+        MatrixVersion, RawBody,
+    },
+    mxc_uri, server_name,
+};
+
+/// Serves the media endpoints on a random local port.
+fn spawn_server() -> SocketAddr {
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    addr
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::POST, "/_matrix/media/v3/upload")
+            if request.headers().get(AUTHORIZATION).map_or(false, |v| v == "Bearer secret") =>
+        {
+            // Describe the received request in the media ID, so the test can check it.
+            let content_length = match request.headers().get(CONTENT_LENGTH) {
+                Some(value) => value.to_str().unwrap().to_owned(),
+                None => "chunked".to_owned(),
+            };
+            let content_type = request.headers()[CONTENT_TYPE].to_str().unwrap().replace('/', "-");
+            let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap().replace(' ', "-");
+
+            let content_uri = format!("mxc://localhost/{content_type}-{content_length}-{body}");
+            json_response(StatusCode::OK, serde_json::json!({ "content_uri": content_uri }))
+        }
+        (&Method::GET, "/_matrix/media/v3/download/localhost/text") => Response::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .header(CONTENT_DISPOSITION, "inline; filename=\"hello.txt\"")
+            .body(Body::wrap_stream(stream::iter(
+                ["hello", " ", "world"].map(|chunk| Ok::<_, Infallible>(Bytes::from(chunk))),
+            )))
+            .unwrap(),
+        (&Method::GET, "/_matrix/media/v3/thumbnail/localhost/text") => Response::builder()
+            .header(CONTENT_TYPE, "image/png")
+            .header(CONTENT_LENGTH, "4")
+            .body(Body::from(&b"\x89PNG"[..]))
+            .unwrap(),
+        _ => json_response(
+            StatusCode::NOT_FOUND,
+            serde_json::json!({ "errcode": "M_NOT_FOUND", "error": "Media not found" }),
+        ),
+    };
+
+    Ok(response)
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        .unwrap()
+}
+
+async fn client<C: HttpClient>(http_client: C) -> Client<C>
+where
+    C::Error: Debug,
+{
+    let addr = spawn_server();
+
+    Client::builder()
+        .homeserver_url(format!("http://{addr}"))
+        .access_token(Some("secret".to_owned()))
+        .supported_matrix_versions(vec![MatrixVersion::V1_1])
+        .http_client(http_client)
+        .await
+        .unwrap()
+}
+
+async fn collect_body(body: RawBody) -> Vec<u8> {
+    assert!(body.is_stream());
+
+    body.try_fold(Vec::new(), |mut content, chunk| async move {
+        content.extend_from_slice(&chunk);
+        Ok(content)
+    })
+    .await
+    .unwrap()
+}
+
+async fn upload<C: HttpClient>(client: &Client<C>)
+where
+    C::Error: Debug,
+{
+    let chunks = || ["hello", " ", "world"].map(|chunk| Ok::<_, Infallible>(Bytes::from(chunk)));
+    let request = |file| {
+        let mut request = create_content::v3::Request::new(file);
+        request.content_type = Some("text/plain");
+        request
+    };
+
+    let body = RawBody::from_stream(stream::iter(chunks()));
+    let response = client.send_request(request(body)).await.unwrap();
+    assert_eq!(response.content_uri, mxc_uri!("mxc://localhost/text-plain-chunked-hello-world"));
+
+    let body = RawBody::from_stream(stream::iter(chunks())).with_content_length(11);
+    let response = client.send_request(request(body)).await.unwrap();
+    assert_eq!(response.content_uri, mxc_uri!("mxc://localhost/text-plain-11-hello-world"));
+
+    let body = RawBody::from(b"hello world".to_vec());
+    let response = client.send_request(request(body)).await.unwrap();
+    assert_eq!(response.content_uri, mxc_uri!("mxc://localhost/text-plain-11-hello-world"));
+}
+
+async fn download<C: HttpClient>(client: &Client<C>)
+where
+    C::Error: Debug,
+{
+    let response = client
+        .send_request(get_content::v3::Request::new("text", server_name!("localhost")))
+        .await
+        .unwrap();
+    assert_eq!(response.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(response.content_disposition.as_deref(), Some("inline; filename=\"hello.txt\""));
+    assert_eq!(response.file.content_length(), None);
+    assert_eq!(collect_body(response.file).await, b"hello world");
+
+    let response = client
+        .send_request(get_content_thumbnail::v3::Request::new(
+            "text",
+            server_name!("localhost"),
+            32_u32.into(),
+            32_u32.into(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.content_type.as_deref(), Some("image/png"));
+    assert_eq!(response.file.content_length(), Some(4));
+    assert_eq!(collect_body(response.file).await, b"\x89PNG");
+}
+
+async fn download_not_found<C: HttpClient>(client: &Client<C>)
+where
+    C::Error: Debug,
+{
+    let error = client
+        .send_request(get_content::v3::Request::new("missing", server_name!("localhost")))
+        .await
+        .unwrap_err();
+
+    match error {
+        Error::FromHttpResponse(FromHttpResponseError::Server(ServerError::Known(error))) => {
+            assert_eq!(error.status_code, StatusCode::NOT_FOUND);
+            assert_eq!(error.kind, ErrorKind::NotFound);
+            assert_eq!(error.message, "Media not found");
+        }
+        error => panic!("unexpected error: {error:?}"),
+    }
+}
+
+#[cfg(feature = "hyper")]
+#[tokio::test]
+async fn hyper_streaming() {
+    let client = client(ruma_client::http_client::Hyper::new()).await;
+
+    upload(&client).await;
+    download(&client).await;
+    download_not_found(&client).await;
+}
+
+#[cfg(feature = "isahc")]
+#[tokio::test]
+async fn isahc_streaming() {
+    let client = client(ruma_client::http_client::Isahc::new().unwrap()).await;
+
+    upload(&client).await;
+    download(&client).await;
+    download_not_found(&client).await;
+}
+
+#[cfg(feature = "reqwest")]
+#[tokio::test]
+async fn reqwest_streaming() {
+    let client = client(ruma_client::http_client::Reqwest::new()).await;
+
+    upload(&client).await;
+    download(&client).await;
+    download_not_found(&client).await;
+}
@@ -0,0 +1,113 @@
+// To pass args to criterion, use this form
+// `cargo bench --features criterion --bench push_rules -- --save-baseline <name>`.
+
+#![allow(unused_imports, dead_code)]
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "criterion")]
+use criterion::{criterion_group, criterion_main, Criterion};
+use js_int::{int, uint};
+use ruma_common::{
+    power_levels::NotificationPowerLevels,
+    push::{CompiledRuleset, FlattenedJson, PushConditionRoomCtx, Ruleset},
+    room_id,
+    serde::Raw,
+    user_id, OwnedUserId,
+};
+use serde_json::{json, Value as JsonValue};
+
+const USERS: usize = 1000;
+
+fn message() -> Raw<JsonValue> {
+    Raw::new(&json!({
+        "content": {
+            "body": "Hey jj, do you have a minute? The Daltons escaped again.",
+            "msgtype": "m.text"
+        },
+        "event_id": "$15139375512JaHAW:localhost",
+        "origin_server_ts": 45,
+        "sender": "@example:localhost",
+        "room_id": "!room:localhost",
+        "type": "m.room.message",
+        "unsigned": {
+            "age": 45
+        }
+    }))
+    .unwrap()
+}
+
+fn users() -> Vec<OwnedUserId> {
+    (0..USERS).map(|i| format!("@user{}:localhost", i).try_into().unwrap()).collect()
+}
+
+fn context(user_id: OwnedUserId) -> PushConditionRoomCtx {
+    PushConditionRoomCtx {
+        room_id: room_id!("!room:localhost").to_owned(),
+        member_count: uint!(1000),
+        user_display_name: user_id.localpart().to_owned(),
+        user_id,
+        users_power_levels: BTreeMap::new(),
+        default_power_level: int!(0),
+        notification_power_levels: NotificationPowerLevels::new(),
+    }
+}
+
+#[cfg(feature = "criterion")]
+fn ruleset_get_actions(c: &mut Criterion) {
+    let event = message();
+    let users: Vec<_> = users()
+        .into_iter()
+        .map(|user_id| (Ruleset::server_default(&user_id), context(user_id)))
+        .collect();
+
+    c.bench_function("`Ruleset::get_actions` for 1000 users", |b| {
+        b.iter(|| {
+            for (ruleset, context) in &users {
+                let _ = ruleset.get_actions(&event, context);
+            }
+        })
+    });
+}
+
+#[cfg(feature = "criterion")]
+fn compiled_ruleset_get_actions(c: &mut Criterion) {
+    let event = message();
+    let users: Vec<_> = users()
+        .into_iter()
+        .map(|user_id| (CompiledRuleset::from(Ruleset::server_default(&user_id)), context(user_id)))
+        .collect();
+
+    c.bench_function("`CompiledRuleset::get_actions` for 1000 users", |b| {
+        b.iter(|| {
+            let event = FlattenedJson::from_raw(&event);
+            for (ruleset, context) in &users {
+                let _ = ruleset.get_actions(&event, context);
+            }
+        })
+    });
+}
+
+#[cfg(feature = "criterion")]
+fn compile_ruleset(c: &mut Criterion) {
+    let ruleset = Ruleset::server_default(user_id!("@example:localhost"));
+
+    c.bench_function("compile the server-default `Ruleset`", |b| {
+        b.iter(|| {
+            let _ = CompiledRuleset::from(ruleset.clone());
+        })
+    });
+}
+
+#[cfg(feature = "criterion")]
+criterion_group!(benches, ruleset_get_actions, compiled_ruleset_get_actions, compile_ruleset);
+
+#[cfg(feature = "criterion")]
+criterion_main!(benches);
+
+#[cfg(not(feature = "criterion"))]
+fn main() {
+    // To run the benchmarks the "criterion" feature must be enabled use:
+    // `cargo bench --features criterion --bench push_rules`
+    panic!("Enable the criterion feature to run benchmarks");
+}
@@ -14,7 +14,7 @@
 
 use std::{convert::TryInto as _, error::Error as StdError, fmt};
 
-use bytes::BufMut;
+use bytes::{BufMut, BytesMut};
 use tracing::warn;
 
 use crate::UserId;
@@ -78,9 +78,12 @@ use crate::UserId;
 /// struct is converted into an `http::Request`:
 ///
 /// * `#[ruma_api(header = HEADER_NAME)]`: Fields with this attribute will be treated as HTTP
-///   headers on the request. The value must implement `AsRef<str>`. Generally this is a
+///   headers on the request. The value must implement [`header::ToHeaderValue`] and
+///   [`header::FromHeaderValue`], or be an `Option` of such a type. Generally this is a
 ///   `String`. The attribute value shown above as `HEADER_NAME` must be a header name constant
 ///   from `http::header`, e.g. `CONTENT_TYPE`.
+/// * `#[ruma_api(header_map)]`: One field of type `http::HeaderMap` with this attribute will
+///   contain the HTTP headers of the request that are not in another field.
 /// * `#[ruma_api(path)]`: Fields with this attribute will be inserted into the matching path
 ///   component of the request URL.
 /// * `#[ruma_api(query)]`: Fields with this attribute will be inserting into the URL's query
@@ -96,15 +99,18 @@ use crate::UserId;
 ///
 /// Like the request block, the response block consists of normal struct field definitions.
 /// Doc comments and attributes are allowed as normal.
-/// There is also a special attribute available to control how the struct is created from a
+/// There are also special attributes available to control how the struct is created from a
 /// `http::Request`:
 ///
 /// * `#[ruma_api(header = HEADER_NAME)]`: Fields with this attribute will be treated as HTTP
-///   headers on the response. The value must implement `AsRef<str>`. Generally this is a
+///   headers on the response. The value must implement [`header::ToHeaderValue`] and
+///   [`header::FromHeaderValue`], or be an `Option` of such a type. Generally this is a
 ///   `String`. The attribute value shown above as `HEADER_NAME` must be a header name constant
 ///   from `http::header`, e.g. `CONTENT_TYPE`.
+/// * `#[ruma_api(header_map)]`: One field of type `http::HeaderMap` with this attribute will
+///   contain the HTTP headers of the response that are not in another field.
 ///
-/// Any field that does not include the above attribute will be expected in the response's JSON
+/// Any field that does not include the above attributes will be expected in the response's JSON
 /// body.
 ///
 /// ## Newtype bodies
@@ -118,7 +124,8 @@ use crate::UserId;
 ///
 /// There is another kind of newtype body that is enabled with `#[ruma_api(raw_body)]`. It is
 /// used for endpoints in which the request or response body can be arbitrary bytes instead of
-/// a JSON objects. A field with `#[ruma_api(raw_body)]` needs to have the type `Vec<u8>`.
+/// a JSON objects. A field with `#[ruma_api(raw_body)]` needs to have the type `Vec<u8>`, or
+/// [`RawBody`] for a body that can be streamed instead of being held in memory.
 ///
 /// # Examples
 ///
@@ -194,12 +201,40 @@ use crate::UserId;
 pub use ruma_macros::ruma_api;
 
 pub mod error;
+pub mod header;
 mod metadata;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+mod raw_body;
+mod router;
+#[cfg(any(feature = "axum", feature = "hyper"))]
+pub mod server;
 
 pub use metadata::{MatrixVersion, Metadata, VersioningDecision};
+pub use raw_body::RawBody;
+pub use router::Router;
 
 use error::{FromHttpRequestError, FromHttpResponseError, IntoHttpError};
 
+// Used by `ruma_api!` to only generate the schemas of the endpoints with the `openapi` feature of
+// this crate, regardless of the features of the crate that uses the macro.
+#[cfg(feature = "openapi")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __openapi_item {
+    ($($item:tt)*) => { $($item)* };
+}
+
+#[cfg(not(feature = "openapi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __openapi_item {
+    ($($item:tt)*) => {};
+}
+
+#[doc(hidden)]
+pub use crate::__openapi_item;
+
 /// An enum to control whether an access token should be added to outgoing requests
 #[derive(Clone, Copy, Debug)]
 #[allow(clippy::exhaustive_enums)]
@@ -274,6 +309,28 @@ pub trait OutgoingRequest: Sized {
         access_token: SendAccessToken<'_>,
         considering_versions: &'_ [MatrixVersion],
     ) -> Result<http::Request<T>, IntoHttpError>;
+
+    /// Tries to convert this request into an `http::Request` with a [`RawBody`].
+    ///
+    /// A raw body of type [`RawBody`] is used as-is, so it can be a stream, and its length is
+    /// sent in the `Content-Length` header if it is known. The other bodies are serialized in
+    /// memory.
+    ///
+    /// This fails in the same cases as [`try_into_http_request`](Self::try_into_http_request).
+    fn try_into_streaming_http_request(
+        self,
+        base_url: &str,
+        access_token: SendAccessToken<'_>,
+        considering_versions: &'_ [MatrixVersion],
+    ) -> Result<http::Request<RawBody>, IntoHttpError> {
+        let http_request = self.try_into_http_request::<BytesMut>(
+            base_url,
+            access_token,
+            considering_versions,
+        )?;
+
+        Ok(http_request.map(|body| RawBody::from(body.freeze())))
+    }
 }
 
 /// A response type for a Matrix API endpoint, used for receiving responses.
@@ -281,10 +338,32 @@ pub trait IncomingResponse: Sized {
     /// A type capturing the expected error conditions the server can return.
     type EndpointError: EndpointError;
 
+    /// Whether the body of a successful response can be a stream in
+    /// [`try_from_streaming_http_response`](Self::try_from_streaming_http_response).
+    ///
+    /// This is the case of the responses with a raw body of type [`RawBody`].
+    const STREAMING_BODY: bool = false;
+
     /// Tries to convert the given `http::Response` into this response type.
     fn try_from_http_response<T: AsRef<[u8]>>(
         response: http::Response<T>,
     ) -> Result<Self, FromHttpResponseError<Self::EndpointError>>;
+
+    /// Tries to convert the given `http::Response` with a [`RawBody`] into this response type.
+    ///
+    /// The body must be in memory, unless the response is successful and
+    /// [`STREAMING_BODY`](Self::STREAMING_BODY) is `true`, otherwise this fails with
+    /// [`DeserializationError::StreamingBody`](error::DeserializationError::StreamingBody).
+    fn try_from_streaming_http_response(
+        response: http::Response<RawBody>,
+    ) -> Result<Self, FromHttpResponseError<Self::EndpointError>> {
+        let (parts, body) = response.into_parts();
+        let body = body.into_bytes().map_err(|_| {
+            FromHttpResponseError::Deserialization(error::DeserializationError::StreamingBody)
+        })?;
+
+        Self::try_from_http_response(http::Response::from_parts(parts, body))
+    }
 }
 
 /// An extension to [`OutgoingRequest`] which provides Appservice specific methods.
@@ -337,6 +416,12 @@ pub trait IncomingRequest: Sized {
     /// Metadata about the endpoint.
     const METADATA: Metadata;
 
+    /// Whether the body of the request can be a stream in
+    /// [`try_from_streaming_http_request`](Self::try_from_streaming_http_request).
+    ///
+    /// This is the case of the requests with a raw body of type [`RawBody`].
+    const STREAMING_BODY: bool = false;
+
     /// Tries to turn the given `http::Request` into this request type,
     /// together with the corresponding path arguments.
     ///
@@ -348,6 +433,28 @@ pub trait IncomingRequest: Sized {
     where
         B: AsRef<[u8]>,
         S: AsRef<str>;
+
+    /// Tries to turn the given `http::Request` with a [`RawBody`] into this request type,
+    /// together with the corresponding path arguments.
+    ///
+    /// The body must be in memory, unless [`STREAMING_BODY`](Self::STREAMING_BODY) is `true`,
+    /// otherwise this fails with
+    /// [`DeserializationError::StreamingBody`](error::DeserializationError::StreamingBody).
+    ///
+    /// Note: The strings in path_args need to be percent-decoded.
+    fn try_from_streaming_http_request<S>(
+        req: http::Request<RawBody>,
+        path_args: &[S],
+    ) -> Result<Self, FromHttpRequestError>
+    where
+        S: AsRef<str>,
+    {
+        let (parts, body) = req.into_parts();
+        let body =
+            body.into_bytes().map_err(|_| error::DeserializationError::StreamingBody)?;
+
+        Self::try_from_http_request(http::Request::from_parts(parts, body), path_args)
+    }
 }
 
 /// A request type for a Matrix API endpoint, used for sending responses.
@@ -359,6 +466,21 @@ pub trait OutgoingResponse {
     fn try_into_http_response<T: Default + BufMut>(
         self,
     ) -> Result<http::Response<T>, IntoHttpError>;
+
+    /// Tries to convert this response into an `http::Response` with a [`RawBody`].
+    ///
+    /// A raw body of type [`RawBody`] is used as-is, so it can be a stream, and its length is
+    /// sent in the `Content-Length` header if it is known. The other bodies are serialized in
+    /// memory.
+    ///
+    /// This fails in the same cases as [`try_into_http_response`](Self::try_into_http_response).
+    fn try_into_streaming_http_response(self) -> Result<http::Response<RawBody>, IntoHttpError>
+    where
+        Self: Sized,
+    {
+        let http_response = self.try_into_http_response::<BytesMut>()?;
+        Ok(http_response.map(|body| RawBody::from(body.freeze())))
+    }
 }
 
 /// Gives users the ability to define their own serializable / deserializable errors.
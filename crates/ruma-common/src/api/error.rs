@@ -1,32 +1,389 @@
 //! This module contains types for all kinds of errors that can occur when
 //! converting between http requests / responses and ruma's representation of
 //! matrix API requests / responses.
+//!
+//! It also contains [`MatrixError`], the standard error returned by the endpoints of all the
+//! Matrix APIs.
 
-use std::{error::Error as StdError, fmt};
+use std::{collections::BTreeMap, error::Error as StdError, fmt, time::Duration};
 
 use bytes::BufMut;
-use serde_json::{from_slice as from_json_slice, Value as JsonValue};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{
+    from_slice as from_json_slice, from_value as from_json_value, to_value as to_json_value,
+    Value as JsonValue,
+};
 use thiserror::Error;
 
-use super::{EndpointError, MatrixVersion, OutgoingResponse};
+use super::{
+    header::{FromHeaderValue, RetryAfter, ToHeaderValue},
+    EndpointError, MatrixVersion, OutgoingResponse,
+};
+use crate::{serde::JsonObject, PrivOwnedStr, RoomVersionId};
 
-/// A general-purpose Matrix error type consisting of an HTTP status code and a JSON body.
+/// Deserialize and Serialize implementations for ErrorKind.
+/// Separate module because it's a lot of code.
+mod kind_serde;
+mod uiaa;
+
+pub use self::uiaa::{AuthFlow, AuthType, UiaaInfo};
+
+/// An enum for the error kind.
+///
+/// Items may contain additional information.
+///
+/// Error codes that are not known by Ruma are preserved, with the other fields of the error, so
+/// they are serialized back unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// M_FORBIDDEN
+    Forbidden,
+
+    /// M_UNKNOWN_TOKEN
+    UnknownToken {
+        /// If this is `true`, the client can acquire a new access token by specifying the device
+        /// ID it is already using to the login API.
+        ///
+        /// For more information, see [the spec].
+        ///
+        /// [the spec]: https://spec.matrix.org/v1.2/client-server-api/#soft-logout
+        soft_logout: bool,
+    },
+
+    /// M_MISSING_TOKEN
+    MissingToken,
+
+    /// M_BAD_JSON
+    BadJson,
+
+    /// M_NOT_JSON
+    NotJson,
+
+    /// M_NOT_FOUND
+    NotFound,
+
+    /// M_LIMIT_EXCEEDED
+    LimitExceeded {
+        /// How long a client should wait in milliseconds before they can try again.
+        retry_after_ms: Option<Duration>,
+    },
+
+    /// M_UNKNOWN
+    Unknown,
+
+    /// M_UNRECOGNIZED
+    Unrecognized,
+
+    /// M_UNAUTHORIZED
+    Unauthorized,
+
+    /// M_USER_DEACTIVATED
+    UserDeactivated,
+
+    /// M_USER_IN_USE
+    UserInUse,
+
+    /// M_INVALID_USERNAME
+    InvalidUsername,
+
+    /// M_ROOM_IN_USE
+    RoomInUse,
+
+    /// M_INVALID_ROOM_STATE
+    InvalidRoomState,
+
+    /// M_THREEPID_IN_USE
+    ThreepidInUse,
+
+    /// M_THREEPID_NOT_FOUND
+    ThreepidNotFound,
+
+    /// M_THREEPID_AUTH_FAILED
+    ThreepidAuthFailed,
+
+    /// M_THREEPID_DENIED
+    ThreepidDenied,
+
+    /// M_SERVER_NOT_TRUSTED
+    ServerNotTrusted,
+
+    /// M_UNSUPPORTED_ROOM_VERSION
+    UnsupportedRoomVersion,
+
+    /// M_INCOMPATIBLE_ROOM_VERSION
+    IncompatibleRoomVersion {
+        /// The room's version.
+        room_version: RoomVersionId,
+    },
+
+    /// M_BAD_STATE
+    BadState,
+
+    /// M_GUEST_ACCESS_FORBIDDEN
+    GuestAccessForbidden,
+
+    /// M_CAPTCHA_NEEDED
+    CaptchaNeeded,
+
+    /// M_CAPTCHA_INVALID
+    CaptchaInvalid,
+
+    /// M_MISSING_PARAM
+    MissingParam,
+
+    /// M_INVALID_PARAM
+    InvalidParam,
+
+    /// M_TOO_LARGE
+    TooLarge,
+
+    /// M_EXCLUSIVE
+    Exclusive,
+
+    /// M_RESOURCE_LIMIT_EXCEEDED
+    ResourceLimitExceeded {
+        /// A URI giving a contact method for the server administrator.
+        admin_contact: String,
+    },
+
+    /// M_CANNOT_LEAVE_SERVER_NOTICE_ROOM
+    CannotLeaveServerNoticeRoom,
+
+    /// M_WEAK_PASSWORD
+    WeakPassword,
+
+    /// M_MISSING_PARAMS
+    ///
+    /// Used by the identity service API.
+    MissingParams,
+
+    /// M_SESSION_NOT_VALIDATED
+    ///
+    /// Used by the identity service API.
+    SessionNotValidated,
+
+    /// M_NO_VALID_SESSION
+    ///
+    /// Used by the identity service API.
+    NoValidSession,
+
+    /// M_SESSION_EXPIRED
+    ///
+    /// Used by the identity service API.
+    SessionExpired,
+
+    /// M_INVALID_EMAIL
+    ///
+    /// Used by the identity service API.
+    InvalidEmail,
+
+    /// M_EMAIL_SEND_ERROR
+    ///
+    /// Used by the identity service API.
+    EmailSendError,
+
+    /// M_INVALID_ADDRESS
+    ///
+    /// Used by the identity service API.
+    InvalidAddress,
+
+    /// M_SEND_ERROR
+    ///
+    /// Used by the identity service API.
+    SendError,
+
+    /// M_TERMS_NOT_SIGNED
+    ///
+    /// Used by the identity service API.
+    TermsNotSigned,
+
+    /// M_INVALID_PEPPER
+    ///
+    /// Used by the identity service API.
+    InvalidPepper,
+
+    #[doc(hidden)]
+    _Custom { errcode: PrivOwnedStr, extra: Extra },
+}
+
+#[doc(hidden)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extra(BTreeMap<String, JsonValue>);
+
+impl AsRef<str> for ErrorKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Forbidden => "M_FORBIDDEN",
+            Self::UnknownToken { .. } => "M_UNKNOWN_TOKEN",
+            Self::MissingToken => "M_MISSING_TOKEN",
+            Self::BadJson => "M_BAD_JSON",
+            Self::NotJson => "M_NOT_JSON",
+            Self::NotFound => "M_NOT_FOUND",
+            Self::LimitExceeded { .. } => "M_LIMIT_EXCEEDED",
+            Self::Unknown => "M_UNKNOWN",
+            Self::Unrecognized => "M_UNRECOGNIZED",
+            Self::Unauthorized => "M_UNAUTHORIZED",
+            Self::UserDeactivated => "M_USER_DEACTIVATED",
+            Self::UserInUse => "M_USER_IN_USE",
+            Self::InvalidUsername => "M_INVALID_USERNAME",
+            Self::RoomInUse => "M_ROOM_IN_USE",
+            Self::InvalidRoomState => "M_INVALID_ROOM_STATE",
+            Self::ThreepidInUse => "M_THREEPID_IN_USE",
+            Self::ThreepidNotFound => "M_THREEPID_NOT_FOUND",
+            Self::ThreepidAuthFailed => "M_THREEPID_AUTH_FAILED",
+            Self::ThreepidDenied => "M_THREEPID_DENIED",
+            Self::ServerNotTrusted => "M_SERVER_NOT_TRUSTED",
+            Self::UnsupportedRoomVersion => "M_UNSUPPORTED_ROOM_VERSION",
+            Self::IncompatibleRoomVersion { .. } => "M_INCOMPATIBLE_ROOM_VERSION",
+            Self::BadState => "M_BAD_STATE",
+            Self::GuestAccessForbidden => "M_GUEST_ACCESS_FORBIDDEN",
+            Self::CaptchaNeeded => "M_CAPTCHA_NEEDED",
+            Self::CaptchaInvalid => "M_CAPTCHA_INVALID",
+            Self::MissingParam => "M_MISSING_PARAM",
+            Self::InvalidParam => "M_INVALID_PARAM",
+            Self::TooLarge => "M_TOO_LARGE",
+            Self::Exclusive => "M_EXCLUSIVE",
+            Self::ResourceLimitExceeded { .. } => "M_RESOURCE_LIMIT_EXCEEDED",
+            Self::CannotLeaveServerNoticeRoom => "M_CANNOT_LEAVE_SERVER_NOTICE_ROOM",
+            Self::WeakPassword => "M_WEAK_PASSWORD",
+            Self::MissingParams => "M_MISSING_PARAMS",
+            Self::SessionNotValidated => "M_SESSION_NOT_VALIDATED",
+            Self::NoValidSession => "M_NO_VALID_SESSION",
+            Self::SessionExpired => "M_SESSION_EXPIRED",
+            Self::InvalidEmail => "M_INVALID_EMAIL",
+            Self::EmailSendError => "M_EMAIL_SEND_ERROR",
+            Self::InvalidAddress => "M_INVALID_ADDRESS",
+            Self::SendError => "M_SEND_ERROR",
+            Self::TermsNotSigned => "M_TERMS_NOT_SIGNED",
+            Self::InvalidPepper => "M_INVALID_PEPPER",
+            Self::_Custom { errcode, .. } => &errcode.0,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+/// A Matrix error without a status code.
+#[derive(Debug, Clone)]
+#[allow(clippy::exhaustive_structs)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct ErrorBody {
+    /// A value which can be used to handle an error message.
+    pub kind: ErrorKind,
+
+    /// A human-readable error message, usually a sentence explaining what went wrong.
+    ///
+    /// It is empty if the error doesn't have one.
+    pub message: String,
+
+    /// The other fields of the error, that are not part of its kind.
+    ///
+    /// They are preserved so the error can be serialized back unchanged.
+    pub extra: JsonObject,
+}
+
+impl ErrorBody {
+    /// Creates a new `ErrorBody` with the given kind and message, without other fields.
+    pub fn new(kind: ErrorKind, message: String) -> Self {
+        Self { kind, message, extra: JsonObject::new() }
+    }
+
+    /// Convert the ErrorBody into a MatrixError by adding the http status code.
+    pub fn into_error(self, status_code: http::StatusCode) -> MatrixError {
+        MatrixError { kind: self.kind, message: self.message, extra: self.extra, status_code }
+    }
+}
+
+impl From<MatrixError> for ErrorBody {
+    fn from(error: MatrixError) -> Self {
+        Self { kind: error.kind, message: error.message, extra: error.extra }
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorBody {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut extra = JsonObject::deserialize(deserializer)?;
+
+        let message = extra
+            .remove("error")
+            .map(from_json_value)
+            .transpose()
+            .map_err(de::Error::custom)?
+            .unwrap_or_default();
+        let kind: ErrorKind =
+            from_json_value(JsonValue::Object(extra.clone())).map_err(de::Error::custom)?;
+
+        // The fields that are serialized with the kind are not kept twice.
+        if let JsonValue::Object(kind_fields) = to_json_value(&kind).map_err(de::Error::custom)? {
+            extra.retain(|name, _| !kind_fields.contains_key(name));
+        }
+
+        Ok(Self { kind, message, extra })
+    }
+}
+
+impl Serialize for ErrorBody {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct SerErrorBody<'a> {
+            #[serde(flatten)]
+            kind: &'a ErrorKind,
+            #[serde(rename = "error")]
+            message: &'a str,
+            #[serde(flatten)]
+            extra: &'a JsonObject,
+        }
+
+        SerErrorBody { kind: &self.kind, message: &self.message, extra: &self.extra }
+            .serialize(serializer)
+    }
+}
+
+/// A Matrix error, as returned by the endpoints of all the Matrix APIs.
 ///
-/// Note that individual `ruma-*-api` crates may provide more specific error types.
+/// This is the default error type of the endpoints defined with `ruma_api!`.
+#[derive(Debug, Clone)]
 #[allow(clippy::exhaustive_structs)]
-#[derive(Clone, Debug)]
 pub struct MatrixError {
-    /// The http response's status code.
+    /// A value which can be used to handle an error message.
+    pub kind: ErrorKind,
+
+    /// A human-readable error message, usually a sentence explaining what went wrong.
+    ///
+    /// It is empty if the error doesn't have one.
+    pub message: String,
+
+    /// The other fields of the error, that are not part of its kind.
+    ///
+    /// They are preserved so the error can be serialized back unchanged.
+    pub extra: JsonObject,
+
+    /// The http status code.
     pub status_code: http::StatusCode,
+}
 
-    /// The http response's body.
-    pub body: JsonValue,
+impl MatrixError {
+    /// Creates a new `MatrixError` with the given status code, kind and message, without other
+    /// fields.
+    pub fn new(status_code: http::StatusCode, kind: ErrorKind, message: String) -> Self {
+        Self { kind, message, extra: JsonObject::new(), status_code }
+    }
 }
 
 impl fmt::Display for MatrixError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[{}] ", self.status_code.as_u16())?;
-        fmt::Display::fmt(&self.body, f)
+        write!(f, "[{} / {}] {}", self.status_code.as_u16(), self.kind, self.message)
     }
 }
 
@@ -36,11 +393,17 @@ impl OutgoingResponse for MatrixError {
     fn try_into_http_response<T: Default + BufMut>(
         self,
     ) -> Result<http::Response<T>, IntoHttpError> {
-        http::Response::builder()
+        let mut builder = http::Response::builder()
             .header(http::header::CONTENT_TYPE, "application/json")
-            .status(self.status_code)
-            .body(crate::serde::json_to_buf(&self.body)?)
-            .map_err(Into::into)
+            .status(self.status_code);
+
+        // Rate-limited requests also get the delay in the `Retry-After` header.
+        if let ErrorKind::LimitExceeded { retry_after_ms: Some(duration) } = &self.kind {
+            builder =
+                builder.header(http::header::RETRY_AFTER, RetryAfter(*duration).to_header_value()?);
+        }
+
+        builder.body(crate::serde::json_to_buf(&ErrorBody::from(self))?).map_err(Into::into)
     }
 }
 
@@ -48,10 +411,21 @@ impl EndpointError for MatrixError {
     fn try_from_http_response<T: AsRef<[u8]>>(
         response: http::Response<T>,
     ) -> Result<Self, DeserializationError> {
-        Ok(Self {
-            status_code: response.status(),
-            body: from_json_slice(response.body().as_ref())?,
-        })
+        let status = response.status();
+        let mut error_body: ErrorBody = from_json_slice(response.body().as_ref())?;
+
+        // The `Retry-After` header is used when the body doesn't contain the delay.
+        if let ErrorKind::LimitExceeded { retry_after_ms: retry_after_ms @ None } =
+            &mut error_body.kind
+        {
+            *retry_after_ms = response
+                .headers()
+                .get(http::header::RETRY_AFTER)
+                .and_then(|value| RetryAfter::from_header_value(value).ok())
+                .map(|RetryAfter(duration)| duration);
+        }
+
+        Ok(error_body.into_error(status))
     }
 }
 
@@ -94,6 +468,12 @@ pub enum IntoHttpError {
     /// HTTP request construction failed.
     #[error("HTTP request construction failed: {0}")]
     Http(#[from] http::Error),
+
+    /// The raw body is a stream, so it can't be written to a buffer.
+    ///
+    /// The `streaming` methods of the endpoint traits must be used with such a body.
+    #[error("the raw body is a stream and can't be written to a buffer")]
+    StreamingBody,
 }
 
 /// An error when converting a http request to one of ruma's endpoint-specific request types.
@@ -123,6 +503,23 @@ where
     }
 }
 
+/// An error when routing a http request with a [`Router`](super::Router).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RouteError {
+    /// No endpoint has the path of the request.
+    #[error("no endpoint matches the request path")]
+    NotFound,
+
+    /// Endpoints have the path of the request, but with another HTTP method.
+    #[error("no endpoint matches the request method for this path")]
+    MethodNotAllowed,
+
+    /// The request could not be converted to the request type of the endpoint.
+    #[error(transparent)]
+    Request(FromHttpRequestError),
+}
+
 /// An error when converting a http response to one of Ruma's endpoint-specific response types.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -253,6 +650,10 @@ pub enum DeserializationError {
     /// Header value deserialization failed.
     #[error(transparent)]
     Header(#[from] HeaderDeserializationError),
+
+    /// The body is a stream, but it must be in memory to be deserialized.
+    #[error("the body is a stream, but it must be in memory to be deserialized")]
+    StreamingBody,
 }
 
 impl From<std::convert::Infallible> for DeserializationError {
@@ -278,6 +679,10 @@ pub enum HeaderDeserializationError {
     /// The given required header is missing.
     #[error("missing header `{0}`")]
     MissingHeader(String),
+
+    /// The value of a header could not be parsed to the type of its field.
+    #[error("invalid header value: {0}")]
+    InvalidValue(Box<dyn StdError + Send + Sync>),
 }
 
 /// An error that happens when Ruma cannot understand a Matrix version.
@@ -292,3 +697,128 @@ impl fmt::Display for UnknownVersionError {
 }
 
 impl StdError for UnknownVersionError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
+
+    use super::{ErrorBody, ErrorKind, MatrixError};
+    use crate::api::{EndpointError, OutgoingResponse};
+
+    #[test]
+    fn deserialize_forbidden() {
+        let deserialized: ErrorBody = from_json_value(json!({
+            "errcode": "M_FORBIDDEN",
+            "error": "You are not authorized to ban users in this room.",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            deserialized,
+            ErrorBody::new(
+                ErrorKind::Forbidden,
+                "You are not authorized to ban users in this room.".into(),
+            )
+        );
+    }
+
+    #[test]
+    fn deserialize_without_message() {
+        let response = http::Response::builder()
+            .status(http::StatusCode::NOT_FOUND)
+            .body(br#"{ "errcode": "M_NOT_FOUND" }"#)
+            .unwrap();
+        let error = MatrixError::try_from_http_response(response).unwrap();
+
+        assert_eq!(error.kind, ErrorKind::NotFound);
+        assert_eq!(error.message, "");
+    }
+
+    #[test]
+    fn known_errcode_extra_fields_roundtrip() {
+        let json = json!({
+            "errcode": "M_UNKNOWN_TOKEN",
+            "error": "Access token has expired",
+            "soft_logout": true,
+            "org.example.expired_at": 1_432_735_824_653_u64,
+        });
+        let deserialized: ErrorBody = from_json_value(json.clone()).unwrap();
+
+        assert_eq!(deserialized.kind, ErrorKind::UnknownToken { soft_logout: true });
+        assert_eq!(deserialized.extra.len(), 1);
+        assert_eq!(deserialized.extra["org.example.expired_at"], 1_432_735_824_653_u64);
+        assert_eq!(to_json_value(&deserialized).unwrap(), json);
+    }
+
+    #[test]
+    fn custom_errcode_extra_fields_not_duplicated() {
+        let deserialized: ErrorBody = from_json_value(json!({
+            "errcode": "ORG_EXAMPLE_CUSTOM",
+            "error": "Custom error",
+            "org.example.detail": 3,
+        }))
+        .unwrap();
+
+        assert_eq!(deserialized.kind.as_ref(), "ORG_EXAMPLE_CUSTOM");
+        assert!(deserialized.extra.is_empty());
+    }
+
+    #[test]
+    fn identity_service_errcode() {
+        let deserialized: ErrorBody = from_json_value(json!({
+            "errcode": "M_SESSION_NOT_VALIDATED",
+            "error": "This validation session has not yet been completed",
+        }))
+        .unwrap();
+
+        assert_eq!(deserialized.kind, ErrorKind::SessionNotValidated);
+        assert_eq!(
+            to_json_value(ErrorBody::new(ErrorKind::TermsNotSigned, String::new())).unwrap(),
+            json!({ "errcode": "M_TERMS_NOT_SIGNED", "error": "" })
+        );
+    }
+
+    #[test]
+    fn limit_exceeded_retry_after_header() {
+        let response = http::Response::builder()
+            .status(http::StatusCode::TOO_MANY_REQUESTS)
+            .header(http::header::RETRY_AFTER, "3")
+            .body(br#"{ "errcode": "M_LIMIT_EXCEEDED", "error": "Too many requests" }"#)
+            .unwrap();
+        let error = MatrixError::try_from_http_response(response).unwrap();
+
+        assert_eq!(
+            error.kind,
+            ErrorKind::LimitExceeded { retry_after_ms: Some(Duration::from_secs(3)) }
+        );
+    }
+
+    #[test]
+    fn limit_exceeded_retry_after_body_takes_precedence() {
+        let response = http::Response::builder()
+            .status(http::StatusCode::TOO_MANY_REQUESTS)
+            .header(http::header::RETRY_AFTER, "3")
+            .body(br#"{ "errcode": "M_LIMIT_EXCEEDED", "error": "Slow down", "retry_after_ms": 1500 }"#)
+            .unwrap();
+        let error = MatrixError::try_from_http_response(response).unwrap();
+
+        assert_eq!(
+            error.kind,
+            ErrorKind::LimitExceeded { retry_after_ms: Some(Duration::from_millis(1500)) }
+        );
+    }
+
+    #[test]
+    fn serialize_limit_exceeded_retry_after_header() {
+        let error = MatrixError::new(
+            http::StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::LimitExceeded { retry_after_ms: Some(Duration::from_millis(1500)) },
+            "Too many requests".to_owned(),
+        );
+        let response = error.try_into_http_response::<Vec<u8>>().unwrap();
+
+        assert_eq!(response.headers().get(http::header::RETRY_AFTER).unwrap(), "2");
+    }
+}
@@ -6,7 +6,6 @@ use std::{
 };
 
 use js_int::UInt;
-use ruma_common::serde::{DeserializeFromCowStr, FromString};
 use serde::{
     de::{self, Deserialize, Deserializer, MapAccess, Visitor},
     ser::{self, Serialize, SerializeMap, Serializer},
@@ -14,7 +13,10 @@ use serde::{
 use serde_json::from_value as from_json_value;
 
 use super::{ErrorKind, Extra};
-use crate::PrivOwnedStr;
+use crate::{
+    serde::{DeserializeFromCowStr, FromString},
+    PrivOwnedStr,
+};
 
 enum Field<'de> {
     ErrCode,
@@ -206,6 +208,16 @@ impl<'de> Visitor<'de> for ErrorKindVisitor {
             },
             ErrCode::CannotLeaveServerNoticeRoom => ErrorKind::CannotLeaveServerNoticeRoom,
             ErrCode::WeakPassword => ErrorKind::WeakPassword,
+            ErrCode::MissingParams => ErrorKind::MissingParams,
+            ErrCode::SessionNotValidated => ErrorKind::SessionNotValidated,
+            ErrCode::NoValidSession => ErrorKind::NoValidSession,
+            ErrCode::SessionExpired => ErrorKind::SessionExpired,
+            ErrCode::InvalidEmail => ErrorKind::InvalidEmail,
+            ErrCode::EmailSendError => ErrorKind::EmailSendError,
+            ErrCode::InvalidAddress => ErrorKind::InvalidAddress,
+            ErrCode::SendError => ErrorKind::SendError,
+            ErrCode::TermsNotSigned => ErrorKind::TermsNotSigned,
+            ErrCode::InvalidPepper => ErrorKind::InvalidPepper,
             ErrCode::_Custom(errcode) => ErrorKind::_Custom { errcode, extra },
         })
     }
@@ -247,6 +259,16 @@ enum ErrCode {
     ResourceLimitExceeded,
     CannotLeaveServerNoticeRoom,
     WeakPassword,
+    MissingParams,
+    SessionNotValidated,
+    NoValidSession,
+    SessionExpired,
+    InvalidEmail,
+    EmailSendError,
+    InvalidAddress,
+    SendError,
+    TermsNotSigned,
+    InvalidPepper,
     _Custom(PrivOwnedStr),
 }
 
@@ -295,10 +317,10 @@ impl Serialize for ErrorKind {
 
 #[cfg(test)]
 mod tests {
-    use ruma_common::room_version_id;
-    use serde_json::{from_value as from_json_value, json};
+    use serde_json::{from_value as from_json_value, json, to_value as to_json_value};
 
     use super::ErrorKind;
+    use crate::room_version_id;
 
     #[test]
     fn deserialize_forbidden() {
@@ -330,4 +352,16 @@ mod tests {
             ErrorKind::IncompatibleRoomVersion { room_version: room_version_id!("7") }
         );
     }
+
+    #[test]
+    fn custom_errcode_roundtrip() {
+        let json = json!({
+            "errcode": "ORG_EXAMPLE_CUSTOM",
+            "org.example.detail": { "count": 3 },
+        });
+        let deserialized: ErrorKind = from_json_value(json.clone()).unwrap();
+
+        assert_eq!(deserialized.as_ref(), "ORG_EXAMPLE_CUSTOM");
+        assert_eq!(to_json_value(&deserialized).unwrap(), json);
+    }
 }
@@ -0,0 +1,98 @@
+//! Types of the [User-Interactive Authentication API][uiaa] that are part of the errors of the
+//! endpoints using it.
+//!
+//! [uiaa]: https://spec.matrix.org/v1.2/client-server-api/#user-interactive-authentication-api
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue as RawJsonValue;
+
+use super::ErrorBody;
+use crate::{serde::StringEnum, PrivOwnedStr};
+
+/// The type of an authentication stage.
+#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/doc/string_enum.md"))]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, StringEnum)]
+#[non_exhaustive]
+pub enum AuthType {
+    /// Password-based authentication (`m.login.password`).
+    #[ruma_enum(rename = "m.login.password")]
+    Password,
+
+    /// Google ReCaptcha 2.0 authentication (`m.login.recaptcha`).
+    #[ruma_enum(rename = "m.login.recaptcha")]
+    ReCaptcha,
+
+    /// Email-based authentication (`m.login.email.identity`).
+    #[ruma_enum(rename = "m.login.email.identity")]
+    EmailIdentity,
+
+    /// Phone number-based authentication (`m.login.msisdn`).
+    #[ruma_enum(rename = "m.login.msisdn")]
+    Msisdn,
+
+    /// SSO-based authentication (`m.login.sso`).
+    #[ruma_enum(rename = "m.login.sso")]
+    Sso,
+
+    /// Dummy authentication (`m.login.dummy`).
+    #[ruma_enum(rename = "m.login.dummy")]
+    Dummy,
+
+    /// Registration token-based authentication (`m.login.registration_token`).
+    #[ruma_enum(rename = "m.login.registration_token")]
+    RegistrationToken,
+
+    #[doc(hidden)]
+    _Custom(PrivOwnedStr),
+}
+
+/// Information about available authentication flows and status for User-Interactive Authenticiation
+/// API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct UiaaInfo {
+    /// List of authentication flows available for this endpoint.
+    pub flows: Vec<AuthFlow>,
+
+    /// List of stages in the current flow completed by the client.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed: Vec<AuthType>,
+
+    /// Authentication parameters required for the client to complete authentication.
+    ///
+    /// To create a `Box<RawJsonValue>`, use `serde_json::value::to_raw_value`.
+    pub params: Box<RawJsonValue>,
+
+    /// Session key for client to use to complete authentication.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+
+    /// Authentication-related errors for previous request returned by homeserver.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub auth_error: Option<ErrorBody>,
+}
+
+impl UiaaInfo {
+    /// Creates a new `UiaaInfo` with the given flows and parameters.
+    pub fn new(flows: Vec<AuthFlow>, params: Box<RawJsonValue>) -> Self {
+        Self { flows, completed: Vec::new(), params, session: None, auth_error: None }
+    }
+}
+
+/// Description of steps required to authenticate via the User-Interactive Authentication API.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct AuthFlow {
+    /// Ordered list of stages required to complete authentication.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<AuthType>,
+}
+
+impl AuthFlow {
+    /// Creates a new `AuthFlow` with the given stages.
+    ///
+    /// To create an empty `AuthFlow`, use `AuthFlow::default()`.
+    pub fn new(stages: Vec<AuthType>) -> Self {
+        Self { stages }
+    }
+}
@@ -0,0 +1,147 @@
+//! Conversions between the values of HTTP headers and the types of the header fields of requests
+//! and responses.
+//!
+//! The fields with the `#[ruma_api(header = NAME)]` attribute can have any type that implements
+//! [`ToHeaderValue`] and [`FromHeaderValue`], or an `Option` of such a type for optional headers.
+//!
+//! The field with the `#[ruma_api(header_map)]` attribute, of type [`HeaderMap`], contains the
+//! headers that are not mapped to another field.
+//!
+//! [`HeaderMap`]: http::HeaderMap
+
+use std::time::Duration;
+
+use http::header::HeaderValue;
+use js_int::UInt;
+
+use super::error::{HeaderDeserializationError, IntoHttpError};
+
+/// A type that can be converted to the value of an HTTP header.
+pub trait ToHeaderValue {
+    /// Converts this value to a `HeaderValue`.
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError>;
+}
+
+/// A type that can be parsed from the value of an HTTP header.
+pub trait FromHeaderValue: Sized {
+    /// Parses the given `HeaderValue`.
+    fn from_header_value(value: &HeaderValue) -> Result<Self, HeaderDeserializationError>;
+}
+
+impl<T: ToHeaderValue + ?Sized> ToHeaderValue for &T {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        (*self).to_header_value()
+    }
+}
+
+impl ToHeaderValue for str {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        Ok(HeaderValue::from_str(self)?)
+    }
+}
+
+impl ToHeaderValue for String {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        self.as_str().to_header_value()
+    }
+}
+
+impl FromHeaderValue for String {
+    fn from_header_value(value: &HeaderValue) -> Result<Self, HeaderDeserializationError> {
+        Ok(value.to_str().map_err(HeaderDeserializationError::ToStrError)?.to_owned())
+    }
+}
+
+impl ToHeaderValue for HeaderValue {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        Ok(self.clone())
+    }
+}
+
+impl FromHeaderValue for HeaderValue {
+    fn from_header_value(value: &HeaderValue) -> Result<Self, HeaderDeserializationError> {
+        Ok(value.clone())
+    }
+}
+
+impl ToHeaderValue for UInt {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        Ok(HeaderValue::from(u64::from(*self)))
+    }
+}
+
+impl FromHeaderValue for UInt {
+    fn from_header_value(value: &HeaderValue) -> Result<Self, HeaderDeserializationError> {
+        let s = value.to_str().map_err(HeaderDeserializationError::ToStrError)?;
+        s.parse().map_err(|e| HeaderDeserializationError::InvalidValue(Box::new(e)))
+    }
+}
+
+/// The value of the `Retry-After` header, the time a client should wait before retrying a
+/// request.
+///
+/// Only the delay in seconds form of the header is supported, which is the one used by Matrix
+/// homeservers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct RetryAfter(pub Duration);
+
+impl ToHeaderValue for RetryAfter {
+    fn to_header_value(&self) -> Result<HeaderValue, IntoHttpError> {
+        // The header only supports whole seconds, round up to not retry too early.
+        let mut secs = self.0.as_secs();
+        if self.0.subsec_nanos() > 0 {
+            secs += 1;
+        }
+
+        Ok(HeaderValue::from(secs))
+    }
+}
+
+impl FromHeaderValue for RetryAfter {
+    fn from_header_value(value: &HeaderValue) -> Result<Self, HeaderDeserializationError> {
+        let secs = UInt::from_header_value(value)?;
+        Ok(Self(Duration::from_secs(secs.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::header::HeaderValue;
+    use js_int::uint;
+
+    use super::{FromHeaderValue, RetryAfter, ToHeaderValue};
+
+    #[test]
+    fn string_header_value() {
+        let value = "text/plain".to_header_value().unwrap();
+        assert_eq!(value, "text/plain");
+        assert_eq!(String::from_header_value(&value).unwrap(), "text/plain");
+
+        assert!("invalid\nvalue".to_header_value().is_err());
+    }
+
+    #[test]
+    fn uint_header_value() {
+        let value = uint!(1024).to_header_value().unwrap();
+        assert_eq!(value, "1024");
+        assert_eq!(js_int::UInt::from_header_value(&value).unwrap(), uint!(1024));
+
+        assert!(js_int::UInt::from_header_value(&HeaderValue::from_static("-1")).is_err());
+    }
+
+    #[test]
+    fn retry_after_header_value() {
+        let value = RetryAfter(Duration::from_millis(1500)).to_header_value().unwrap();
+        assert_eq!(value, "2");
+        assert_eq!(
+            RetryAfter::from_header_value(&value).unwrap(),
+            RetryAfter(Duration::from_secs(2))
+        );
+
+        let date = HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert!(RetryAfter::from_header_value(&date).is_err());
+    }
+}
@@ -0,0 +1,567 @@
+//! Generation of [OpenAPI] documents from the endpoints defined with [`ruma_api!`].
+//!
+//! With the `openapi` feature, the `ruma_api!` macro generates an `openapi_schema()` function next
+//! to the `METADATA` of each endpoint, that returns its [`EndpointSchema`]. The schemas of the
+//! endpoints are then added to an [`OpenApi`] to generate the OpenAPI document describing them for
+//! a set of Matrix versions.
+//!
+//! ```
+//! # mod get_thing {
+//! #     ruma_common::api::ruma_api! {
+//! #         metadata: {
+//! #             description: "Get a thing.",
+//! #             method: GET,
+//! #             name: "get_thing",
+//! #             stable_path: "/_matrix/client/v3/things/:thing_id",
+//! #             rate_limited: false,
+//! #             authentication: AccessToken,
+//! #             added: 1.1,
+//! #         }
+//! #         request: {
+//! #             #[ruma_api(path)]
+//! #             pub thing_id: String,
+//! #         }
+//! #         response: {}
+//! #     }
+//! # }
+//! use ruma_common::api::{openapi::OpenApi, MatrixVersion};
+//!
+//! let document = OpenApi::new("My homeserver", "1.0.0")
+//!     .with_endpoint(get_thing::openapi_schema())
+//!     .to_json(&[MatrixVersion::V1_2]);
+//!
+//! assert!(document["paths"]["/_matrix/client/v3/things/{thing_id}"]["get"].is_object());
+//! ```
+//!
+//! The types of the fields are derived from the names of their Rust types, so types that are not
+//! recognized, like enums and structs, are described as any JSON value.
+//!
+//! [OpenAPI]: https://spec.openapis.org/oas/v3.0.3
+//! [`ruma_api!`]: super::ruma_api
+
+use http::HeaderName;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+use super::{AuthScheme, MatrixVersion, Metadata, VersioningDecision};
+
+/// The schema of an endpoint.
+#[derive(Clone, Debug)]
+#[allow(clippy::exhaustive_structs)]
+pub struct EndpointSchema {
+    /// The metadata of the endpoint.
+    pub metadata: Metadata,
+
+    /// The fields of the request.
+    pub request: Vec<FieldSchema>,
+
+    /// The fields of the response.
+    pub response: Vec<FieldSchema>,
+}
+
+/// The schema of a field of a request or a response.
+#[derive(Clone, Debug)]
+#[allow(clippy::exhaustive_structs)]
+pub struct FieldSchema {
+    /// The name of the field in the HTTP request or response.
+    pub name: &'static str,
+
+    /// Where the field is in the HTTP request or response.
+    pub location: FieldLocation,
+
+    /// The type of the JSON value of the field.
+    pub ty: FieldType,
+
+    /// Whether the field is required.
+    pub required: bool,
+
+    /// The documentation of the field.
+    pub description: &'static str,
+}
+
+/// Where a field is in an HTTP request or response.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum FieldLocation {
+    /// A field of the JSON object in the body.
+    Body,
+
+    /// A field whose fields are flattened into the JSON object in the body.
+    ///
+    /// These fields are described as additional properties of the body.
+    FlattenedBody,
+
+    /// The whole JSON body.
+    NewtypeBody,
+
+    /// The whole body, as arbitrary bytes.
+    RawBody,
+
+    /// A path parameter.
+    Path,
+
+    /// A query parameter.
+    Query,
+
+    /// All the query parameters, as a map.
+    QueryMap,
+
+    /// The header with the given name.
+    Header(HeaderName),
+
+    /// The headers that are not in another field, as a map.
+    ///
+    /// These fields are not included in OpenAPI documents.
+    HeaderMap,
+}
+
+/// The type of the JSON value of a field.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum FieldType {
+    /// A string.
+    String,
+
+    /// An integer.
+    Integer,
+
+    /// A floating-point number.
+    Number,
+
+    /// A boolean.
+    Boolean,
+
+    /// An array with items of the given type.
+    Array(&'static FieldType),
+
+    /// A JSON object.
+    Object,
+
+    /// Any JSON value.
+    Any,
+}
+
+impl FieldType {
+    /// The JSON schema of this type.
+    pub fn to_json_schema(self) -> JsonValue {
+        match self {
+            Self::String => json!({ "type": "string" }),
+            Self::Integer => json!({ "type": "integer" }),
+            Self::Number => json!({ "type": "number" }),
+            Self::Boolean => json!({ "type": "boolean" }),
+            Self::Array(items) => json!({ "type": "array", "items": items.to_json_schema() }),
+            Self::Object => json!({ "type": "object" }),
+            Self::Any => json!({}),
+        }
+    }
+}
+
+/// A builder for an OpenAPI 3 document describing a set of endpoints.
+#[derive(Clone, Debug)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    endpoints: Vec<EndpointSchema>,
+}
+
+impl OpenApi {
+    /// Creates a new `OpenApi` without any endpoint, with the given title and version of the
+    /// document.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self { title: title.into(), version: version.into(), endpoints: Vec::new() }
+    }
+
+    /// Adds the endpoint with the given schema to the document.
+    pub fn with_endpoint(mut self, endpoint: EndpointSchema) -> Self {
+        self.endpoints.push(endpoint);
+        self
+    }
+
+    /// Generates the OpenAPI document, as JSON, for a server supporting the given Matrix versions.
+    ///
+    /// The paths of the endpoints are selected like in
+    /// [`OutgoingRequest::try_into_http_request`](super::OutgoingRequest::try_into_http_request).
+    /// The endpoints that are removed in all the versions, or that are unstable and don't have an
+    /// unstable path, are not part of the document.
+    pub fn to_json(&self, versions: &[MatrixVersion]) -> JsonValue {
+        let mut paths = JsonMap::new();
+
+        for endpoint in &self.endpoints {
+            let metadata = &endpoint.metadata;
+            let (path, deprecated) = match endpoint_path(metadata, versions) {
+                Some(path) => path,
+                None => continue,
+            };
+
+            let operation = operation(endpoint, deprecated);
+            let path_item = paths
+                .entry(openapi_path(path))
+                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+            path_item[metadata.method.as_str().to_lowercase()] = operation;
+        }
+
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": self.title,
+                "version": self.version,
+            },
+            "paths": paths,
+            "components": {
+                "securitySchemes": {
+                    "accessToken": {
+                        "type": "http",
+                        "scheme": "bearer",
+                    },
+                    "accessTokenQuery": {
+                        "type": "apiKey",
+                        "in": "query",
+                        "name": "access_token",
+                    },
+                    "serverSignatures": {
+                        "type": "apiKey",
+                        "in": "header",
+                        "name": "Authorization",
+                    },
+                },
+            },
+        })
+    }
+}
+
+/// The path of the endpoint for the given versions, and whether it is deprecated in all of them.
+fn endpoint_path(metadata: &Metadata, versions: &[MatrixVersion]) -> Option<(&'static str, bool)> {
+    match metadata.versioning_decision_for(versions) {
+        VersioningDecision::Removed => None,
+        VersioningDecision::Stable { all_deprecated, .. } => {
+            let path = match metadata.r0_path {
+                Some(r0) if versions.iter().all(|&v| v == MatrixVersion::V1_0) => r0,
+                _ => metadata.stable_path?,
+            };
+            Some((path, all_deprecated))
+        }
+        VersioningDecision::Unstable => Some((metadata.unstable_path?, false)),
+    }
+}
+
+/// Converts a path with `:name` parameters to a path with `{name}` parameters.
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn operation(endpoint: &EndpointSchema, deprecated: bool) -> JsonValue {
+    let metadata = &endpoint.metadata;
+
+    let mut operation = json!({
+        "operationId": metadata.name,
+        "summary": metadata.description,
+        "parameters": parameters(&endpoint.request),
+        "responses": {
+            "200": response(&endpoint.response),
+        },
+        "x-ruma-rate-limited": metadata.rate_limited,
+    });
+
+    if deprecated {
+        operation["deprecated"] = true.into();
+    }
+
+    if let Some(added) = metadata.added {
+        operation["x-ruma-added"] = added.to_string().into();
+    }
+
+    if let Some(deprecated) = metadata.deprecated {
+        operation["x-ruma-deprecated"] = deprecated.to_string().into();
+    }
+
+    if let Some(removed) = metadata.removed {
+        operation["x-ruma-removed"] = removed.to_string().into();
+    }
+
+    let security = match metadata.authentication {
+        AuthScheme::None => None,
+        AuthScheme::AccessToken => Some(json!([{ "accessToken": [] }, { "accessTokenQuery": [] }])),
+        AuthScheme::QueryOnlyAccessToken => Some(json!([{ "accessTokenQuery": [] }])),
+        AuthScheme::ServerSignatures => Some(json!([{ "serverSignatures": [] }])),
+    };
+    if let Some(security) = security {
+        operation["security"] = security;
+    }
+
+    if let Some(body) = body(&endpoint.request) {
+        operation["requestBody"] = json!({ "required": true, "content": body });
+    }
+
+    operation
+}
+
+fn parameters(fields: &[FieldSchema]) -> JsonValue {
+    fields
+        .iter()
+        .filter_map(|field| {
+            let (location, name) = match &field.location {
+                FieldLocation::Path => ("path", field.name),
+                FieldLocation::Query => ("query", field.name),
+                FieldLocation::Header(name) => ("header", name.as_str()),
+                FieldLocation::QueryMap => {
+                    return Some(json!({
+                        "name": field.name,
+                        "in": "query",
+                        "description": field.description,
+                        "schema": {
+                            "type": "object",
+                            "additionalProperties": { "type": "string" },
+                        },
+                        "style": "form",
+                        "explode": true,
+                    }));
+                }
+                _ => return None,
+            };
+
+            Some(json!({
+                "name": name,
+                "in": location,
+                "description": field.description,
+                "required": field.required || location == "path",
+                "schema": field.ty.to_json_schema(),
+            }))
+        })
+        .collect()
+}
+
+fn response(fields: &[FieldSchema]) -> JsonValue {
+    let mut response = json!({ "description": "The request was successful." });
+
+    let headers: JsonMap<_, _> = fields
+        .iter()
+        .filter_map(|field| match &field.location {
+            FieldLocation::Header(name) => Some((
+                name.to_string(),
+                json!({
+                    "description": field.description,
+                    "required": field.required,
+                    "schema": field.ty.to_json_schema(),
+                }),
+            )),
+            _ => None,
+        })
+        .collect();
+    if !headers.is_empty() {
+        response["headers"] = headers.into();
+    }
+
+    if let Some(body) = body(fields) {
+        response["content"] = body;
+    }
+
+    response
+}
+
+/// The content of the body with the given fields, if any.
+fn body(fields: &[FieldSchema]) -> Option<JsonValue> {
+    let mut properties = JsonMap::new();
+    let mut required = Vec::new();
+    let mut additional_properties = None;
+
+    for field in fields {
+        match field.location {
+            FieldLocation::RawBody => {
+                return Some(json!({
+                    "application/octet-stream": {
+                        "schema": { "type": "string", "format": "binary" },
+                    },
+                }));
+            }
+            FieldLocation::NewtypeBody => {
+                return Some(json!({
+                    "application/json": { "schema": field.ty.to_json_schema() },
+                }));
+            }
+            FieldLocation::Body => {
+                let mut schema = field.ty.to_json_schema();
+                if !field.description.is_empty() {
+                    schema["description"] = field.description.into();
+                }
+
+                properties.insert(field.name.to_owned(), schema);
+                if field.required {
+                    required.push(field.name);
+                }
+            }
+            FieldLocation::FlattenedBody => {
+                additional_properties = Some(field.ty.to_json_schema());
+            }
+            _ => {}
+        }
+    }
+
+    if properties.is_empty() && additional_properties.is_none() {
+        return None;
+    }
+
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = required.into();
+    }
+    if let Some(additional_properties) = additional_properties {
+        schema["additionalProperties"] = additional_properties;
+    }
+
+    Some(json!({ "application/json": { "schema": schema } }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::OpenApi;
+    use crate::api::MatrixVersion;
+
+    mod set_thing {
+        use std::collections::BTreeMap;
+
+        crate::api::ruma_api! {
+            metadata: {
+                description: "Set a thing.",
+                method: PUT,
+                name: "set_thing",
+                unstable_path: "/_matrix/client/unstable/org.example/things/:thing_id",
+                r0_path: "/_matrix/client/r0/things/:thing_id",
+                stable_path: "/_matrix/client/v3/things/:thing_id",
+                rate_limited: true,
+                authentication: AccessToken,
+                added: 1.0,
+                deprecated: 1.1,
+                removed: 1.2,
+            }
+
+            request: {
+                /// The ID of the thing.
+                #[ruma_api(path)]
+                pub thing_id: String,
+
+                /// Whether to notify the owner.
+                #[ruma_api(query)]
+                #[serde(default)]
+                pub notify: bool,
+
+                /// The new values.
+                #[serde(rename = "values")]
+                pub new_values: Vec<u64>,
+
+                /// The label.
+                pub label: Option<String>,
+
+                /// The other values.
+                #[serde(flatten)]
+                pub other: BTreeMap<String, u64>,
+            }
+
+            response: {
+                /// The content type.
+                #[ruma_api(header = CONTENT_TYPE)]
+                pub content_type: Option<String>,
+
+                /// The revision.
+                pub revision: u64,
+            }
+        }
+    }
+
+    #[test]
+    fn document() {
+        let document = OpenApi::new("Things", "0.1.0").with_endpoint(set_thing::openapi_schema());
+
+        let v1_0 = document.to_json(&[MatrixVersion::V1_0]);
+        assert!(v1_0["paths"]["/_matrix/client/r0/things/{thing_id}"]["put"].is_object());
+
+        let v1_2 = document.to_json(&[MatrixVersion::V1_2]);
+        assert_eq!(v1_2["paths"], json!({}));
+
+        let v1_1 = document.to_json(&[MatrixVersion::V1_1]);
+        assert_eq!(
+            v1_1["paths"]["/_matrix/client/v3/things/{thing_id}"]["put"],
+            json!({
+                "operationId": "set_thing",
+                "summary": "Set a thing.",
+                "deprecated": true,
+                "x-ruma-added": "v1.0",
+                "x-ruma-deprecated": "v1.1",
+                "x-ruma-removed": "v1.2",
+                "x-ruma-rate-limited": true,
+                "security": [{ "accessToken": [] }, { "accessTokenQuery": [] }],
+                "parameters": [
+                    {
+                        "name": "thing_id",
+                        "in": "path",
+                        "description": "The ID of the thing.",
+                        "required": true,
+                        "schema": { "type": "string" },
+                    },
+                    {
+                        "name": "notify",
+                        "in": "query",
+                        "description": "Whether to notify the owner.",
+                        "required": false,
+                        "schema": { "type": "boolean" },
+                    },
+                ],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "object",
+                                "properties": {
+                                    "values": {
+                                        "type": "array",
+                                        "items": { "type": "integer" },
+                                        "description": "The new values.",
+                                    },
+                                    "label": {
+                                        "type": "string",
+                                        "description": "The label.",
+                                    },
+                                },
+                                "required": ["values"],
+                                "additionalProperties": {},
+                            },
+                        },
+                    },
+                },
+                "responses": {
+                    "200": {
+                        "description": "The request was successful.",
+                        "headers": {
+                            "content-type": {
+                                "description": "The content type.",
+                                "required": false,
+                                "schema": { "type": "string" },
+                            },
+                        },
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "revision": {
+                                            "type": "integer",
+                                            "description": "The revision.",
+                                        },
+                                    },
+                                    "required": ["revision"],
+                                },
+                            },
+                        },
+                    },
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    error::Error as StdError,
+    fmt, mem,
+    pin::Pin,
+    sync::{Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+type BoxStream = Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send>>;
+
+/// The body of a request or response of an endpoint with a `#[ruma_api(raw_body)]` field, which is
+/// either in memory or a stream of chunks.
+///
+/// Streams allow to send and receive bodies that are too large to be held in memory, like the
+/// content of the media endpoints. They are only supported by the `streaming` methods of the
+/// endpoint traits, like [`OutgoingRequest::try_into_streaming_http_request`], the other methods
+/// fail if the body is a stream.
+///
+/// [`OutgoingRequest::try_into_streaming_http_request`]: super::OutgoingRequest::try_into_streaming_http_request
+#[derive(Default)]
+pub struct RawBody {
+    inner: Inner,
+}
+
+enum Inner {
+    Bytes(Bytes),
+    Stream {
+        // The mutex makes `RawBody` `Sync` without requiring it from the stream. It is never
+        // locked, since the stream is only polled through a mutable reference.
+        stream: Mutex<BoxStream>,
+        content_length: Option<u64>,
+    },
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self::Bytes(Bytes::new())
+    }
+}
+
+impl RawBody {
+    /// Creates a new `RawBody` from the given stream of chunks.
+    pub fn from_stream<S, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        let stream = Box::pin(ErrInto(Box::pin(stream)));
+        Self { inner: Inner::Stream { stream: Mutex::new(stream), content_length: None } }
+    }
+
+    /// Sets the total length of the chunks of this body, if it is a stream.
+    ///
+    /// It is sent in the `Content-Length` header, which some homeservers require for uploads.
+    pub fn with_content_length(mut self, length: u64) -> Self {
+        if let Inner::Stream { content_length, .. } = &mut self.inner {
+            *content_length = Some(length);
+        }
+
+        self
+    }
+
+    /// The total length of the chunks of this body, if known.
+    pub fn content_length(&self) -> Option<u64> {
+        match &self.inner {
+            Inner::Bytes(bytes) => Some(bytes.len() as u64),
+            Inner::Stream { content_length, .. } => *content_length,
+        }
+    }
+
+    /// Whether this body is a stream.
+    pub fn is_stream(&self) -> bool {
+        matches!(self.inner, Inner::Stream { .. })
+    }
+
+    /// The bytes of this body, if it is in memory.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.inner {
+            Inner::Bytes(bytes) => Some(bytes),
+            Inner::Stream { .. } => None,
+        }
+    }
+
+    /// Converts this body into its bytes, if it is in memory.
+    ///
+    /// Returns `Err(self)` if it is a stream.
+    pub fn into_bytes(self) -> Result<Bytes, Self> {
+        match self.inner {
+            Inner::Bytes(bytes) => Ok(bytes),
+            inner @ Inner::Stream { .. } => Err(Self { inner }),
+        }
+    }
+}
+
+impl From<Bytes> for RawBody {
+    fn from(bytes: Bytes) -> Self {
+        Self { inner: Inner::Bytes(bytes) }
+    }
+}
+
+impl From<Vec<u8>> for RawBody {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from(Bytes::from(bytes))
+    }
+}
+
+impl Stream for RawBody {
+    type Item = Result<Bytes, BoxError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match &mut self.get_mut().inner {
+            Inner::Bytes(bytes) if bytes.is_empty() => Poll::Ready(None),
+            Inner::Bytes(bytes) => Poll::Ready(Some(Ok(mem::take(bytes)))),
+            Inner::Stream { stream, .. } => {
+                stream.get_mut().unwrap_or_else(PoisonError::into_inner).as_mut().poll_next(cx)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RawBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Inner::Bytes(bytes) => f.debug_tuple("RawBody").field(bytes).finish(),
+            Inner::Stream { content_length, .. } => f
+                .debug_struct("RawBody")
+                .field("content_length", content_length)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A stream that converts the errors of the inner stream to `BoxError`.
+struct ErrInto<S>(Pin<Box<S>>);
+
+impl<S, E> Stream for ErrInto<S>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: Into<BoxError>,
+{
+    type Item = Result<Bytes, BoxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.as_mut().poll_next(cx).map(|item| item.map(|result| result.map_err(Into::into)))
+    }
+}
@@ -0,0 +1,250 @@
+use std::{fmt, sync::Arc};
+
+use http::Method;
+use percent_encoding::percent_decode_str;
+
+use super::{
+    error::{DeserializationError, FromHttpRequestError, RouteError},
+    IncomingRequest, Metadata, RawBody,
+};
+
+type DecodeFn<T> =
+    dyn Fn(http::Request<&[u8]>, &[String]) -> Result<T, FromHttpRequestError> + Send + Sync;
+
+type StreamingDecodeFn<T> =
+    dyn Fn(http::Request<RawBody>, &[String]) -> Result<T, FromHttpRequestError> + Send + Sync;
+
+/// A router that matches `http::Request`s against the paths of endpoints and converts them to the
+/// corresponding [`IncomingRequest`] types.
+///
+/// The endpoints are registered with [`with_endpoint`](Self::with_endpoint), and all the paths of
+/// their [`Metadata`], unstable, r0 and stable, are matched. Since the requests of the endpoints
+/// have different types, they are converted to a common type `T` after being decoded, usually an
+/// enum with a variant per endpoint.
+pub struct Router<T> {
+    routes: Vec<Route<T>>,
+}
+
+impl<T> Router<T> {
+    /// Creates a new `Router` without any endpoint.
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Registers the endpoint of the request type `R`, with the function to convert its
+    /// requests to `T`.
+    ///
+    /// If several endpoints have the same method and path, the first one that was registered is
+    /// used.
+    pub fn with_endpoint<R, F>(mut self, f: F) -> Self
+    where
+        R: IncomingRequest + 'static,
+        F: Fn(R) -> T + Send + Sync + 'static,
+        T: 'static,
+    {
+        let metadata = R::METADATA;
+        let f = Arc::new(f);
+        let decode_f = f.clone();
+        let decode: Arc<DecodeFn<T>> = Arc::new(move |request, path_args| {
+            R::try_from_http_request(request, path_args).map(&*decode_f)
+        });
+        let decode_streaming: Arc<StreamingDecodeFn<T>> = Arc::new(move |request, path_args| {
+            R::try_from_streaming_http_request(request, path_args).map(&*f)
+        });
+
+        for path in metadata_paths(&metadata) {
+            self.routes.push(Route {
+                metadata: metadata.clone(),
+                segments: parse_path(path),
+                streaming_body: R::STREAMING_BODY,
+                decode: decode.clone(),
+                decode_streaming: decode_streaming.clone(),
+            });
+        }
+
+        self
+    }
+
+    /// Finds the endpoint matching the given request and converts the request to `T`.
+    ///
+    /// The path arguments are percent-decoded before the request is converted. The query string
+    /// is not used to select the endpoint.
+    pub fn route<B: AsRef<[u8]>>(&self, request: http::Request<B>) -> Result<T, RouteError> {
+        let (route, path_args) = self.find_route_with_path_args(&request)?;
+
+        let (parts, body) = request.into_parts();
+        (route.decode)(http::Request::from_parts(parts, body.as_ref()), &path_args)
+            .map_err(RouteError::Request)
+    }
+
+    /// Finds the endpoint matching the given request with a [`RawBody`] and converts the request
+    /// to `T`.
+    ///
+    /// The body can only be a stream if [`has_streaming_body`](Self::has_streaming_body) returns
+    /// `true` for the request, otherwise it must be in memory.
+    pub fn route_streaming(&self, request: http::Request<RawBody>) -> Result<T, RouteError> {
+        let (route, path_args) = self.find_route_with_path_args(&request)?;
+        (route.decode_streaming)(request, &path_args).map_err(RouteError::Request)
+    }
+
+    /// Checks whether an endpoint matches the method and path of the given request, without
+    /// converting it, and returns the metadata of the endpoint.
+    ///
+    /// This can be used to reject requests before their body is received.
+    pub fn check<B>(&self, request: &http::Request<B>) -> Result<&Metadata, RouteError> {
+        let segments: Vec<_> = request.uri().path().split('/').collect();
+        self.find_route(request.method(), &segments).map(|route| &route.metadata)
+    }
+
+    /// Whether the endpoint matching the given request accepts a body that is a stream in
+    /// [`route_streaming`](Self::route_streaming), according to
+    /// [`IncomingRequest::STREAMING_BODY`].
+    ///
+    /// Returns `false` if no endpoint matches the request.
+    pub fn has_streaming_body<B>(&self, request: &http::Request<B>) -> bool {
+        let segments: Vec<_> = request.uri().path().split('/').collect();
+        self.find_route(request.method(), &segments).map_or(false, |route| route.streaming_body)
+    }
+
+    /// Finds the endpoint matching the given request and extracts the percent-decoded path
+    /// arguments of the request.
+    fn find_route_with_path_args<B>(
+        &self,
+        request: &http::Request<B>,
+    ) -> Result<(&Route<T>, Vec<String>), RouteError> {
+        let segments: Vec<_> = request.uri().path().split('/').collect();
+        let route = self.find_route(request.method(), &segments)?;
+
+        let path_args = route
+            .path_args(&segments)
+            .map_err(|e| RouteError::Request(FromHttpRequestError::from(e)))?;
+
+        Ok((route, path_args))
+    }
+
+    fn find_route(&self, method: &Method, segments: &[&str]) -> Result<&Route<T>, RouteError> {
+        let mut found_path = false;
+        let mut best_match: Option<&Route<T>> = None;
+
+        for route in self.routes.iter().filter(|route| route.matches(segments)) {
+            found_path = true;
+
+            if route.metadata.method != method {
+                continue;
+            }
+
+            if best_match.map_or(true, |best| route.is_more_specific_than(best)) {
+                best_match = Some(route);
+            }
+        }
+
+        match best_match {
+            Some(route) => Ok(route),
+            None if found_path => Err(RouteError::MethodNotAllowed),
+            None => Err(RouteError::NotFound),
+        }
+    }
+}
+
+impl<T> Clone for Router<T> {
+    fn clone(&self) -> Self {
+        Self { routes: self.routes.clone() }
+    }
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Router<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router").field("routes", &self.routes).finish()
+    }
+}
+
+/// The paths of the endpoint with the given metadata.
+fn metadata_paths(metadata: &Metadata) -> impl Iterator<Item = &'static str> {
+    [metadata.unstable_path, metadata.r0_path, metadata.stable_path].into_iter().flatten()
+}
+
+fn parse_path(path: &'static str) -> Vec<Segment> {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(_) => Segment::Arg,
+            None => Segment::Literal(segment),
+        })
+        .collect()
+}
+
+struct Route<T> {
+    metadata: Metadata,
+    segments: Vec<Segment>,
+    streaming_body: bool,
+    decode: Arc<DecodeFn<T>>,
+    decode_streaming: Arc<StreamingDecodeFn<T>>,
+}
+
+impl<T> Clone for Route<T> {
+    fn clone(&self) -> Self {
+        Self {
+            metadata: self.metadata.clone(),
+            segments: self.segments.clone(),
+            streaming_body: self.streaming_body,
+            decode: self.decode.clone(),
+            decode_streaming: self.decode_streaming.clone(),
+        }
+    }
+}
+
+impl<T> Route<T> {
+    fn matches(&self, segments: &[&str]) -> bool {
+        self.segments.len() == segments.len()
+            && self.segments.iter().zip(segments).all(
+                |(route_segment, segment)| match route_segment {
+                    Segment::Literal(literal) => literal == segment,
+                    Segment::Arg => true,
+                },
+            )
+    }
+
+    /// Whether this route has a literal segment where `other` has an argument, before the
+    /// opposite happens.
+    fn is_more_specific_than(&self, other: &Self) -> bool {
+        self.segments
+            .iter()
+            .zip(&other.segments)
+            .find_map(|(a, b)| match (a, b) {
+                (Segment::Literal(_), Segment::Arg) => Some(true),
+                (Segment::Arg, Segment::Literal(_)) => Some(false),
+                _ => None,
+            })
+            .unwrap_or(false)
+    }
+
+    fn path_args(&self, segments: &[&str]) -> Result<Vec<String>, DeserializationError> {
+        self.segments
+            .iter()
+            .zip(segments)
+            .filter(|(route_segment, _)| matches!(route_segment, Segment::Arg))
+            .map(|(_, segment)| Ok(percent_decode_str(segment).decode_utf8()?.into_owned()))
+            .collect()
+    }
+}
+
+impl<T> fmt::Debug for Route<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route")
+            .field("name", &self.metadata.name)
+            .field("method", &self.metadata.method)
+            .field("segments", &self.segments)
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Segment {
+    Literal(&'static str),
+    Arg,
+}
@@ -0,0 +1,240 @@
+//! Integration of endpoint types with HTTP server frameworks.
+//!
+//! [`Ruma`] holds a request that was received for an endpoint, with its access token. The
+//! framework-specific modules convert the requests of the framework to it, and the responses and
+//! errors of the endpoints to responses of the framework.
+
+use std::{error::Error as StdError, fmt, time::Duration};
+
+use http::{
+    header::{
+        HeaderMap, HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION,
+    },
+    StatusCode, Uri,
+};
+
+use super::{
+    error::{ErrorKind, FromHttpRequestError, MatrixError},
+    AuthScheme, IncomingRequest, RawBody,
+};
+
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+mod limits;
+
+pub use limits::{RateLimited, RateLimiter, RequestInfo, RequestLimits};
+
+/// A request received for the endpoint of `R`.
+#[derive(Clone, Debug)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Ruma<R> {
+    /// The request.
+    pub request: R,
+
+    /// The access token sent with the request, if any.
+    ///
+    /// It is always `Some(_)` if the endpoint requires one.
+    pub access_token: Option<String>,
+}
+
+impl<R: IncomingRequest> Ruma<R> {
+    /// Tries to convert the given `http::Request` to a request for the endpoint of `R`, with the
+    /// given percent-decoded path arguments.
+    ///
+    /// Fails if the endpoint requires an access token and the request has none.
+    pub fn from_http_request<B, S>(
+        request: http::Request<B>,
+        path_args: &[S],
+    ) -> Result<Self, RumaRejection>
+    where
+        B: AsRef<[u8]>,
+        S: AsRef<str>,
+    {
+        let access_token = required_access_token::<R, _>(&request)?;
+        let request =
+            R::try_from_http_request(request, path_args).map_err(RumaRejection::Request)?;
+
+        Ok(Self { request, access_token })
+    }
+
+    /// Tries to convert the given `http::Request` with a [`RawBody`] to a request for the
+    /// endpoint of `R`, with the given percent-decoded path arguments.
+    ///
+    /// The body can only be a stream if [`R::STREAMING_BODY`](IncomingRequest::STREAMING_BODY)
+    /// is `true`. Fails if the endpoint requires an access token and the request has none.
+    pub fn from_streaming_http_request<S>(
+        request: http::Request<RawBody>,
+        path_args: &[S],
+    ) -> Result<Self, RumaRejection>
+    where
+        S: AsRef<str>,
+    {
+        let access_token = required_access_token::<R, _>(&request)?;
+        let request = R::try_from_streaming_http_request(request, path_args)
+            .map_err(RumaRejection::Request)?;
+
+        Ok(Self { request, access_token })
+    }
+}
+
+/// Get the access token of a request for the endpoint of `R`, or an error if the endpoint requires
+/// one and the request has none.
+fn required_access_token<R: IncomingRequest, B>(
+    request: &http::Request<B>,
+) -> Result<Option<String>, RumaRejection> {
+    let access_token = access_token(request.headers(), request.uri(), R::METADATA.authentication);
+
+    match (R::METADATA.authentication, access_token) {
+        (AuthScheme::AccessToken | AuthScheme::QueryOnlyAccessToken, None) => {
+            Err(RumaRejection::MissingAccessToken)
+        }
+        (_, access_token) => Ok(access_token),
+    }
+}
+
+/// An error when a request for an endpoint can't be converted to a [`Ruma`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RumaRejection {
+    /// The endpoint requires an access token but the request has none.
+    MissingAccessToken,
+
+    /// The path arguments of the request could not be extracted.
+    PathArgs(Box<dyn StdError + Send + Sync>),
+
+    /// The body of the request could not be read.
+    Body(Box<dyn StdError + Send + Sync>),
+
+    /// The request could not be converted to the request type of the endpoint.
+    Request(FromHttpRequestError),
+
+    /// The body of the request is larger than the maximum size for the endpoint.
+    TooLarge {
+        /// The maximum size of the body, in bytes.
+        max_size: u64,
+    },
+
+    /// The request exceeds the rate limit of the endpoint.
+    LimitExceeded {
+        /// How long the client should wait before retrying the request, if known.
+        retry_after: Option<Duration>,
+    },
+}
+
+impl fmt::Display for RumaRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAccessToken => write!(f, "Missing access token."),
+            Self::PathArgs(e) => write!(f, "Invalid path arguments: {}", e),
+            Self::Body(e) => write!(f, "Failed to read the request body: {}", e),
+            Self::Request(e) => write!(f, "Invalid request: {}", e),
+            Self::TooLarge { max_size } => {
+                write!(f, "The request body is larger than {} bytes.", max_size)
+            }
+            Self::LimitExceeded { .. } => write!(f, "Too many requests."),
+        }
+    }
+}
+
+impl StdError for RumaRejection {}
+
+impl From<RumaRejection> for MatrixError {
+    fn from(rejection: RumaRejection) -> Self {
+        let (status_code, kind) = match &rejection {
+            RumaRejection::MissingAccessToken => {
+                (StatusCode::UNAUTHORIZED, ErrorKind::MissingToken)
+            }
+            RumaRejection::PathArgs(_) => (StatusCode::BAD_REQUEST, ErrorKind::InvalidParam),
+            RumaRejection::Body(_) => (StatusCode::BAD_REQUEST, ErrorKind::NotJson),
+            RumaRejection::Request(_) => (StatusCode::BAD_REQUEST, ErrorKind::BadJson),
+            RumaRejection::TooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, ErrorKind::TooLarge),
+            RumaRejection::LimitExceeded { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorKind::LimitExceeded { retry_after_ms: *retry_after },
+            ),
+        };
+
+        Self::new(status_code, kind, rejection.to_string())
+    }
+}
+
+/// Get the access token of a request for an endpoint with the given authentication scheme.
+///
+/// The access token is looked for in the `Authorization` header, with the `Bearer` scheme, then in
+/// the `access_token` query parameter. With [`AuthScheme::QueryOnlyAccessToken`], only the query
+/// parameter is used.
+pub fn access_token(headers: &HeaderMap, uri: &Uri, authentication: AuthScheme) -> Option<String> {
+    let header_token = || {
+        headers
+            .get(AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")
+            .map(|token| token.trim().to_owned())
+    };
+    let query_token = || {
+        form_urlencoded::parse(uri.query()?.as_bytes())
+            .find(|(key, _)| key == "access_token")
+            .map(|(_, token)| token.into_owned())
+    };
+
+    match authentication {
+        AuthScheme::QueryOnlyAccessToken => query_token(),
+        _ => header_token().or_else(query_token),
+    }
+}
+
+/// Adds the [CORS headers] required by the Client-Server API to the given headers of a response.
+///
+/// [CORS headers]: https://spec.matrix.org/v1.2/client-server-api/#web-browser-clients
+pub fn add_cors_headers(headers: &mut HeaderMap) {
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("GET, POST, PUT, DELETE, OPTIONS"),
+    );
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("X-Requested-With, Content-Type, Authorization"),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use http::{header::AUTHORIZATION, HeaderMap, HeaderValue, Uri};
+
+    use super::access_token;
+    use crate::api::AuthScheme;
+
+    #[test]
+    fn extract_access_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer header_token"));
+        let uri = Uri::from_static("/_matrix/client/v3/sync?access_token=query%20token");
+
+        assert_matches!(
+            access_token(&headers, &uri, AuthScheme::AccessToken).as_deref(),
+            Some("header_token")
+        );
+        assert_matches!(
+            access_token(&headers, &uri, AuthScheme::QueryOnlyAccessToken).as_deref(),
+            Some("query token")
+        );
+        assert_matches!(
+            access_token(&HeaderMap::new(), &uri, AuthScheme::AccessToken).as_deref(),
+            Some("query token")
+        );
+        assert_matches!(
+            access_token(
+                &headers,
+                &Uri::from_static("/_matrix/client/v3/sync"),
+                AuthScheme::QueryOnlyAccessToken
+            ),
+            None
+        );
+    }
+}
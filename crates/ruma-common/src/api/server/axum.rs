@@ -0,0 +1,178 @@
+//! Integration with [axum].
+//!
+//! [`Ruma`] can be used as an extractor in handlers of routes that use the paths of the
+//! [`Metadata`](crate::api::Metadata) of the endpoint, since they use the same syntax for path
+//! parameters as axum. The responses and errors of the endpoints are converted to axum responses
+//! with [`RumaResponse`].
+//!
+//! The bodies of the requests and responses of the endpoints with a streaming raw body, like the
+//! media endpoints, are not buffered.
+//!
+//! The [`RequestLimits`](super::RequestLimits) in the extensions of the requests are applied by
+//! the extractor, they can be added with an `axum::Extension` layer. The remote address of the
+//! requests is given to the [`RateLimiter`](super::RateLimiter) if the app is served with
+//! `into_make_service_with_connect_info::<SocketAddr>()`.
+//!
+//! ```no_run
+//! # mod get_thing {
+//! #     ruma_common::api::ruma_api! {
+//! #         metadata: {
+//! #             description: "Get a thing.",
+//! #             method: GET,
+//! #             name: "get_thing",
+//! #             stable_path: "/_matrix/client/v3/things/:thing_id",
+//! #             rate_limited: false,
+//! #             authentication: AccessToken,
+//! #             added: 1.1,
+//! #         }
+//! #         request: {
+//! #             #[ruma_api(path)]
+//! #             pub thing_id: String,
+//! #         }
+//! #         response: {}
+//! #     }
+//! # }
+//! # use axum_crate as axum;
+//! use axum::{middleware, routing::get, Router};
+//! use ruma_common::api::{
+//!     error::MatrixError,
+//!     server::{
+//!         axum::{cors, RumaResponse},
+//!         Ruma,
+//!     },
+//!     IncomingRequest,
+//! };
+//!
+//! async fn get_thing(
+//!     Ruma { request, .. }: Ruma<get_thing::Request>,
+//! ) -> Result<RumaResponse<get_thing::Response>, RumaResponse<MatrixError>> {
+//!     Ok(RumaResponse(get_thing::Response {}))
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route(get_thing::Request::METADATA.stable_path.unwrap(), get(get_thing))
+//!     .layer(middleware::from_fn(cors));
+//! ```
+//!
+//! [axum]: https://github.com/tokio-rs/axum
+
+use std::net::SocketAddr;
+
+use axum_crate::{
+    async_trait,
+    body::{boxed, Full, HttpBody, StreamBody},
+    extract::{ConnectInfo, FromRequest, Path, RequestParts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use http::{Method, StatusCode};
+
+use super::{access_token, add_cors_headers, RequestInfo, RequestLimits, Ruma, RumaRejection};
+use crate::api::{error::MatrixError, AuthScheme, IncomingRequest, OutgoingResponse};
+
+#[async_trait]
+impl<B, R> FromRequest<B> for Ruma<R>
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    R: IncomingRequest,
+{
+    type Rejection = RumaRejection;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let has_path_args =
+            [R::METADATA.unstable_path, R::METADATA.r0_path, R::METADATA.stable_path]
+                .into_iter()
+                .flatten()
+                .any(|path| path.contains("/:"));
+
+        let path_args = if has_path_args {
+            Path::<Vec<String>>::from_request(req)
+                .await
+                .map_err(|e| RumaRejection::PathArgs(e.into()))?
+                .0
+        } else {
+            Vec::new()
+        };
+
+        let limits = req.extensions().get::<RequestLimits>().cloned().unwrap_or_default();
+        let authentication = R::METADATA.authentication;
+        let access_token = access_token(req.headers(), req.uri(), authentication);
+
+        // Reject requests without a required access token before reading the body.
+        if access_token.is_none()
+            && matches!(authentication, AuthScheme::AccessToken | AuthScheme::QueryOnlyAccessToken)
+        {
+            return Err(RumaRejection::MissingAccessToken);
+        }
+
+        let mut request_info =
+            RequestInfo::new(req.method(), req.uri(), req.headers(), req.extensions());
+        request_info.remote_addr =
+            req.extensions().get::<ConnectInfo<SocketAddr>>().map(|connect_info| connect_info.0);
+        request_info.access_token = access_token.as_deref();
+        limits.check_rate_limit(&R::METADATA, &request_info)?;
+        limits.check_content_length(&R::METADATA, req.headers())?;
+
+        let body = req.take_body().ok_or_else(|| {
+            RumaRejection::Body("the body of the request was already extracted".into())
+        })?;
+
+        if R::STREAMING_BODY {
+            let body = limits.stream_body(&R::METADATA, body);
+            Self::from_streaming_http_request(http_request(req, body), &path_args)
+        } else {
+            let body = limits.read_body(&R::METADATA, body).await?;
+            Self::from_http_request(http_request(req, body), &path_args)
+        }
+    }
+}
+
+/// An `http::Request` with the given body and the method, URI and headers of the given request.
+fn http_request<B, T>(req: &RequestParts<B>, body: T) -> http::Request<T> {
+    let mut request = http::Request::new(body);
+    *request.method_mut() = req.method().clone();
+    *request.uri_mut() = req.uri().clone();
+    *request.headers_mut() = req.headers().clone();
+    request
+}
+
+impl IntoResponse for RumaRejection {
+    fn into_response(self) -> Response {
+        RumaResponse(MatrixError::from(self)).into_response()
+    }
+}
+
+/// A wrapper to convert the response or the error of an endpoint to an axum response.
+#[derive(Clone, Debug)]
+#[allow(clippy::exhaustive_structs)]
+pub struct RumaResponse<T>(pub T);
+
+impl<T: OutgoingResponse> IntoResponse for RumaResponse<T> {
+    fn into_response(self) -> Response {
+        match self.0.try_into_streaming_http_response() {
+            Ok(response) => response.map(|body| match body.into_bytes() {
+                Ok(bytes) => boxed(Full::from(bytes)),
+                Err(body) => boxed(StreamBody::new(body)),
+            }),
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
+
+/// A middleware that adds the CORS headers required by the Client-Server API to the responses, and
+/// responds to `OPTIONS` requests.
+///
+/// Use it with [`axum::middleware::from_fn`](axum_crate::middleware::from_fn).
+pub async fn cors<B>(req: http::Request<B>, next: Next<B>) -> Response {
+    let mut response = if req.method() == Method::OPTIONS {
+        StatusCode::OK.into_response()
+    } else {
+        next.run(req).await
+    };
+
+    add_cors_headers(response.headers_mut());
+    response
+}
@@ -0,0 +1,547 @@
+//! Integration with [hyper].
+//!
+//! [`RouterService`] is a hyper `Service` that serves endpoints with a handler per endpoint.
+//!
+//! [hyper]: https://hyper.rs/
+
+use std::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::{Method, StatusCode};
+use hyper_crate::{service::Service, Body};
+
+use super::{access_token, add_cors_headers, RequestInfo, RequestLimits, Ruma, RumaRejection};
+use crate::api::{
+    error::{ErrorKind, MatrixError, RouteError},
+    AuthScheme, IncomingRequest, Metadata, OutgoingResponse, Router,
+};
+
+type ResponseFuture = Pin<Box<dyn Future<Output = http::Response<Body>> + Send>>;
+
+/// A request converted by the router, that is given to its handler with its access token.
+type RoutedRequest = Box<dyn FnOnce(Option<String>) -> ResponseFuture + Send>;
+
+/// A hyper `Service` that serves endpoints with a handler per endpoint.
+///
+/// The endpoints are registered with [`with_endpoint`](Self::with_endpoint), and the requests are
+/// matched against all their paths with a [`Router`]. The requests to other paths get a
+/// `404 Not Found` response, and the requests that can't be converted to a [`Ruma<R>`] get the
+/// error response of the corresponding [`RumaRejection`].
+///
+/// The CORS headers are added to the responses to the requests for the Client-Server API and the
+/// media repository, and `OPTIONS` requests to the paths of their endpoints get an empty response.
+///
+/// The bodies of the requests and responses of the endpoints with a streaming raw body, like the
+/// media endpoints, are not buffered.
+///
+/// The size of the bodies and the rate of the requests can be limited with
+/// [`with_limits`](Self::with_limits).
+pub struct RouterService {
+    router: Arc<Router<RoutedRequest>>,
+    limits: RequestLimits,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl RouterService {
+    /// Creates a new `RouterService` without any endpoint.
+    pub fn new() -> Self {
+        Self { router: Arc::new(Router::new()), limits: RequestLimits::new(), remote_addr: None }
+    }
+
+    /// Registers the endpoint of the request type `R`, with the given handler.
+    ///
+    /// If several endpoints have the same method and path, the first one that was registered is
+    /// used.
+    pub fn with_endpoint<R, F, Fut>(self, handler: F) -> Self
+    where
+        R: IncomingRequest + Send + 'static,
+        F: Fn(Ruma<R>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R::OutgoingResponse, R::EndpointError>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let router = Arc::try_unwrap(self.router).unwrap_or_else(|router| (*router).clone());
+        let router = router.with_endpoint(move |request: R| -> RoutedRequest {
+            let handler = handler.clone();
+            Box::new(move |access_token| {
+                Box::pin(async move {
+                    match handler(Ruma { request, access_token }).await {
+                        Ok(response) => into_hyper_response(response),
+                        Err(error) => into_hyper_response(error),
+                    }
+                })
+            })
+        });
+
+        Self { router: Arc::new(router), ..self }
+    }
+
+    /// Sets the limits applied to the requests before they are given to the handlers.
+    pub fn with_limits(self, limits: RequestLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Sets the address of the client of the connection that is served, to be given to the
+    /// [`RateLimiter`](super::RateLimiter).
+    ///
+    /// The address is available when the service is created for each connection, for example with
+    /// `hyper::service::make_service_fn` and `AddrStream::remote_addr`.
+    pub fn with_remote_addr(self, remote_addr: SocketAddr) -> Self {
+        Self { remote_addr: Some(remote_addr), ..self }
+    }
+}
+
+impl Clone for RouterService {
+    fn clone(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+            limits: self.limits.clone(),
+            remote_addr: self.remote_addr,
+        }
+    }
+}
+
+impl Default for RouterService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RouterService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouterService")
+            .field("router", &self.router)
+            .field("limits", &self.limits)
+            .field("remote_addr", &self.remote_addr)
+            .finish()
+    }
+}
+
+impl Service<http::Request<Body>> for RouterService {
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let router = self.router.clone();
+        let limits = self.limits.clone();
+        let remote_addr = self.remote_addr;
+
+        Box::pin(async move {
+            let cors = is_browser_path(request.uri().path());
+
+            // Reject the requests that can't succeed before reading the body.
+            let mut response = match router.check(&request) {
+                // Preflight requests are only answered for the paths of the endpoints.
+                Err(RouteError::MethodNotAllowed)
+                    if cors && request.method() == Method::OPTIONS =>
+                {
+                    http::Response::new(Body::empty())
+                }
+                Err(e) => route_error_response(e),
+                Ok(metadata) => handle(request, &router, metadata, &limits, remote_addr).await,
+            };
+
+            if cors {
+                add_cors_headers(response.headers_mut());
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+async fn handle(
+    request: http::Request<Body>,
+    router: &Router<RoutedRequest>,
+    metadata: &Metadata,
+    limits: &RequestLimits,
+    remote_addr: Option<SocketAddr>,
+) -> http::Response<Body> {
+    let authentication = metadata.authentication;
+    let access_token = access_token(request.headers(), request.uri(), authentication);
+
+    if access_token.is_none()
+        && matches!(authentication, AuthScheme::AccessToken | AuthScheme::QueryOnlyAccessToken)
+    {
+        return into_hyper_response(MatrixError::from(RumaRejection::MissingAccessToken));
+    }
+
+    let streaming_body = router.has_streaming_body(&request);
+    let (parts, body) = request.into_parts();
+
+    let mut request_info =
+        RequestInfo::new(&parts.method, &parts.uri, &parts.headers, &parts.extensions);
+    request_info.remote_addr = remote_addr;
+    request_info.access_token = access_token.as_deref();
+    if let Err(rejection) = limits
+        .check_rate_limit(metadata, &request_info)
+        .and_then(|_| limits.check_content_length(metadata, &parts.headers))
+    {
+        return into_hyper_response(MatrixError::from(rejection));
+    }
+
+    let routed_request = if streaming_body {
+        let body = limits.stream_body(metadata, body);
+        router.route_streaming(http::Request::from_parts(parts, body))
+    } else {
+        match limits.read_body(metadata, body).await {
+            Ok(body) => router.route(http::Request::from_parts(parts, body)),
+            Err(rejection) => return into_hyper_response(MatrixError::from(rejection)),
+        }
+    };
+
+    match routed_request {
+        Ok(request) => request(access_token).await,
+        Err(e) => route_error_response(e),
+    }
+}
+
+/// Whether the given request path is part of an API that can be used by web browsers.
+fn is_browser_path(path: &str) -> bool {
+    path.starts_with("/_matrix/client/") || path.starts_with("/_matrix/media/")
+}
+
+fn into_hyper_response<T: OutgoingResponse>(response: T) -> http::Response<Body> {
+    match response.try_into_streaming_http_response() {
+        Ok(response) => response.map(|body| match body.into_bytes() {
+            Ok(bytes) => Body::from(bytes),
+            Err(body) => Body::wrap_stream(body),
+        }),
+        Err(_) => {
+            let mut response = http::Response::new(Body::empty());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        }
+    }
+}
+
+fn route_error_response(error: RouteError) -> http::Response<Body> {
+    match error {
+        RouteError::NotFound => error_response(StatusCode::NOT_FOUND, "Unrecognized request"),
+        RouteError::MethodNotAllowed => {
+            error_response(StatusCode::METHOD_NOT_ALLOWED, "Unrecognized request method")
+        }
+        RouteError::Request(e) => into_hyper_response(MatrixError::from(RumaRejection::Request(e))),
+    }
+}
+
+fn error_response(status_code: StatusCode, error: &str) -> http::Response<Body> {
+    into_hyper_response(MatrixError::new(status_code, ErrorKind::Unrecognized, error.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::{
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_LENGTH, RETRY_AFTER},
+        Method, StatusCode,
+    };
+    use hyper_crate::{body::to_bytes, service::Service, Body};
+    use serde_json::{from_slice as from_json_slice, json, Value as JsonValue};
+
+    use super::RouterService;
+    use crate::api::{
+        error::{ErrorKind, MatrixError},
+        server::{RateLimited, RateLimiter, RequestInfo, RequestLimits, Ruma},
+        Metadata, RawBody,
+    };
+
+    mod get_thing {
+        crate::api::ruma_api! {
+            metadata: {
+                description: "Get a thing.",
+                method: GET,
+                name: "get_thing",
+                stable_path: "/_matrix/client/v3/things/:thing_id",
+                rate_limited: true,
+                authentication: AccessToken,
+                added: 1.1,
+            }
+
+            request: {
+                #[ruma_api(path)]
+                pub thing_id: String,
+            }
+
+            response: {
+                pub value: String,
+            }
+
+            error: crate::api::error::MatrixError
+        }
+    }
+
+    mod get_version {
+        crate::api::ruma_api! {
+            metadata: {
+                description: "Get the version of the server.",
+                method: GET,
+                name: "get_version",
+                stable_path: "/_matrix/federation/v1/version",
+                rate_limited: false,
+                authentication: None,
+                added: 1.1,
+            }
+
+            request: {}
+
+            response: {
+                pub version: String,
+            }
+
+            error: crate::api::error::MatrixError
+        }
+    }
+
+    mod echo_file {
+        crate::api::ruma_api! {
+            metadata: {
+                description: "Send back the uploaded file.",
+                method: POST,
+                name: "echo_file",
+                stable_path: "/_matrix/media/v3/echo",
+                rate_limited: false,
+                authentication: None,
+                added: 1.1,
+            }
+
+            request: {
+                #[ruma_api(raw_body)]
+                pub file: crate::api::RawBody,
+            }
+
+            response: {
+                #[ruma_api(raw_body)]
+                pub file: crate::api::RawBody,
+            }
+
+            error: crate::api::error::MatrixError
+        }
+    }
+
+    async fn get_thing(
+        Ruma { request, access_token }: Ruma<get_thing::Request>,
+    ) -> Result<get_thing::Response, MatrixError> {
+        if request.thing_id == "missing" {
+            return Err(MatrixError::new(
+                StatusCode::NOT_FOUND,
+                ErrorKind::NotFound,
+                "Thing not found".to_owned(),
+            ));
+        }
+
+        Ok(get_thing::Response {
+            value: format!("{} for {}", request.thing_id, access_token.unwrap()),
+        })
+    }
+
+    async fn get_version(
+        _: Ruma<get_version::Request>,
+    ) -> Result<get_version::Response, MatrixError> {
+        Ok(get_version::Response { version: "1.0".to_owned() })
+    }
+
+    async fn echo_file(
+        Ruma { request, .. }: Ruma<echo_file::Request>,
+    ) -> Result<echo_file::Response, MatrixError> {
+        assert!(request.file.is_stream());
+
+        let file = to_bytes(Body::wrap_stream(request.file)).await.map_err(|_| {
+            MatrixError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorKind::TooLarge,
+                "File too large".to_owned(),
+            )
+        })?;
+
+        Ok(echo_file::Response { file: RawBody::from_stream(Body::from(file)) })
+    }
+
+    fn service() -> RouterService {
+        RouterService::new()
+            .with_endpoint(get_thing)
+            .with_endpoint(get_version)
+            .with_endpoint(echo_file)
+    }
+
+    fn request(method: Method, path: &str, access_token: Option<&str>) -> http::Request<Body> {
+        let mut request = http::Request::builder().method(method).uri(path);
+        if let Some(access_token) = access_token {
+            request = request.header("Authorization", format!("Bearer {}", access_token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    async fn body_json(response: http::Response<Body>) -> JsonValue {
+        from_json_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn serve_endpoints() {
+        let mut service = service();
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/client/v3/things/a%20thing", Some("token")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert_eq!(body_json(response).await, json!({ "value": "a thing for token" }));
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/client/v3/things/missing", Some("token")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body_json(response).await["errcode"], "M_NOT_FOUND");
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/federation/v1/version", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN), None);
+        assert_eq!(body_json(response).await, json!({ "version": "1.0" }));
+    }
+
+    #[tokio::test]
+    async fn stream_raw_bodies() {
+        let limits = RequestLimits::new().with_max_body_size(16);
+        let mut service = service().with_limits(limits);
+        let chunks = |count| (0..count).map(|_| Ok::<_, hyper_crate::Error>("chunk"));
+
+        let mut upload = request(Method::POST, "/_matrix/media/v3/echo", None);
+        *upload.body_mut() = Body::wrap_stream(futures_util::stream::iter(chunks(3)));
+        let response = service.call(upload).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(CONTENT_LENGTH), None);
+        assert_eq!(to_bytes(response.into_body()).await.unwrap(), "chunkchunkchunk");
+
+        // The limit applies to streaming bodies without a `Content-Length` header.
+        let mut upload = request(Method::POST, "/_matrix/media/v3/echo", None);
+        *upload.body_mut() = Body::wrap_stream(futures_util::stream::iter(chunks(4)));
+        let response = service.call(upload).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body_json(response).await["errcode"], "M_TOO_LARGE");
+    }
+
+    #[tokio::test]
+    async fn cors_preflight() {
+        let mut service = service();
+
+        let response = service
+            .call(request(Method::OPTIONS, "/_matrix/client/v3/things/thing", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+
+        let response = service
+            .call(request(Method::OPTIONS, "/_matrix/client/v3/other/thing", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = service
+            .call(request(Method::OPTIONS, "/_matrix/federation/v1/version", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN), None);
+    }
+
+    #[tokio::test]
+    async fn reject_requests() {
+        let mut service = service();
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/client/v3/things/thing", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(body_json(response).await["errcode"], "M_MISSING_TOKEN");
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/client/v3/other/thing", Some("token")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = service
+            .call(request(Method::POST, "/_matrix/client/v3/things/thing", Some("token")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    struct LimitAddr;
+
+    impl RateLimiter for LimitAddr {
+        fn check(&self, _: &Metadata, request: &RequestInfo<'_>) -> Result<(), RateLimited> {
+            match request.remote_addr {
+                Some(addr) if addr.port() == 1 => {
+                    Err(RateLimited::new(Some(Duration::from_millis(1500))))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn limit_requests() {
+        let limits = RequestLimits::new().with_max_body_size(16).with_rate_limiter(LimitAddr);
+        let service = service().with_limits(limits);
+
+        // Requests are limited by remote address, whatever their access token.
+        let mut limited_service = service.clone().with_remote_addr(([127, 0, 0, 1], 1).into());
+        let response = limited_service
+            .call(request(Method::GET, "/_matrix/client/v3/things/thing", Some("random")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "2");
+        let body = body_json(response).await;
+        assert_eq!(body["errcode"], "M_LIMIT_EXCEEDED");
+        assert_eq!(body["retry_after_ms"], 1500);
+
+        // Endpoints that are not rate-limited are not checked.
+        let response = limited_service
+            .call(request(Method::GET, "/_matrix/federation/v1/version", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut service = service.with_remote_addr(([127, 0, 0, 1], 2).into());
+        let mut large_request =
+            request(Method::GET, "/_matrix/client/v3/things/thing", Some("token"));
+        *large_request.body_mut() = Body::from(vec![b' '; 32]);
+        let response = service.call(large_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body_json(response).await["errcode"], "M_TOO_LARGE");
+
+        // Unauthenticated requests are rejected before their body is read.
+        let mut large_request = request(Method::GET, "/_matrix/client/v3/things/thing", None);
+        *large_request.body_mut() = Body::from(vec![b' '; 32]);
+        let response = service.call(large_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(body_json(response).await["errcode"], "M_MISSING_TOKEN");
+
+        let response = service
+            .call(request(Method::GET, "/_matrix/client/v3/things/thing", Some("token")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,380 @@
+//! Limits on the requests received for endpoints.
+
+use std::{
+    collections::BTreeMap,
+    error::Error as StdError,
+    fmt,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_core::Stream;
+use http::{
+    header::{HeaderMap, CONTENT_LENGTH},
+    Extensions, Method, Uri,
+};
+use http_body::Body as HttpBody;
+
+use super::RumaRejection;
+use crate::api::{Metadata, RawBody};
+
+/// A rate limiter for the requests received for the endpoints that are
+/// [`rate_limited`](Metadata::rate_limited).
+///
+/// The implementations decide how the requests are counted, for example per endpoint and per
+/// remote address, or per user after looking up the user of the access token.
+pub trait RateLimiter: Send + Sync {
+    /// Checks whether the given request for the endpoint with the given metadata can be
+    /// processed.
+    ///
+    /// This is called before the request is authenticated and before its body is read.
+    fn check(&self, metadata: &Metadata, request: &RequestInfo<'_>) -> Result<(), RateLimited>;
+}
+
+/// The parts of a request that are available to a [`RateLimiter`].
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct RequestInfo<'a> {
+    /// The method of the request.
+    pub method: &'a Method,
+
+    /// The URI of the request.
+    pub uri: &'a Uri,
+
+    /// The headers of the request.
+    pub headers: &'a HeaderMap,
+
+    /// The extensions of the request.
+    pub extensions: &'a Extensions,
+
+    /// The address of the client that sent the request, if known.
+    pub remote_addr: Option<SocketAddr>,
+
+    /// The access token sent with the request, if any.
+    ///
+    /// It is not validated yet, so a client can send a different access token with every
+    /// request. Requests that are not authenticated by the server should be counted with the
+    /// remote address instead.
+    pub access_token: Option<&'a str>,
+}
+
+impl<'a> RequestInfo<'a> {
+    /// Creates a new `RequestInfo` with the given method, URI, headers and extensions, without
+    /// remote address or access token.
+    pub fn new(
+        method: &'a Method,
+        uri: &'a Uri,
+        headers: &'a HeaderMap,
+        extensions: &'a Extensions,
+    ) -> Self {
+        Self { method, uri, headers, extensions, remote_addr: None, access_token: None }
+    }
+}
+
+/// An error returned by a [`RateLimiter`] for a request that exceeds the rate limit of an
+/// endpoint.
+///
+/// It is converted to an `M_LIMIT_EXCEEDED` error.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RateLimited {
+    /// How long the client should wait before retrying the request, if known.
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimited {
+    /// Creates a new `RateLimited` with the given delay before the request can be retried.
+    pub fn new(retry_after: Option<Duration>) -> Self {
+        Self { retry_after }
+    }
+}
+
+/// The key of an endpoint in [`RequestLimits`].
+///
+/// The names of the endpoints are not unique across the Matrix APIs, but an endpoint is
+/// identified by its method and its paths.
+type EndpointKey = (String, [Option<&'static str>; 3]);
+
+fn endpoint_key(metadata: &Metadata) -> EndpointKey {
+    let paths = [metadata.unstable_path, metadata.r0_path, metadata.stable_path];
+    (metadata.method.as_str().to_owned(), paths)
+}
+
+/// The limits applied to the requests received for endpoints, before they are converted to the
+/// request types of the endpoints.
+///
+/// With the hyper integration, it is set with
+/// [`RouterService::with_limits`](super::hyper::RouterService::with_limits). With the axum
+/// integration, the [`Ruma`](super::Ruma) extractor uses the `RequestLimits` in the extensions of
+/// the request, which can be added with an `axum::Extension` layer.
+#[derive(Clone, Default)]
+pub struct RequestLimits {
+    max_body_size: Option<u64>,
+    endpoint_max_body_sizes: BTreeMap<EndpointKey, u64>,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
+}
+
+impl RequestLimits {
+    /// Creates a new `RequestLimits` without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of the body of the requests, in bytes.
+    ///
+    /// Requests with a larger body are rejected with an `M_TOO_LARGE` error.
+    pub fn with_max_body_size(self, max_body_size: u64) -> Self {
+        Self { max_body_size: Some(max_body_size), ..self }
+    }
+
+    /// Sets the maximum size of the body of the requests for the endpoint with the given
+    /// metadata, in bytes.
+    ///
+    /// This replaces the global maximum size for this endpoint, which is useful for endpoints that
+    /// accept large bodies, like the media upload endpoint.
+    pub fn with_endpoint_max_body_size(mut self, metadata: &Metadata, max_body_size: u64) -> Self {
+        self.endpoint_max_body_sizes.insert(endpoint_key(metadata), max_body_size);
+        self
+    }
+
+    /// Sets the rate limiter of the endpoints that are rate-limited.
+    pub fn with_rate_limiter<L: RateLimiter + 'static>(self, rate_limiter: L) -> Self {
+        Self { rate_limiter: Some(Arc::new(rate_limiter)), ..self }
+    }
+
+    /// The maximum size of the body of the requests for the endpoint with the given metadata, if
+    /// any.
+    pub fn max_body_size(&self, metadata: &Metadata) -> Option<u64> {
+        self.endpoint_max_body_sizes.get(&endpoint_key(metadata)).copied().or(self.max_body_size)
+    }
+
+    /// Checks the rate limit of the endpoint with the given metadata, if it is rate-limited, for
+    /// the given request.
+    pub fn check_rate_limit(
+        &self,
+        metadata: &Metadata,
+        request: &RequestInfo<'_>,
+    ) -> Result<(), RumaRejection> {
+        match &self.rate_limiter {
+            Some(rate_limiter) if metadata.rate_limited => rate_limiter
+                .check(metadata, request)
+                .map_err(|e| RumaRejection::LimitExceeded { retry_after: e.retry_after }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the `Content-Length` header of a request for the endpoint with the given metadata
+    /// against the maximum size of its body.
+    ///
+    /// This allows to reject a request before reading its body. Since the header can be absent or
+    /// wrong, the size of the body must still be checked while it is read, like
+    /// [`read_body`](Self::read_body) does.
+    pub fn check_content_length(
+        &self,
+        metadata: &Metadata,
+        headers: &HeaderMap,
+    ) -> Result<(), RumaRejection> {
+        let max_size = match self.max_body_size(metadata) {
+            Some(max_size) => max_size,
+            None => return Ok(()),
+        };
+
+        let content_length = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+
+        match content_length {
+            Some(content_length) if content_length > max_size => {
+                Err(RumaRejection::TooLarge { max_size })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the body of a request for the endpoint with the given metadata, stopping as soon as
+    /// it is larger than the maximum size.
+    pub async fn read_body<B>(&self, metadata: &Metadata, body: B) -> Result<Bytes, RumaRejection>
+    where
+        B: HttpBody,
+        B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let max_size = self.max_body_size(metadata);
+        let mut body = Box::pin(body);
+        let mut bytes = BytesMut::new();
+
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| RumaRejection::Body(e.into()))?;
+            bytes.put(chunk);
+
+            if let Some(max_size) = max_size {
+                if bytes.len() as u64 > max_size {
+                    return Err(RumaRejection::TooLarge { max_size });
+                }
+            }
+        }
+
+        Ok(bytes.freeze())
+    }
+
+    /// Converts the body of a request for the endpoint with the given metadata to a streaming
+    /// [`RawBody`], that fails as soon as it is larger than the maximum size.
+    ///
+    /// This is used instead of [`read_body`](Self::read_body) for the endpoints with a
+    /// [streaming body](crate::api::IncomingRequest::STREAMING_BODY). The errors of the stream
+    /// are [`RumaRejection`]s.
+    pub fn stream_body<B>(&self, metadata: &Metadata, body: B) -> RawBody
+    where
+        B: HttpBody + Send + 'static,
+        B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        RawBody::from_stream(LimitedBody {
+            body: Box::pin(body),
+            max_size: self.max_body_size(metadata),
+            size: 0,
+        })
+    }
+}
+
+/// A stream of the chunks of an HTTP body, that fails when it is larger than the maximum size.
+struct LimitedBody<B> {
+    body: Pin<Box<B>>,
+    max_size: Option<u64>,
+    size: u64,
+}
+
+impl<B> Stream for LimitedBody<B>
+where
+    B: HttpBody,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Bytes, RumaRejection>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut chunk = match self.body.as_mut().poll_data(cx) {
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+            Poll::Ready(Some(Err(e))) => {
+                return Poll::Ready(Some(Err(RumaRejection::Body(e.into()))));
+            }
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.size += chunk.remaining() as u64;
+        if let Some(max_size) = self.max_size {
+            if self.size > max_size {
+                return Poll::Ready(Some(Err(RumaRejection::TooLarge { max_size })));
+            }
+        }
+
+        Poll::Ready(Some(Ok(chunk.copy_to_bytes(chunk.remaining()))))
+    }
+}
+
+impl fmt::Debug for RequestLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestLimits")
+            .field("max_body_size", &self.max_body_size)
+            .field("endpoint_max_body_sizes", &self.endpoint_max_body_sizes)
+            .field("has_rate_limiter", &self.rate_limiter.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use assert_matches::assert_matches;
+    use http::{header::CONTENT_LENGTH, Extensions, HeaderMap, HeaderValue, Method, Uri};
+
+    use super::{RateLimited, RateLimiter, RequestInfo, RequestLimits};
+    use crate::api::{server::RumaRejection, AuthScheme, Metadata};
+
+    const METADATA: Metadata = Metadata {
+        description: "Upload a thing.",
+        method: Method::POST,
+        name: "upload_thing",
+        unstable_path: None,
+        r0_path: None,
+        stable_path: Some("/_matrix/client/v3/things"),
+        rate_limited: true,
+        authentication: AuthScheme::AccessToken,
+        added: None,
+        deprecated: None,
+        removed: None,
+    };
+
+    struct DenyAddr;
+
+    impl RateLimiter for DenyAddr {
+        fn check(&self, _: &Metadata, request: &RequestInfo<'_>) -> Result<(), RateLimited> {
+            match request.remote_addr {
+                Some(addr) if addr.ip().is_loopback() => {
+                    Err(RateLimited::new(Some(Duration::from_secs(2))))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn rate_limit() {
+        let limits = RequestLimits::new().with_rate_limiter(DenyAddr);
+        let (method, uri) = (Method::POST, Uri::from_static("/_matrix/client/v3/things"));
+        let (headers, extensions) = (HeaderMap::new(), Extensions::new());
+        let mut request = RequestInfo::new(&method, &uri, &headers, &extensions);
+
+        request.remote_addr = Some(SocketAddr::from(([192, 0, 2, 1], 1234)));
+        limits.check_rate_limit(&METADATA, &request).unwrap();
+
+        request.remote_addr = Some(SocketAddr::from(([127, 0, 0, 1], 1234)));
+        assert_matches!(
+            limits.check_rate_limit(&METADATA, &request),
+            Err(RumaRejection::LimitExceeded { retry_after: Some(retry_after) })
+            if retry_after == Duration::from_secs(2)
+        );
+
+        let not_rate_limited = Metadata { rate_limited: false, ..METADATA };
+        limits.check_rate_limit(&not_rate_limited, &request).unwrap();
+    }
+
+    #[test]
+    fn content_length() {
+        let limits = RequestLimits::new().with_max_body_size(10);
+        let mut headers = HeaderMap::new();
+
+        limits.check_content_length(&METADATA, &headers).unwrap();
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("10"));
+        limits.check_content_length(&METADATA, &headers).unwrap();
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("11"));
+        assert_matches!(
+            limits.check_content_length(&METADATA, &headers),
+            Err(RumaRejection::TooLarge { max_size: 10 })
+        );
+
+        let limits = limits.with_endpoint_max_body_size(&METADATA, 100);
+        limits.check_content_length(&METADATA, &headers).unwrap();
+    }
+
+    #[test]
+    fn endpoint_max_body_size_with_same_name() {
+        // Endpoints of different APIs can have the same name.
+        let federation_metadata = Metadata {
+            stable_path: Some("/_matrix/federation/v1/things"),
+            authentication: AuthScheme::ServerSignatures,
+            ..METADATA
+        };
+        let limits =
+            RequestLimits::new().with_max_body_size(10).with_endpoint_max_body_size(&METADATA, 100);
+
+        assert_eq!(limits.max_body_size(&METADATA), Some(100));
+        assert_eq!(limits.max_body_size(&federation_metadata), Some(10));
+    }
+}
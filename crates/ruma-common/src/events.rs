@@ -158,6 +158,7 @@ pub mod relation;
 pub mod room;
 pub mod room_key;
 pub mod room_key_request;
+pub mod room_state;
 pub mod secret;
 pub mod secret_storage;
 pub mod space;
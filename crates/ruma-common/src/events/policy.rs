@@ -1,3 +1,4 @@
 //! Modules for events in the `m.policy` namespace.
 
+pub mod list;
 pub mod rule;
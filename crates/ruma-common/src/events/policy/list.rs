@@ -0,0 +1,325 @@
+//! Evaluation of [moderation policy lists].
+//!
+//! A policy list is a room containing `m.policy.rule.*` state events. [`PolicyList`] collects the
+//! rules of one or more of those rooms and checks users, rooms and servers against them.
+//!
+//! [moderation policy lists]: https://spec.matrix.org/v1.2/client-server-api/#moderation-policy-lists
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::value::RawValue as RawJsonValue;
+
+use super::rule::{PolicyRuleEventContent, Recommendation};
+use crate::{
+    events::AnySyncStateEvent, push::Glob, serde::Raw, OwnedRoomId, RoomId, ServerName, UserId,
+};
+
+/// The policy rules of one or more policy list rooms.
+///
+/// Rules are added with [`ingest`](Self::ingest) or [`apply`](Self::apply). A rule is replaced by
+/// a later event with the same type and state key, and removed if that event is redacted or has
+/// an empty content.
+///
+/// The legacy `m.room.rule.*` and `org.matrix.mjolnir.rule.*` event types that are still used by
+/// some moderation tools are also supported.
+#[derive(Clone, Debug, Default)]
+pub struct PolicyList {
+    rules: BTreeMap<(OwnedRoomId, PolicyRuleKind, String), PolicyRule>,
+}
+
+impl PolicyList {
+    /// Creates an empty `PolicyList`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deserializes the given state event of the given room and updates the policy rules with it.
+    ///
+    /// Events that are not policy rules are ignored.
+    pub fn ingest(
+        &mut self,
+        room_id: &RoomId,
+        event: &Raw<AnySyncStateEvent>,
+    ) -> serde_json::Result<()> {
+        #[derive(Deserialize)]
+        struct PolicyRuleEventDeHelper<'a> {
+            #[serde(rename = "type", borrow)]
+            event_type: &'a str,
+            state_key: String,
+            #[serde(borrow)]
+            content: &'a RawJsonValue,
+        }
+
+        let PolicyRuleEventDeHelper { event_type, state_key, content } = event.deserialize_as()?;
+        let kind = match PolicyRuleKind::from_event_type(event_type) {
+            Some(kind) => kind,
+            None => return Ok(()),
+        };
+
+        // A rule is removed by replacing it with an event with an empty content, which can't be
+        // deserialized.
+        let content = serde_json::from_str::<PolicyRuleEventContent>(content.get()).ok();
+        self.update(room_id, kind, state_key, content);
+
+        Ok(())
+    }
+
+    /// Updates the policy rules with the given state event of the given room.
+    ///
+    /// Events that are not policy rules are ignored.
+    pub fn apply(&mut self, room_id: &RoomId, event: &AnySyncStateEvent) {
+        let (kind, content) = match event {
+            AnySyncStateEvent::PolicyRuleUser(ev) => {
+                (PolicyRuleKind::User, ev.as_original().map(|ev| &ev.content.0))
+            }
+            AnySyncStateEvent::PolicyRuleRoom(ev) => {
+                (PolicyRuleKind::Room, ev.as_original().map(|ev| &ev.content.0))
+            }
+            AnySyncStateEvent::PolicyRuleServer(ev) => {
+                (PolicyRuleKind::Server, ev.as_original().map(|ev| &ev.content.0))
+            }
+            _ => return,
+        };
+
+        self.update(room_id, kind, event.state_key().to_owned(), content.cloned());
+    }
+
+    fn update(
+        &mut self,
+        room_id: &RoomId,
+        kind: PolicyRuleKind,
+        state_key: String,
+        content: Option<PolicyRuleEventContent>,
+    ) {
+        let key = (room_id.to_owned(), kind, state_key);
+
+        match content {
+            Some(content) => {
+                let rule = PolicyRule::new(key.0.clone(), kind, content);
+                self.rules.insert(key, rule);
+            }
+            None => {
+                self.rules.remove(&key);
+            }
+        }
+    }
+
+    /// Removes all the rules of the given room, e.g. when unsubscribing from a policy list.
+    pub fn remove_room(&mut self, room_id: &RoomId) {
+        self.rules.retain(|(rule_room_id, _, _), _| rule_room_id != room_id);
+    }
+
+    /// Returns an iterator over all the rules.
+    pub fn iter(&self) -> impl Iterator<Item = &PolicyRule> {
+        self.rules.values()
+    }
+
+    /// Returns an iterator over the rules of the given kind whose entity matches the given value.
+    pub fn matching<'a>(
+        &'a self,
+        kind: PolicyRuleKind,
+        entity: &'a str,
+    ) -> impl Iterator<Item = &'a PolicyRule> {
+        self.iter().filter(move |rule| rule.kind == kind && rule.matches(entity))
+    }
+
+    /// Returns the first rule recommending to ban the given user, if any.
+    ///
+    /// Server rules matching the server of the user are also taken into account.
+    pub fn user_ban(&self, user_id: &UserId) -> Option<&PolicyRule> {
+        self.find_ban(PolicyRuleKind::User, user_id.as_str())
+            .or_else(|| self.server_ban(user_id.server_name()))
+    }
+
+    /// Returns the first rule recommending to ban the given room, if any.
+    pub fn room_ban(&self, room_id: &RoomId) -> Option<&PolicyRule> {
+        self.find_ban(PolicyRuleKind::Room, room_id.as_str())
+    }
+
+    /// Returns the first rule recommending to ban the given server, if any.
+    pub fn server_ban(&self, server_name: &ServerName) -> Option<&PolicyRule> {
+        self.find_ban(PolicyRuleKind::Server, server_name.as_str())
+    }
+
+    fn find_ban(&self, kind: PolicyRuleKind, entity: &str) -> Option<&PolicyRule> {
+        self.iter().find(|rule| rule.kind == kind && rule.is_ban() && rule.matches(entity))
+    }
+}
+
+/// A policy rule of a [`PolicyList`].
+#[derive(Clone, Debug)]
+pub struct PolicyRule {
+    room_id: OwnedRoomId,
+    kind: PolicyRuleKind,
+    content: PolicyRuleEventContent,
+    glob: Glob,
+}
+
+impl PolicyRule {
+    fn new(room_id: OwnedRoomId, kind: PolicyRuleKind, content: PolicyRuleEventContent) -> Self {
+        let glob = Glob::new(&content.entity);
+        Self { room_id, kind, content, glob }
+    }
+
+    /// The ID of the policy list room this rule comes from.
+    pub fn room_id(&self) -> &RoomId {
+        &self.room_id
+    }
+
+    /// The kind of entities this rule applies to.
+    pub fn kind(&self) -> PolicyRuleKind {
+        self.kind
+    }
+
+    /// The entity affected by this rule, as a glob pattern.
+    pub fn entity(&self) -> &str {
+        &self.content.entity
+    }
+
+    /// The suggested action to take.
+    pub fn recommendation(&self) -> &Recommendation {
+        &self.content.recommendation
+    }
+
+    /// The human-readable description for the recommendation.
+    pub fn reason(&self) -> &str {
+        &self.content.reason
+    }
+
+    /// Whether this rule recommends to ban the entities it matches.
+    pub fn is_ban(&self) -> bool {
+        self.content.recommendation == Recommendation::Ban
+    }
+
+    /// Whether the given entity matches the entity of this rule.
+    ///
+    /// Matching is case-insensitive, like for the glob patterns of push rules.
+    pub fn matches(&self, entity: &str) -> bool {
+        self.glob.matches(entity)
+    }
+}
+
+/// The kinds of entities policy rules apply to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::exhaustive_enums)]
+pub enum PolicyRuleKind {
+    /// Rules for users, from `m.policy.rule.user` events.
+    User,
+
+    /// Rules for rooms, from `m.policy.rule.room` events.
+    Room,
+
+    /// Rules for servers, from `m.policy.rule.server` events.
+    Server,
+}
+
+impl PolicyRuleKind {
+    fn from_event_type(event_type: &str) -> Option<Self> {
+        match event_type {
+            "m.policy.rule.user" | "m.room.rule.user" | "org.matrix.mjolnir.rule.user" => {
+                Some(Self::User)
+            }
+            "m.policy.rule.room" | "m.room.rule.room" | "org.matrix.mjolnir.rule.room" => {
+                Some(Self::Room)
+            }
+            "m.policy.rule.server" | "m.room.rule.server" | "org.matrix.mjolnir.rule.server" => {
+                Some(Self::Server)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as JsonValue};
+
+    use super::{PolicyList, PolicyRuleKind};
+    use crate::{events::AnySyncStateEvent, room_id, serde::Raw, server_name, user_id};
+
+    fn rule_event(event_type: &str, state_key: &str, content: JsonValue) -> Raw<AnySyncStateEvent> {
+        Raw::new(&json!({
+            "type": event_type,
+            "state_key": state_key,
+            "content": content,
+            "event_id": "$rule",
+            "sender": "@mod:localhost",
+            "origin_server_ts": 1,
+        }))
+        .unwrap()
+        .cast()
+    }
+
+    fn ban(entity: &str, reason: &str) -> JsonValue {
+        json!({ "entity": entity, "recommendation": "m.ban", "reason": reason })
+    }
+
+    #[test]
+    fn match_rules() {
+        let list_a = room_id!("!a:localhost");
+        let list_b = room_id!("!b:localhost");
+
+        let mut list = PolicyList::new();
+        list.ingest(
+            list_a,
+            &rule_event("m.policy.rule.user", "1", ban("@spam*:example.org", "spam")),
+        )
+        .unwrap();
+        list.ingest(list_a, &rule_event("m.policy.rule.server", "2", ban("*.evil.com", "evil")))
+            .unwrap();
+        list.ingest(
+            list_b,
+            &rule_event("org.matrix.mjolnir.rule.room", "3", ban("!bad:localhost", "bad")),
+        )
+        .unwrap();
+        list.ingest(list_b, &rule_event("m.room.topic", "", json!({ "topic": "Rules" }))).unwrap();
+        assert_eq!(list.iter().count(), 3);
+
+        let rule = list.user_ban(user_id!("@spammer:example.org")).unwrap();
+        assert_eq!(rule.reason(), "spam");
+        assert_eq!(rule.room_id(), list_a);
+        assert_eq!(rule.kind(), PolicyRuleKind::User);
+        assert!(list.user_ban(user_id!("@alice:example.org")).is_none());
+
+        let rule = list.user_ban(user_id!("@alice:chat.evil.com")).unwrap();
+        assert_eq!(rule.reason(), "evil");
+        assert!(list.server_ban(server_name!("CHAT.EVIL.COM")).is_some());
+        assert!(list.server_ban(server_name!("evil.com")).is_none());
+
+        assert_eq!(list.room_ban(room_id!("!bad:localhost")).unwrap().room_id(), list_b);
+
+        list.remove_room(list_b);
+        assert!(list.room_ban(room_id!("!bad:localhost")).is_none());
+    }
+
+    #[test]
+    fn replace_and_remove_rules() {
+        let room_id = room_id!("!a:localhost");
+
+        let mut list = PolicyList::new();
+        list.ingest(room_id, &rule_event("m.policy.rule.user", "1", ban("@alice:*", "spam")))
+            .unwrap();
+        assert!(list.user_ban(user_id!("@alice:localhost")).is_some());
+
+        list.ingest(room_id, &rule_event("m.policy.rule.user", "1", ban("@bob:*", "spam")))
+            .unwrap();
+        assert!(list.user_ban(user_id!("@alice:localhost")).is_none());
+        assert!(list.user_ban(user_id!("@bob:localhost")).is_some());
+
+        list.ingest(room_id, &rule_event("m.policy.rule.user", "1", json!({}))).unwrap();
+        assert!(list.user_ban(user_id!("@bob:localhost")).is_none());
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn apply_typed_events() {
+        let room_id = room_id!("!a:localhost");
+
+        let mut list = PolicyList::new();
+        let event =
+            rule_event("m.policy.rule.user", "1", ban("@alice:*", "spam")).deserialize().unwrap();
+        list.apply(room_id, &event);
+        assert!(list.user_ban(user_id!("@alice:localhost")).is_some());
+    }
+}
@@ -22,6 +22,8 @@ use crate::{
 };
 
 pub mod aliases;
+#[cfg(feature = "attachment-encryption")]
+pub mod attachment;
 pub mod avatar;
 pub mod canonical_alias;
 pub mod create;
@@ -36,6 +38,7 @@ pub mod name;
 pub mod pinned_events;
 pub mod power_levels;
 pub mod redaction;
+pub mod routing;
 pub mod server_acl;
 pub mod third_party_invite;
 mod thumbnail_source_serde;
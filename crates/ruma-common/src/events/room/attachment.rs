@@ -0,0 +1,299 @@
+//! Encryption and decryption of [attachments] in end-to-end encrypted rooms.
+//!
+//! Attachments are encrypted with AES-CTR using a 256-bit key and a 128-bit counter block whose
+//! lower 64 bits are zero. The SHA-256 hash of the ciphertext is sent alongside the key in the
+//! resulting [`EncryptedFile`].
+//!
+//! Both [`AttachmentEncryptor`] and [`AttachmentDecryptor`] wrap a [`Read`] implementation, so
+//! arbitrarily large files can be processed without being held in memory in full.
+//!
+//! [attachments]: https://spec.matrix.org/v1.2/client-server-api/#sending-encrypted-attachments
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read},
+};
+
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes256,
+};
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+
+use super::{EncryptedFile, EncryptedFileInit, JsonWebKey, JsonWebKeyInit};
+use crate::{serde::Base64, OwnedMxcUri};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+const KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+const HASH_ALGORITHM: &str = "sha256";
+const KEY_ALGORITHM: &str = "A256CTR";
+const KEY_TYPE: &str = "oct";
+const VERSION: &str = "v2";
+
+/// A [`Read`] adapter that encrypts the data read from the inner reader.
+///
+/// The ciphertext can be uploaded with `media::create_content` once it has been read in full. The
+/// resulting content URI is then passed to [`finish`](Self::finish) to get the [`EncryptedFile`]
+/// that is sent in the event.
+pub struct AttachmentEncryptor<R> {
+    inner: R,
+    cipher: Aes256Ctr,
+    sha: Sha256,
+    key: [u8; KEY_SIZE],
+    iv: [u8; IV_SIZE],
+}
+
+impl<R: Read> AttachmentEncryptor<R> {
+    /// Creates a new `AttachmentEncryptor` encrypting the plaintext read from `inner` with a
+    /// freshly generated key.
+    pub fn new(inner: R) -> Self {
+        let mut rng = thread_rng();
+
+        let mut key = [0; KEY_SIZE];
+        rng.fill_bytes(&mut key);
+
+        // Only the upper 64 bits are random, the lower 64 bits are the counter and start at zero
+        // to avoid overflowing it for large files.
+        let mut iv = [0; IV_SIZE];
+        rng.fill_bytes(&mut iv[..8]);
+
+        let cipher = Aes256Ctr::new(&key.into(), &iv.into());
+
+        Self { inner, cipher, sha: Sha256::new(), key, iv }
+    }
+
+    /// Consumes this `AttachmentEncryptor` and returns the information needed to decrypt the
+    /// attachment uploaded at the given URL.
+    ///
+    /// This should only be called once all of the data has been read, otherwise the hash will not
+    /// match the uploaded ciphertext.
+    pub fn finish(self, url: OwnedMxcUri) -> EncryptedFile {
+        let key: JsonWebKey = JsonWebKeyInit {
+            kty: KEY_TYPE.to_owned(),
+            key_ops: vec!["encrypt".to_owned(), "decrypt".to_owned()],
+            alg: KEY_ALGORITHM.to_owned(),
+            k: Base64::new(self.key.to_vec()),
+            ext: true,
+        }
+        .into();
+
+        let hashes = BTreeMap::from([(
+            HASH_ALGORITHM.to_owned(),
+            Base64::new(self.sha.finalize().to_vec()),
+        )]);
+
+        EncryptedFileInit {
+            url,
+            key,
+            iv: Base64::new(self.iv.to_vec()),
+            hashes,
+            v: VERSION.to_owned(),
+        }
+        .into()
+    }
+}
+
+impl<R: Read> Read for AttachmentEncryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let ciphertext = &mut buf[..read];
+
+        self.cipher.apply_keystream(ciphertext);
+        self.sha.update(ciphertext);
+
+        Ok(read)
+    }
+}
+
+impl<R> fmt::Debug for AttachmentEncryptor<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttachmentEncryptor").finish_non_exhaustive()
+    }
+}
+
+/// A [`Read`] adapter that decrypts the data read from the inner reader.
+///
+/// The hash of the ciphertext is checked once the inner reader is exhausted. If it doesn't match
+/// the one in the [`EncryptedFile`], the last call to [`read`](Read::read) returns an error of
+/// kind [`io::ErrorKind::InvalidData`] that wraps [`AttachmentDecryptionError::HashMismatch`].
+///
+/// Since the plaintext is returned before the hash can be checked, callers should not use any of
+/// the data until the reader has returned `Ok(0)`.
+pub struct AttachmentDecryptor<R> {
+    inner: R,
+    cipher: Aes256Ctr,
+    sha: Sha256,
+    expected_hash: Vec<u8>,
+    verified: bool,
+}
+
+impl<R: Read> AttachmentDecryptor<R> {
+    /// Creates a new `AttachmentDecryptor` decrypting the ciphertext read from `inner` with the
+    /// given encryption info.
+    ///
+    /// Returns an error if the encryption info uses an unsupported version or algorithm, or if
+    /// the key or counter block doesn't have the expected length.
+    pub fn new(inner: R, file: &EncryptedFile) -> Result<Self, AttachmentDecryptionError> {
+        if file.v != VERSION {
+            return Err(AttachmentDecryptionError::UnsupportedVersion(file.v.clone()));
+        }
+
+        if file.key.kty != KEY_TYPE {
+            return Err(AttachmentDecryptionError::UnsupportedKeyType(file.key.kty.clone()));
+        }
+
+        if file.key.alg != KEY_ALGORITHM {
+            return Err(AttachmentDecryptionError::UnsupportedAlgorithm(file.key.alg.clone()));
+        }
+
+        let key: [u8; KEY_SIZE] = file
+            .key
+            .k
+            .as_bytes()
+            .try_into()
+            .map_err(|_| AttachmentDecryptionError::InvalidKeyLength)?;
+        let iv: [u8; IV_SIZE] = file
+            .iv
+            .as_bytes()
+            .try_into()
+            .map_err(|_| AttachmentDecryptionError::InvalidIvLength)?;
+
+        let expected_hash = file
+            .hashes
+            .get(HASH_ALGORITHM)
+            .ok_or(AttachmentDecryptionError::MissingHash)?
+            .as_bytes()
+            .to_owned();
+
+        let cipher = Aes256Ctr::new(&key.into(), &iv.into());
+
+        Ok(Self { inner, cipher, sha: Sha256::new(), expected_hash, verified: false })
+    }
+}
+
+impl<R: Read> Read for AttachmentDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+
+        if read == 0 && !buf.is_empty() && !self.verified {
+            if self.sha.finalize_reset().as_slice() != self.expected_hash {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    AttachmentDecryptionError::HashMismatch,
+                ));
+            }
+
+            self.verified = true;
+        }
+
+        let data = &mut buf[..read];
+        self.sha.update(&*data);
+        self.cipher.apply_keystream(data);
+
+        Ok(read)
+    }
+}
+
+impl<R> fmt::Debug for AttachmentDecryptor<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttachmentDecryptor").finish_non_exhaustive()
+    }
+}
+
+/// An error encountered when trying to decrypt an attachment.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum AttachmentDecryptionError {
+    /// The version of the encrypted attachments protocol is not supported.
+    #[error("unsupported encrypted attachments version `{0}`")]
+    UnsupportedVersion(String),
+
+    /// The type of the key is not supported.
+    #[error("unsupported key type `{0}`")]
+    UnsupportedKeyType(String),
+
+    /// The algorithm of the key is not supported.
+    #[error("unsupported key algorithm `{0}`")]
+    UnsupportedAlgorithm(String),
+
+    /// The key doesn't have a length of 256 bits.
+    #[error("invalid key length")]
+    InvalidKeyLength,
+
+    /// The counter block doesn't have a length of 128 bits.
+    #[error("invalid counter block length")]
+    InvalidIvLength,
+
+    /// There is no SHA-256 hash of the ciphertext.
+    #[error("missing SHA-256 hash")]
+    MissingHash,
+
+    /// The SHA-256 hash of the ciphertext doesn't match the expected one.
+    #[error("hash mismatch")]
+    HashMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use assert_matches::assert_matches;
+
+    use super::{AttachmentDecryptionError, AttachmentDecryptor, AttachmentEncryptor};
+    use crate::{mxc_uri, serde::Base64};
+
+    const PLAINTEXT: &[u8] = b"It's a secret to everybody";
+
+    #[test]
+    fn encrypt_decrypt_roundtrip() {
+        let mut encryptor = AttachmentEncryptor::new(PLAINTEXT);
+        let mut ciphertext = Vec::new();
+        encryptor.read_to_end(&mut ciphertext).unwrap();
+        assert_ne!(ciphertext, PLAINTEXT);
+
+        let file = encryptor.finish(mxc_uri!("mxc://localhost/encrypted").to_owned());
+        assert_eq!(file.v, "v2");
+        assert_eq!(file.key.alg, "A256CTR");
+        assert_eq!(file.key.k.as_bytes().len(), 32);
+        assert_eq!(&file.iv.as_bytes()[8..], [0; 8]);
+
+        let mut decryptor = AttachmentDecryptor::new(ciphertext.as_slice(), &file).unwrap();
+        let mut plaintext = Vec::new();
+        decryptor.read_to_end(&mut plaintext).unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn decrypt_hash_mismatch() {
+        let mut encryptor = AttachmentEncryptor::new(PLAINTEXT);
+        let mut ciphertext = Vec::new();
+        encryptor.read_to_end(&mut ciphertext).unwrap();
+        let file = encryptor.finish(mxc_uri!("mxc://localhost/encrypted").to_owned());
+
+        ciphertext[0] ^= 1;
+
+        let mut decryptor = AttachmentDecryptor::new(ciphertext.as_slice(), &file).unwrap();
+        let err = decryptor.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_matches!(
+            err.into_inner().unwrap().downcast_ref(),
+            Some(AttachmentDecryptionError::HashMismatch)
+        );
+    }
+
+    #[test]
+    fn decrypt_invalid_key() {
+        let encryptor = AttachmentEncryptor::new(PLAINTEXT);
+        let mut file = encryptor.finish(mxc_uri!("mxc://localhost/encrypted").to_owned());
+        file.key.k = Base64::new(vec![0; 16]);
+
+        assert_matches!(
+            AttachmentDecryptor::new(&[][..], &file),
+            Err(AttachmentDecryptionError::InvalidKeyLength)
+        );
+    }
+}
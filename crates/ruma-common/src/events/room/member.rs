@@ -19,9 +19,13 @@ use crate::{
 };
 
 mod change;
+mod roster;
 
 use self::change::membership_change;
-pub use self::change::{Change, MembershipChange, MembershipDetails};
+pub use self::{
+    change::{Change, MembershipChange, MembershipDetails},
+    roster::MemberRoster,
+};
 
 /// The content of an `m.room.member` event.
 ///
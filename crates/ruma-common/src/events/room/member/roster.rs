@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+
+use super::{MembershipState, StrippedRoomMemberEvent, SyncRoomMemberEvent};
+use crate::{events::SyncStateEvent, serde::Raw, MxcUri, OwnedMxcUri, OwnedUserId, UserId};
+
+/// The members of a room, tracked from `m.room.member` events.
+///
+/// Besides the membership of every user, this keeps track of the display names that are in use by
+/// joined and invited members, to implement the spec's [display name disambiguation]: if several
+/// members use the same display name, their user ID is appended to it.
+///
+/// Display names are sanitized by removing bidirectional control characters and zero-width
+/// characters, and names that only differ by characters that look alike (e.g. a Latin `a` and a
+/// Cyrillic `а`) or by case are considered the same name.
+///
+/// [display name disambiguation]: https://spec.matrix.org/v1.2/client-server-api/#calculating-the-display-name-for-a-user
+#[derive(Clone, Debug, Default)]
+pub struct MemberRoster {
+    members: BTreeMap<OwnedUserId, RosterEntry>,
+
+    /// The number of joined or invited members using a display name, keyed by its skeleton.
+    name_counts: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug)]
+struct RosterEntry {
+    membership: MembershipState,
+    display_name: Option<String>,
+    avatar_url: Option<OwnedMxcUri>,
+}
+
+impl RosterEntry {
+    fn is_active(&self) -> bool {
+        matches!(self.membership, MembershipState::Join | MembershipState::Invite)
+    }
+
+    fn skeleton(&self) -> Option<String> {
+        self.display_name.as_deref().filter(|_| self.is_active()).map(display_name_skeleton)
+    }
+}
+
+impl MemberRoster {
+    /// Creates an empty `MemberRoster`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deserializes the given `m.room.member` event and adds it to the roster.
+    pub fn ingest(&mut self, event: &Raw<SyncRoomMemberEvent>) -> serde_json::Result<()> {
+        self.apply(&event.deserialize()?);
+        Ok(())
+    }
+
+    /// Adds the given `m.room.member` event to the roster.
+    ///
+    /// The event replaces any previous membership of the same user.
+    pub fn apply(&mut self, event: &SyncRoomMemberEvent) {
+        let entry = match event {
+            SyncStateEvent::Original(ev) => RosterEntry {
+                membership: ev.content.membership.clone(),
+                display_name: ev.content.displayname.as_deref().and_then(sanitize_display_name),
+                avatar_url: ev.content.avatar_url.clone(),
+            },
+            SyncStateEvent::Redacted(ev) => RosterEntry {
+                membership: ev.content.membership.clone(),
+                display_name: None,
+                avatar_url: None,
+            },
+        };
+
+        self.insert(event.state_key().clone(), entry);
+    }
+
+    /// Adds the given stripped `m.room.member` event to the roster.
+    ///
+    /// This is useful to show the members of a room the user is invited to.
+    pub fn apply_stripped(&mut self, event: &StrippedRoomMemberEvent) {
+        let entry = RosterEntry {
+            membership: event.content.membership.clone(),
+            display_name: event.content.displayname.as_deref().and_then(sanitize_display_name),
+            avatar_url: event.content.avatar_url.clone(),
+        };
+
+        self.insert(event.state_key.clone(), entry);
+    }
+
+    fn insert(&mut self, user_id: OwnedUserId, entry: RosterEntry) {
+        if let Some(skeleton) = entry.skeleton() {
+            *self.name_counts.entry(skeleton).or_default() += 1;
+        }
+
+        if let Some(skeleton) = self.members.insert(user_id, entry).and_then(|prev| prev.skeleton())
+        {
+            if let Some(count) = self.name_counts.get_mut(&skeleton) {
+                *count -= 1;
+                if *count == 0 {
+                    self.name_counts.remove(&skeleton);
+                }
+            }
+        }
+    }
+
+    /// Returns the membership state of the given user, if they are part of the roster.
+    pub fn membership(&self, user_id: &UserId) -> Option<&MembershipState> {
+        self.members.get(user_id).map(|entry| &entry.membership)
+    }
+
+    /// Returns the sanitized display name of the given user, if they set one.
+    ///
+    /// This is the name that should be matched against by the `ContainsDisplayName` push
+    /// condition, see [`PushConditionRoomCtx::user_display_name`].
+    ///
+    /// [`PushConditionRoomCtx::user_display_name`]: crate::push::PushConditionRoomCtx::user_display_name
+    pub fn display_name(&self, user_id: &UserId) -> Option<&str> {
+        self.members.get(user_id)?.display_name.as_deref()
+    }
+
+    /// Returns the name of the given user that should be displayed in the room.
+    ///
+    /// This is the user's display name if it is unique among the joined and invited members,
+    /// the display name followed by the user ID in parentheses if it isn't, or the user ID if the
+    /// user doesn't have a display name.
+    pub fn disambiguated_display_name(&self, user_id: &UserId) -> String {
+        let display_name = match self.display_name(user_id) {
+            Some(display_name) => display_name,
+            None => return user_id.to_string(),
+        };
+
+        let count = self.name_counts.get(&display_name_skeleton(display_name)).copied();
+        if count.unwrap_or(0) > 1 {
+            format!("{} ({})", display_name, user_id)
+        } else {
+            display_name.to_owned()
+        }
+    }
+
+    /// Returns the avatar URL of the given user, if they set one.
+    pub fn avatar_url(&self, user_id: &UserId) -> Option<&MxcUri> {
+        self.members.get(user_id)?.avatar_url.as_deref()
+    }
+
+    /// Returns an iterator over the users in the roster and their membership, ordered by user ID.
+    pub fn iter(&self) -> impl Iterator<Item = (&UserId, &MembershipState)> {
+        self.members.iter().map(|(user_id, entry)| (&**user_id, &entry.membership))
+    }
+
+    /// Returns an iterator over the users with the given membership, ordered by user ID.
+    pub fn with_membership<'a>(
+        &'a self,
+        membership: &'a MembershipState,
+    ) -> impl Iterator<Item = &'a UserId> {
+        self.iter().filter(move |(_, m)| *m == membership).map(|(user_id, _)| user_id)
+    }
+
+    /// Returns the number of joined members.
+    pub fn joined_member_count(&self) -> usize {
+        self.with_membership(&MembershipState::Join).count()
+    }
+
+    /// Returns the number of invited members.
+    pub fn invited_member_count(&self) -> usize {
+        self.with_membership(&MembershipState::Invite).count()
+    }
+}
+
+/// Removes bidirectional control characters and zero-width characters from the given display
+/// name.
+///
+/// Returns `None` if the display name is empty afterwards.
+fn sanitize_display_name(name: &str) -> Option<String> {
+    let name: String = name.chars().filter(|c| !is_hidden_char(*c)).collect();
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+fn is_hidden_char(c: char) -> bool {
+    matches!(
+        c,
+        // Arabic letter mark
+        '\u{061C}'
+        // Mongolian vowel separator
+        | '\u{180E}'
+        // Zero-width space, non-joiner and joiner, left-to-right and right-to-left marks
+        | '\u{200B}'..='\u{200F}'
+        // Bidirectional embeddings and overrides
+        | '\u{202A}'..='\u{202E}'
+        // Word joiner and invisible operators
+        | '\u{2060}'..='\u{2064}'
+        // Bidirectional isolates
+        | '\u{2066}'..='\u{2069}'
+        // Zero-width no-break space
+        | '\u{FEFF}'
+    )
+}
+
+/// Computes a representation of the given sanitized display name where characters that look
+/// alike are mapped to the same character, for detecting names that are visually ambiguous.
+///
+/// The name is lowercased first, so names that only differ by case are ambiguous too.
+fn display_name_skeleton(name: &str) -> String {
+    name.chars().flat_map(char::to_lowercase).map(unconfuse_char).collect()
+}
+
+/// Maps lowercase characters that are commonly confused with ASCII characters to that ASCII
+/// character.
+fn unconfuse_char(c: char) -> char {
+    match c {
+        // Fullwidth forms of ASCII characters.
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).map_or(c, unconfuse_char),
+        // Characters that look like a lowercase L, or like an uppercase I before lowercasing.
+        'i' | '1' | '|' | 'ı' | 'ι' | 'і' | 'ӏ' | 'ǀ' => 'l',
+        '0' | 'о' | 'ο' => 'o',
+        'а' | 'α' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ε' => 'e',
+        'ɡ' => 'g',
+        'һ' | 'н' | 'η' => 'h',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'м' | 'μ' => 'm',
+        'ν' => 'v',
+        'р' | 'ρ' => 'p',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'х' | 'χ' => 'x',
+        'у' | 'υ' => 'y',
+        'ζ' => 'z',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::MemberRoster;
+    use crate::{events::room::member::MembershipState, serde::Raw, user_id};
+
+    fn roster(members: &[(&str, &str, Option<&str>)]) -> MemberRoster {
+        let mut roster = MemberRoster::new();
+        for (user_id, membership, displayname) in members {
+            let event = json!({
+                "type": "m.room.member",
+                "state_key": user_id,
+                "content": { "membership": membership, "displayname": displayname },
+                "event_id": format!("${}", user_id.replace(':', "_")),
+                "sender": user_id,
+                "origin_server_ts": 1,
+            });
+            roster.ingest(&Raw::new(&event).unwrap().cast()).unwrap();
+        }
+        roster
+    }
+
+    #[test]
+    fn unique_display_names() {
+        let roster = roster(&[
+            ("@alice:localhost", "join", Some("Alice")),
+            ("@bob:localhost", "invite", Some("Bob")),
+            ("@carl:localhost", "join", None),
+        ]);
+
+        assert_eq!(roster.disambiguated_display_name(user_id!("@alice:localhost")), "Alice");
+        assert_eq!(roster.disambiguated_display_name(user_id!("@bob:localhost")), "Bob");
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@carl:localhost")),
+            "@carl:localhost"
+        );
+        assert_eq!(roster.disambiguated_display_name(user_id!("@dan:localhost")), "@dan:localhost");
+        assert_eq!(roster.joined_member_count(), 2);
+        assert_eq!(roster.invited_member_count(), 1);
+    }
+
+    #[test]
+    fn duplicate_display_names() {
+        let mut roster = roster(&[
+            ("@alice:localhost", "join", Some("Alice")),
+            ("@alice:example.org", "join", Some("Alice")),
+            ("@bob:localhost", "leave", Some("Bob")),
+            ("@bob:example.org", "join", Some("Bob")),
+        ]);
+
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@alice:localhost")),
+            "Alice (@alice:localhost)"
+        );
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@alice:example.org")),
+            "Alice (@alice:example.org)"
+        );
+        // Members that left don't count.
+        assert_eq!(roster.disambiguated_display_name(user_id!("@bob:example.org")), "Bob");
+
+        roster = self::roster(&[
+            ("@alice:localhost", "join", Some("Alice")),
+            ("@alice:example.org", "join", Some("Alice")),
+            ("@alice:example.org", "leave", Some("Alice")),
+        ]);
+        assert_eq!(roster.disambiguated_display_name(user_id!("@alice:localhost")), "Alice");
+        assert_eq!(
+            roster.membership(user_id!("@alice:example.org")),
+            Some(&MembershipState::Leave)
+        );
+    }
+
+    #[test]
+    fn confusable_display_names() {
+        let roster = roster(&[
+            ("@alice:localhost", "join", Some("Alice")),
+            // Cyrillic А and zero-width space
+            ("@mallory:localhost", "join", Some("\u{0410}li\u{200B}ce")),
+            ("@bob:localhost", "join", Some("\u{202E}Bob\u{202C}")),
+        ]);
+
+        assert_eq!(roster.display_name(user_id!("@mallory:localhost")), Some("\u{0410}lice"));
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@alice:localhost")),
+            "Alice (@alice:localhost)"
+        );
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@mallory:localhost")),
+            "\u{0410}lice (@mallory:localhost)"
+        );
+        assert_eq!(roster.disambiguated_display_name(user_id!("@bob:localhost")), "Bob");
+    }
+
+    #[test]
+    fn display_names_differing_by_case() {
+        let roster = roster(&[
+            ("@ian:localhost", "join", Some("Ian")),
+            ("@mallory:localhost", "join", Some("ian")),
+            ("@lan:localhost", "join", Some("lan")),
+            ("@bob:localhost", "join", Some("Bob")),
+        ]);
+
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@ian:localhost")),
+            "Ian (@ian:localhost)"
+        );
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@mallory:localhost")),
+            "ian (@mallory:localhost)"
+        );
+        assert_eq!(
+            roster.disambiguated_display_name(user_id!("@lan:localhost")),
+            "lan (@lan:localhost)"
+        );
+        assert_eq!(roster.disambiguated_display_name(user_id!("@bob:localhost")), "Bob");
+    }
+}
@@ -2,15 +2,19 @@
 //!
 //! [`m.room.power_levels`]: https://spec.matrix.org/v1.2/client-server-api/#mroompower_levels
 
-use std::{cmp::max, collections::BTreeMap};
+use std::{
+    cmp::max,
+    collections::{BTreeMap, BTreeSet},
+};
 
 use js_int::{int, Int};
 use ruma_macros::EventContent;
-use serde::{Deserialize, Serialize};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
 
 use crate::{
     events::{EmptyStateKey, MessageLikeEventType, RoomEventType, StateEventType},
     power_levels::{default_power_level, NotificationPowerLevels},
+    serde::Raw,
     OwnedUserId, UserId,
 };
 
@@ -157,6 +161,20 @@ impl RoomPowerLevelsEventContent {
             notifications: NotificationPowerLevels::default(),
         }
     }
+
+    /// Get the list of changes between the levels that are set explicitly in the given raw
+    /// `m.room.power_levels` contents, as needed for the [authorization rules].
+    ///
+    /// Unchanged levels are not part of the list. Unlike [`RoomPowerLevels::diff`], a level that
+    /// is only added or removed at its default value is a change.
+    ///
+    /// Returns an error if one of the contents doesn't deserialize to a
+    /// `RoomPowerLevelsEventContent`.
+    ///
+    /// [authorization rules]: https://spec.matrix.org/v1.2/rooms/v9/#authorization-rules
+    pub fn diff_raw(old: &Raw<Self>, new: &Raw<Self>) -> serde_json::Result<Vec<PowerLevelChange>> {
+        Ok(ExplicitPowerLevels::from_raw(old)?.diff(&ExplicitPowerLevels::from_raw(new)?))
+    }
 }
 
 impl Default for RoomPowerLevelsEventContent {
@@ -286,6 +304,248 @@ impl RoomPowerLevels {
     pub fn max(&self) -> Int {
         self.users.values().fold(self.users_default, |max_pl, user_pl| max(max_pl, *user_pl))
     }
+
+    /// Get the list of changes between `self` and the given new power levels.
+    ///
+    /// Unchanged levels are not part of the list. Since `RoomPowerLevels` has the defaults
+    /// applied, a level that is set explicitly to its default value is not a change.
+    pub fn diff(&self, new: &RoomPowerLevels) -> Vec<PowerLevelChange> {
+        ExplicitPowerLevels::from(self).diff(&ExplicitPowerLevels::from(new))
+    }
+
+    /// Check whether the given user is allowed to replace `self` with the given new power
+    /// levels, according to the [authorization rules] of `m.room.power_levels` events.
+    ///
+    /// The user must already be allowed to send `m.room.power_levels` events, this is not checked
+    /// here.
+    ///
+    /// This compares the effective power levels, after defaults are applied, so it is meant for
+    /// validating a change before sending it. The authorization of events compares the levels
+    /// that are set explicitly in the contents instead, with
+    /// [`RoomPowerLevelsEventContent::diff_raw`].
+    ///
+    /// [authorization rules]: https://spec.matrix.org/v1.2/rooms/v9/#authorization-rules
+    pub fn check_change(
+        &self,
+        sender: &UserId,
+        new: &RoomPowerLevels,
+    ) -> Result<(), PowerLevelChangeError> {
+        let sender_level = self.for_user(sender);
+        self.diff(new).iter().try_for_each(|change| change.check(sender, sender_level))
+    }
+}
+
+/// The power levels that are set in an `m.room.power_levels` content.
+///
+/// This is where the changes between two contents are computed, whether the defaults are applied
+/// or not.
+struct ExplicitPowerLevels {
+    ban: Option<Int>,
+    events: BTreeMap<RoomEventType, Int>,
+    events_default: Option<Int>,
+    invite: Option<Int>,
+    kick: Option<Int>,
+    redact: Option<Int>,
+    state_default: Option<Int>,
+    users: BTreeMap<OwnedUserId, Int>,
+    users_default: Option<Int>,
+    notifications_room: Option<Int>,
+}
+
+impl ExplicitPowerLevels {
+    fn from_raw(content: &Raw<RoomPowerLevelsEventContent>) -> serde_json::Result<Self> {
+        // The keys that are present in the content, the values are deserialized with
+        // `RoomPowerLevelsEventContent` so they follow the same rules.
+        #[derive(Deserialize)]
+        struct SetLevels {
+            ban: Option<IgnoredAny>,
+            events_default: Option<IgnoredAny>,
+            invite: Option<IgnoredAny>,
+            kick: Option<IgnoredAny>,
+            redact: Option<IgnoredAny>,
+            state_default: Option<IgnoredAny>,
+            users_default: Option<IgnoredAny>,
+            notifications: Option<SetNotificationLevels>,
+        }
+
+        #[derive(Deserialize)]
+        struct SetNotificationLevels {
+            room: Option<IgnoredAny>,
+        }
+
+        let levels = content.deserialize()?;
+        let set = content.deserialize_as::<SetLevels>()?;
+        let explicit = |key: Option<IgnoredAny>, level: Int| key.map(|_| level);
+
+        Ok(Self {
+            ban: explicit(set.ban, levels.ban),
+            events: levels.events,
+            events_default: explicit(set.events_default, levels.events_default),
+            invite: explicit(set.invite, levels.invite),
+            kick: explicit(set.kick, levels.kick),
+            redact: explicit(set.redact, levels.redact),
+            state_default: explicit(set.state_default, levels.state_default),
+            users: levels.users,
+            users_default: explicit(set.users_default, levels.users_default),
+            notifications_room: explicit(
+                set.notifications.and_then(|n| n.room),
+                levels.notifications.room,
+            ),
+        })
+    }
+
+    fn diff(&self, new: &Self) -> Vec<PowerLevelChange> {
+        let mut changes = Vec::new();
+
+        let levels = [
+            (PowerLevelTarget::Ban, self.ban, new.ban),
+            (PowerLevelTarget::EventsDefault, self.events_default, new.events_default),
+            (PowerLevelTarget::Invite, self.invite, new.invite),
+            (PowerLevelTarget::Kick, self.kick, new.kick),
+            (PowerLevelTarget::Redact, self.redact, new.redact),
+            (PowerLevelTarget::StateDefault, self.state_default, new.state_default),
+            (PowerLevelTarget::UsersDefault, self.users_default, new.users_default),
+            (
+                PowerLevelTarget::Notification(NotificationPowerLevelType::Room),
+                self.notifications_room,
+                new.notifications_room,
+            ),
+        ];
+        for (target, old, new) in levels {
+            if old != new {
+                changes.push(PowerLevelChange { target, old, new });
+            }
+        }
+
+        let event_types: BTreeSet<_> = self.events.keys().chain(new.events.keys()).collect();
+        for event_type in event_types {
+            let old = self.events.get(event_type).copied();
+            let new = new.events.get(event_type).copied();
+            if old != new {
+                let target = PowerLevelTarget::Event(event_type.clone());
+                changes.push(PowerLevelChange { target, old, new });
+            }
+        }
+
+        let user_ids: BTreeSet<_> = self.users.keys().chain(new.users.keys()).collect();
+        for user_id in user_ids {
+            let old = self.users.get(user_id).copied();
+            let new = new.users.get(user_id).copied();
+            if old != new {
+                let target = PowerLevelTarget::User(user_id.clone());
+                changes.push(PowerLevelChange { target, old, new });
+            }
+        }
+
+        changes
+    }
+}
+
+impl From<&RoomPowerLevels> for ExplicitPowerLevels {
+    fn from(levels: &RoomPowerLevels) -> Self {
+        Self {
+            ban: Some(levels.ban),
+            events: levels.events.clone(),
+            events_default: Some(levels.events_default),
+            invite: Some(levels.invite),
+            kick: Some(levels.kick),
+            redact: Some(levels.redact),
+            state_default: Some(levels.state_default),
+            users: levels.users.clone(),
+            users_default: Some(levels.users_default),
+            notifications_room: Some(levels.notifications.room),
+        }
+    }
+}
+
+/// The change of a single power level between two `m.room.power_levels` contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct PowerLevelChange {
+    /// The power level that changed.
+    pub target: PowerLevelTarget,
+
+    /// The previous value of the power level, if it was set.
+    pub old: Option<Int>,
+
+    /// The new value of the power level, if it is set.
+    pub new: Option<Int>,
+}
+
+impl PowerLevelChange {
+    /// Check whether a user with the given power level is allowed to make this change.
+    ///
+    /// Neither the previous nor the new value can be higher than the sender's level, and the
+    /// sender cannot change the level of another user that has the same level as they do.
+    pub fn check(&self, sender: &UserId, sender_level: Int) -> Result<(), PowerLevelChangeError> {
+        if let PowerLevelTarget::User(user_id) = &self.target {
+            if user_id != sender && self.old == Some(sender_level) {
+                return Err(PowerLevelChangeError::SameLevelAsSender(self.target.clone()));
+            }
+        }
+
+        if self.old.map_or(false, |old| old > sender_level) {
+            return Err(PowerLevelChangeError::OldLevelTooHigh(self.target.clone()));
+        }
+
+        if self.new.map_or(false, |new| new > sender_level) {
+            return Err(PowerLevelChangeError::NewLevelTooHigh(self.target.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// A power level in an `m.room.power_levels` event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PowerLevelTarget {
+    /// The level required to ban a user.
+    Ban,
+
+    /// The default level required to send message events.
+    EventsDefault,
+
+    /// The level required to invite a user.
+    Invite,
+
+    /// The level required to kick a user.
+    Kick,
+
+    /// The level required to redact an event.
+    Redact,
+
+    /// The default level required to send state events.
+    StateDefault,
+
+    /// The default power level for every user in the room.
+    UsersDefault,
+
+    /// The level required to send a specific event type.
+    Event(RoomEventType),
+
+    /// The power level of a specific user.
+    User(OwnedUserId),
+
+    /// The level required to trigger a specific notification type.
+    Notification(NotificationPowerLevelType),
+}
+
+/// An error encountered when a user is not allowed to change power levels.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum PowerLevelChangeError {
+    /// The previous value of the power level is higher than the sender's power level.
+    #[error("the previous power level of {0:?} is higher than the sender's")]
+    OldLevelTooHigh(PowerLevelTarget),
+
+    /// The new value of the power level is higher than the sender's power level.
+    #[error("the new power level of {0:?} is higher than the sender's")]
+    NewLevelTooHigh(PowerLevelTarget),
+
+    /// The power level of another user with the same power level as the sender was changed.
+    #[error("the power level of {0:?} is the same as the sender's")]
+    SameLevelAsSender(PowerLevelTarget),
 }
 
 impl From<RoomPowerLevelsEventContent> for RoomPowerLevels {
@@ -382,11 +642,16 @@ mod tests {
     use maplit::btreemap;
     use serde_json::{json, to_value as to_json_value};
 
-    use super::{default_power_level, NotificationPowerLevels, RoomPowerLevelsEventContent};
+    use super::{
+        default_power_level, NotificationPowerLevelType, NotificationPowerLevels, PowerLevelChange,
+        PowerLevelChangeError, PowerLevelTarget, RoomPowerLevels, RoomPowerLevelsEventContent,
+    };
     use crate::{
         event_id,
         events::{EmptyStateKey, OriginalStateEvent, StateUnsigned},
-        room_id, user_id, MilliSecondsSinceUnixEpoch,
+        room_id,
+        serde::Raw,
+        user_id, MilliSecondsSinceUnixEpoch,
     };
 
     #[test]
@@ -528,4 +793,99 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn diff_and_check_change() {
+        let alice = user_id!("@alice:example.com");
+        let bob = user_id!("@bob:example.com");
+        let carl = user_id!("@carl:example.com");
+
+        let old = RoomPowerLevels::from(assign!(RoomPowerLevelsEventContent::new(), {
+            users: btreemap! {
+                alice.to_owned() => int!(100),
+                bob.to_owned() => int!(50),
+                carl.to_owned() => int!(50),
+            },
+        }));
+
+        let new = assign!(old.clone(), { kick: int!(60) });
+        assert_eq!(
+            old.diff(&new),
+            [PowerLevelChange {
+                target: PowerLevelTarget::Kick,
+                old: Some(int!(50)),
+                new: Some(int!(60)),
+            }]
+        );
+        old.check_change(alice, &new).unwrap();
+        assert_eq!(
+            old.check_change(bob, &new),
+            Err(PowerLevelChangeError::NewLevelTooHigh(PowerLevelTarget::Kick))
+        );
+
+        let mut new = old.clone();
+        new.users.remove(carl);
+        new.events.insert("m.room.name".into(), int!(50));
+        assert_eq!(old.diff(&new).len(), 2);
+        old.check_change(alice, &new).unwrap();
+        assert_eq!(
+            old.check_change(bob, &new),
+            Err(PowerLevelChangeError::SameLevelAsSender(PowerLevelTarget::User(carl.to_owned())))
+        );
+
+        let mut new = old.clone();
+        new.users.insert(bob.to_owned(), int!(0));
+        old.check_change(bob, &new).unwrap();
+        assert_eq!(
+            old.check_change(carl, &new),
+            Err(PowerLevelChangeError::SameLevelAsSender(PowerLevelTarget::User(bob.to_owned())))
+        );
+
+        assert_eq!(old.diff(&old), []);
+    }
+
+    #[test]
+    fn diff_raw() {
+        let old = Raw::new(&json!({
+            "kick": 50,
+            "users": { "@alice:example.com": 100 },
+        }))
+        .unwrap()
+        .cast();
+        let new = Raw::new(&json!({
+            "ban": 50,
+            "users": { "@alice:example.com": 100, "@bob:example.com": 0 },
+            "notifications": { "room": 50 },
+        }))
+        .unwrap()
+        .cast();
+
+        // Levels that are only added or removed at their default value are changes.
+        assert_eq!(
+            RoomPowerLevelsEventContent::diff_raw(&old, &new).unwrap(),
+            [
+                PowerLevelChange { target: PowerLevelTarget::Ban, old: None, new: Some(int!(50)) },
+                PowerLevelChange { target: PowerLevelTarget::Kick, old: Some(int!(50)), new: None },
+                PowerLevelChange {
+                    target: PowerLevelTarget::Notification(NotificationPowerLevelType::Room),
+                    old: None,
+                    new: Some(int!(50)),
+                },
+                PowerLevelChange {
+                    target: PowerLevelTarget::User(user_id!("@bob:example.com").to_owned()),
+                    old: None,
+                    new: Some(int!(0)),
+                },
+            ]
+        );
+
+        // They are not with the defaults applied.
+        let old = RoomPowerLevels::from(old.deserialize().unwrap());
+        let new = RoomPowerLevels::from(new.deserialize().unwrap());
+        assert_eq!(old.diff(&new).len(), 1);
+
+        let invalid = Raw::new(&json!({ "users": { "alice": 100 } })).unwrap().cast();
+        let empty = Raw::new(&json!({})).unwrap().cast();
+        assert!(RoomPowerLevelsEventContent::diff_raw(&empty, &invalid).is_err());
+    }
 }
@@ -0,0 +1,199 @@
+//! Selection of the servers to route through when referencing a room.
+//!
+//! Room IDs are not routable on their own, so links to rooms like [`matrix.to` URIs] and
+//! [`matrix:` URIs] should include `via` servers that are likely to be able to join the room.
+//! [`via_servers`] implements the selection [recommended by the spec].
+//!
+//! [`matrix.to` URIs]: crate::RoomId::matrix_to_uri
+//! [`matrix:` URIs]: crate::RoomId::matrix_uri
+//! [recommended by the spec]: https://spec.matrix.org/v1.2/appendices/#routing
+
+use std::collections::BTreeMap;
+
+use js_int::int;
+
+use super::{power_levels::RoomPowerLevels, server_acl::RoomServerAclEventContent};
+use crate::{OwnedServerName, ServerName, UserId};
+
+/// The maximum number of servers returned by [`via_servers`].
+pub const MAX_VIA_SERVERS: usize = 3;
+
+/// Selects the servers to route through to reach a room, in order of preference.
+///
+/// `joined_members` should contain the users whose membership in the room is `join`.
+///
+/// As recommended by the spec, the result starts with the server of the joined user with the
+/// highest power level, if that level is at least 50. It is followed by the servers with the most
+/// joined users, ties being broken by server name. Servers that are IP literals or that are denied
+/// by `server_acl` are never returned, and at most [`MAX_VIA_SERVERS`] servers are returned.
+///
+/// # Example
+///
+/// ```
+/// use ruma_common::{
+///     events::room::{power_levels::RoomPowerLevelsEventContent, routing::via_servers},
+///     room_id, server_name, user_id,
+/// };
+///
+/// let mut power_levels = RoomPowerLevelsEventContent::new();
+/// power_levels.users.insert(user_id!("@admin:small.example").to_owned(), 100.into());
+///
+/// let members = [
+///     user_id!("@admin:small.example"),
+///     user_id!("@alice:big.example"),
+///     user_id!("@bob:big.example"),
+///     user_id!("@carl:127.0.0.1"),
+/// ];
+/// let via = via_servers(&power_levels.into(), members, None);
+/// assert_eq!(via, [server_name!("small.example"), server_name!("big.example")]);
+///
+/// let uri = room_id!("!room:small.example").matrix_to_uri(via.iter().map(|s| &**s));
+/// assert_eq!(
+///     uri.to_string(),
+///     "https://matrix.to/#/%21room%3Asmall.example?via=small.example&via=big.example"
+/// );
+/// ```
+pub fn via_servers<'a>(
+    power_levels: &RoomPowerLevels,
+    joined_members: impl IntoIterator<Item = &'a UserId>,
+    server_acl: Option<&RoomServerAclEventContent>,
+) -> Vec<OwnedServerName> {
+    let is_routable = |server_name: &ServerName| {
+        !server_name.is_ip_literal() && server_acl.map_or(true, |acl| acl.is_allowed(server_name))
+    };
+
+    let mut member_counts: BTreeMap<&ServerName, usize> = BTreeMap::new();
+    let mut most_powerful: Option<&UserId> = None;
+
+    for user_id in joined_members {
+        let server_name = user_id.server_name();
+        if !is_routable(server_name) {
+            continue;
+        }
+
+        *member_counts.entry(server_name).or_default() += 1;
+
+        // Ties are broken by user ID, so the result doesn't depend on the order of the members.
+        let is_more_powerful = most_powerful.map_or(true, |current| {
+            let (level, current_level) =
+                (power_levels.for_user(user_id), power_levels.for_user(current));
+            level > current_level || (level == current_level && user_id < current)
+        });
+        if is_more_powerful {
+            most_powerful = Some(user_id);
+        }
+    }
+
+    let mut servers: Vec<_> = member_counts.into_iter().collect();
+    // The sort is stable, so servers with the same member count stay sorted by name.
+    servers.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let mut via = Vec::with_capacity(MAX_VIA_SERVERS);
+    if let Some(user_id) = most_powerful.filter(|u| power_levels.for_user(u) >= int!(50)) {
+        via.push(user_id.server_name().to_owned());
+    }
+    for (server_name, _) in servers {
+        if via.len() == MAX_VIA_SERVERS {
+            break;
+        }
+        if !via.iter().any(|s| s == server_name) {
+            via.push(server_name.to_owned());
+        }
+    }
+
+    via
+}
+
+#[cfg(test)]
+mod tests {
+    use super::via_servers;
+    use crate::{
+        events::room::{
+            power_levels::RoomPowerLevelsEventContent, server_acl::RoomServerAclEventContent,
+        },
+        server_name, user_id,
+    };
+
+    #[test]
+    fn most_populous_servers() {
+        let members = [
+            user_id!("@a:one.example"),
+            user_id!("@b:two.example"),
+            user_id!("@c:two.example"),
+            user_id!("@d:three.example"),
+            user_id!("@e:four.example"),
+            user_id!("@f:four.example"),
+        ];
+        let via = via_servers(&RoomPowerLevelsEventContent::new().into(), members, None);
+
+        assert_eq!(
+            via,
+            [
+                server_name!("four.example"),
+                server_name!("two.example"),
+                server_name!("one.example")
+            ]
+        );
+    }
+
+    #[test]
+    fn highest_power_level_first() {
+        let mut power_levels = RoomPowerLevelsEventContent::new();
+        power_levels.users.insert(user_id!("@mod:small.example").to_owned(), 50.into());
+        power_levels.users.insert(user_id!("@admin:big.example").to_owned(), 100.into());
+
+        let members = [
+            user_id!("@mod:small.example"),
+            user_id!("@admin:big.example"),
+            user_id!("@a:other.example"),
+            user_id!("@b:other.example"),
+        ];
+        let via = via_servers(&power_levels.into(), members, None);
+
+        assert_eq!(
+            via,
+            [
+                server_name!("big.example"),
+                server_name!("other.example"),
+                server_name!("small.example")
+            ]
+        );
+    }
+
+    #[test]
+    fn low_power_level_ignored() {
+        let mut power_levels = RoomPowerLevelsEventContent::new();
+        power_levels.users.insert(user_id!("@mod:small.example").to_owned(), 49.into());
+
+        let members = [
+            user_id!("@mod:small.example"),
+            user_id!("@a:big.example"),
+            user_id!("@b:big.example"),
+        ];
+        let via = via_servers(&power_levels.into(), members, None);
+
+        assert_eq!(via, [server_name!("big.example"), server_name!("small.example")]);
+    }
+
+    #[test]
+    fn ip_literals_and_acl_excluded() {
+        let mut power_levels = RoomPowerLevelsEventContent::new();
+        power_levels.users.insert(user_id!("@admin:evil.example").to_owned(), 100.into());
+        power_levels.users.insert(user_id!("@mod:127.0.0.1").to_owned(), 50.into());
+
+        let acl = RoomServerAclEventContent::new(
+            true,
+            vec!["*".to_owned()],
+            vec!["evil.example".to_owned()],
+        );
+        let members = [
+            user_id!("@admin:evil.example"),
+            user_id!("@mod:127.0.0.1"),
+            user_id!("@a:[::1]"),
+            user_id!("@b:good.example"),
+        ];
+        let via = via_servers(&power_levels.into(), members, Some(&acl));
+
+        assert_eq!(via, [server_name!("good.example")]);
+    }
+}
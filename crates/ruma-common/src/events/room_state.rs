@@ -0,0 +1,763 @@
+//! Accumulation of the current state of a room from state events.
+//!
+//! [`RoomState`] keeps the latest state event for every `(type, state_key)` pair it has seen, and
+//! exposes typed accessors for the state events that are commonly needed by clients, as well as
+//! the [room display name calculation] from the spec.
+//!
+//! [room display name calculation]: https://spec.matrix.org/v1.2/client-server-api/#calculating-the-display-name-for-a-room
+
+use std::{collections::BTreeMap, fmt};
+
+use js_int::{int, UInt};
+
+use super::{
+    room::{
+        encryption::RoomEncryptionEventContent,
+        join_rules::JoinRule,
+        member::{
+            MemberRoster, MembershipChange, MembershipState, RoomMemberEventContent,
+            SyncRoomMemberEvent,
+        },
+        power_levels::{RoomPowerLevels, RoomPowerLevelsEventContent},
+        redaction::SyncRoomRedactionEvent,
+        routing::via_servers,
+        server_acl::RoomServerAclEventContent,
+    },
+    AnySyncMessageLikeEvent, AnySyncRoomEvent, AnySyncStateEvent, Redact, StateEventType,
+    SyncStateEvent,
+};
+use crate::{
+    serde::Raw, EventId, MxcUri, OwnedEventId, OwnedRoomAliasId, OwnedServerName, OwnedUserId,
+    RoomAliasId, RoomVersionId, UserId,
+};
+
+/// The maximum number of heroes used for calculating the display name of a room.
+const MAX_HEROES: usize = 5;
+
+/// The current state of a room, accumulated from state events.
+///
+/// State events can be added with [`ingest`](Self::ingest) (or [`apply`](Self::apply) if they
+/// have already been deserialized), in the order they are received. Redactions of state events
+/// are applied with [`ingest_timeline`](Self::ingest_timeline) or
+/// [`apply_redaction`](Self::apply_redaction).
+///
+/// Since the room summary is only sent in `/sync` responses when it changes, it is stored as part
+/// of the room state as well, through [`update_summary`](Self::update_summary).
+#[derive(Clone, Debug, Default)]
+pub struct RoomState {
+    events: BTreeMap<(StateEventType, String), AnySyncStateEvent>,
+    event_keys: BTreeMap<OwnedEventId, (StateEventType, String)>,
+    prev_member_contents: BTreeMap<OwnedUserId, RoomMemberEventContent>,
+    room_version: Option<RoomVersionId>,
+    roster: MemberRoster,
+    heroes: Option<Vec<String>>,
+    joined_member_count: Option<UInt>,
+    invited_member_count: Option<UInt>,
+}
+
+impl RoomState {
+    /// Creates an empty `RoomState`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deserializes the given state event and adds it to the room state.
+    ///
+    /// The event replaces any previous event with the same type and state key.
+    pub fn ingest(&mut self, event: &Raw<AnySyncStateEvent>) -> serde_json::Result<()> {
+        self.apply(event.deserialize()?);
+        Ok(())
+    }
+
+    /// Deserializes the given timeline event and updates the room state with it.
+    ///
+    /// State events are added to the room state, `m.room.redaction` events redact the state event
+    /// they refer to, if it is part of the current state. Other events are ignored.
+    pub fn ingest_timeline(&mut self, event: &Raw<AnySyncRoomEvent>) -> serde_json::Result<()> {
+        match event.deserialize()? {
+            AnySyncRoomEvent::State(ev) => self.apply(ev),
+            AnySyncRoomEvent::MessageLike(AnySyncMessageLikeEvent::RoomRedaction(redaction)) => {
+                self.apply_redaction(redaction);
+            }
+            AnySyncRoomEvent::MessageLike(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Adds the given state event to the room state.
+    ///
+    /// The event replaces any previous event with the same type and state key.
+    pub fn apply(&mut self, event: AnySyncStateEvent) {
+        match &event {
+            AnySyncStateEvent::RoomCreate(SyncStateEvent::Original(ev)) => {
+                self.room_version = Some(ev.content.room_version.clone());
+            }
+            AnySyncStateEvent::RoomMember(member) => {
+                self.update_prev_member_content(member);
+                self.roster.apply(member);
+            }
+            _ => {}
+        }
+
+        let key = (event.event_type(), event.state_key().to_owned());
+        let event_id = event.event_id().to_owned();
+
+        // The previous event might be the same as this one, so its entry must be removed before
+        // adding the new one.
+        if let Some(previous) = self.events.insert(key.clone(), event) {
+            self.event_keys.remove(previous.event_id());
+        }
+        self.event_keys.insert(event_id, key);
+    }
+
+    // Keep the previous content of member events around, so the membership change can still be
+    // calculated once they are redacted. If the event doesn't have a `prev_content`, the content
+    // of the event it replaces is used instead.
+    fn update_prev_member_content(&mut self, event: &SyncRoomMemberEvent) {
+        let user_id = event.state_key();
+        let prev_content = match event {
+            SyncStateEvent::Original(ev) => ev.unsigned.prev_content.clone(),
+            SyncStateEvent::Redacted(_) => None,
+        }
+        .or_else(|| match self.member(user_id) {
+            Some(SyncStateEvent::Original(prev)) => Some(prev.content.clone()),
+            _ => None,
+        });
+
+        match prev_content {
+            Some(content) => {
+                self.prev_member_contents.insert(user_id.clone(), content);
+            }
+            None => {
+                self.prev_member_contents.remove(user_id);
+            }
+        }
+    }
+
+    /// Redacts the state event that the given redaction refers to, if it is part of the current
+    /// state.
+    ///
+    /// Redacted redaction events are ignored.
+    pub fn apply_redaction(&mut self, redaction: SyncRoomRedactionEvent) {
+        let redacts = match &redaction {
+            SyncRoomRedactionEvent::Original(ev) => &ev.redacts,
+            SyncRoomRedactionEvent::Redacted(_) => return,
+        };
+
+        let key = match self.event_keys.get(redacts) {
+            Some(key) => key,
+            None => return,
+        };
+
+        let version = self.room_version();
+        if let Some(event) = self.events.remove(key) {
+            let event = event.redact(redaction, &version);
+            if let AnySyncStateEvent::RoomMember(member) = &event {
+                self.roster.apply(member);
+            }
+            self.events.insert(key.clone(), event);
+        }
+    }
+
+    /// Updates the stored room summary with the fields of the summary of a `/sync` response.
+    ///
+    /// Fields that are `None` keep their previous value, since the server only sends them when
+    /// they change.
+    pub fn update_summary(
+        &mut self,
+        heroes: Vec<String>,
+        joined_member_count: Option<UInt>,
+        invited_member_count: Option<UInt>,
+    ) {
+        if !heroes.is_empty() {
+            self.heroes = Some(heroes);
+        }
+        if joined_member_count.is_some() {
+            self.joined_member_count = joined_member_count;
+        }
+        if invited_member_count.is_some() {
+            self.invited_member_count = invited_member_count;
+        }
+    }
+
+    /// Returns the current state event with the given type and state key, if any.
+    pub fn get(&self, event_type: &StateEventType, state_key: &str) -> Option<&AnySyncStateEvent> {
+        self.events.get(&(event_type.clone(), state_key.to_owned()))
+    }
+
+    /// Returns the state event in the current state with the given event ID, if any.
+    pub fn get_by_event_id(&self, event_id: &EventId) -> Option<&AnySyncStateEvent> {
+        self.event_keys.get(event_id).and_then(|key| self.events.get(key))
+    }
+
+    /// Returns an iterator over all the events in the current state.
+    pub fn iter(&self) -> impl Iterator<Item = &AnySyncStateEvent> {
+        self.events.values()
+    }
+
+    /// Returns the version of the room.
+    ///
+    /// Defaults to version 1 if no unredacted `m.room.create` event was added, as per the spec.
+    pub fn room_version(&self) -> RoomVersionId {
+        self.room_version.clone().unwrap_or(RoomVersionId::V1)
+    }
+
+    /// Returns the name of the room, if any.
+    pub fn name(&self) -> Option<&str> {
+        match self.get(&StateEventType::RoomName, "") {
+            Some(AnySyncStateEvent::RoomName(SyncStateEvent::Original(ev))) => {
+                ev.content.name.as_deref().map(|name| name.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the canonical alias of the room, if any.
+    pub fn canonical_alias(&self) -> Option<&RoomAliasId> {
+        match self.get(&StateEventType::RoomCanonicalAlias, "") {
+            Some(AnySyncStateEvent::RoomCanonicalAlias(SyncStateEvent::Original(ev))) => {
+                ev.content.alias.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the topic of the room, if any.
+    pub fn topic(&self) -> Option<&str> {
+        match self.get(&StateEventType::RoomTopic, "") {
+            Some(AnySyncStateEvent::RoomTopic(SyncStateEvent::Original(ev))) => {
+                Some(&ev.content.topic)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the URL of the avatar of the room, if any.
+    pub fn avatar_url(&self) -> Option<&MxcUri> {
+        match self.get(&StateEventType::RoomAvatar, "") {
+            Some(AnySyncStateEvent::RoomAvatar(SyncStateEvent::Original(ev))) => {
+                ev.content.url.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the join rule of the room, if there is an `m.room.join_rules` event.
+    pub fn join_rule(&self) -> Option<&JoinRule> {
+        match self.get(&StateEventType::RoomJoinRules, "") {
+            Some(AnySyncStateEvent::RoomJoinRules(ev)) => Some(ev.join_rule()),
+            _ => None,
+        }
+    }
+
+    /// Returns the effective power levels of the room, if there is an `m.room.power_levels` event.
+    pub fn power_levels(&self) -> Option<RoomPowerLevels> {
+        match self.get(&StateEventType::RoomPowerLevels, "") {
+            Some(AnySyncStateEvent::RoomPowerLevels(ev)) => Some(ev.power_levels()),
+            _ => None,
+        }
+    }
+
+    /// Returns the server access control list of the room, if there is an unredacted
+    /// `m.room.server_acl` event.
+    pub fn server_acl(&self) -> Option<&RoomServerAclEventContent> {
+        match self.get(&StateEventType::RoomServerAcl, "") {
+            Some(AnySyncStateEvent::RoomServerAcl(SyncStateEvent::Original(ev))) => {
+                Some(&ev.content)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the encryption settings of the room, if there is an unredacted `m.room.encryption`
+    /// event.
+    pub fn encryption(&self) -> Option<&RoomEncryptionEventContent> {
+        match self.get(&StateEventType::RoomEncryption, "") {
+            Some(AnySyncStateEvent::RoomEncryption(SyncStateEvent::Original(ev))) => {
+                Some(&ev.content)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the room is encrypted.
+    ///
+    /// A room stays encrypted even if its `m.room.encryption` event is redacted.
+    pub fn is_encrypted(&self) -> bool {
+        self.get(&StateEventType::RoomEncryption, "").is_some()
+    }
+
+    /// Returns the `m.room.member` event of the given user, if any.
+    pub fn member(&self, user_id: &UserId) -> Option<&SyncRoomMemberEvent> {
+        match self.get(&StateEventType::RoomMember, user_id.as_str()) {
+            Some(AnySyncStateEvent::RoomMember(ev)) => Some(ev),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the `m.room.member` events in the current state, ordered by user
+    /// ID.
+    pub fn members(&self) -> impl Iterator<Item = &SyncRoomMemberEvent> {
+        self.events.range((StateEventType::RoomMember, String::new())..).map_while(|(_, ev)| {
+            match ev {
+                AnySyncStateEvent::RoomMember(ev) => Some(ev),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns the servers to route through to reach the room, in order of preference.
+    ///
+    /// They are selected from the joined members of the room, its power levels and its server
+    /// access control list, with [`via_servers`]. The result can be used to build
+    /// [`matrix.to`](crate::RoomId::matrix_to_uri) or [`matrix:`](crate::RoomId::matrix_uri) URIs.
+    ///
+    /// If the room has no `m.room.power_levels` event, the creator of the room has power level
+    /// 100, as per the spec.
+    pub fn via_servers(&self) -> Vec<OwnedServerName> {
+        let power_levels = self.power_levels().unwrap_or_else(|| {
+            // Without power levels, the creator of the room has level 100, as in the auth rules.
+            let mut content = RoomPowerLevelsEventContent::new();
+            let creator = match self.get(&StateEventType::RoomCreate, "") {
+                Some(AnySyncStateEvent::RoomCreate(SyncStateEvent::Original(ev))) => {
+                    Some(&ev.content.creator)
+                }
+                Some(AnySyncStateEvent::RoomCreate(SyncStateEvent::Redacted(ev))) => {
+                    Some(&ev.content.creator)
+                }
+                _ => None,
+            };
+            if let Some(creator) = creator {
+                content.users.insert(creator.clone(), int!(100));
+            }
+
+            content.into()
+        });
+        let joined_members = self
+            .members()
+            .filter(|ev| *ev.membership() == MembershipState::Join)
+            .map(|ev| ev.state_key().as_ref());
+
+        via_servers(&power_levels, joined_members, self.server_acl())
+    }
+
+    /// Returns the roster of the members of the room, to get their disambiguated display names.
+    pub fn roster(&self) -> &MemberRoster {
+        &self.roster
+    }
+
+    /// Returns the membership state of the given user, if they have an `m.room.member` event.
+    pub fn membership(&self, user_id: &UserId) -> Option<&MembershipState> {
+        self.member(user_id).map(|ev| ev.membership())
+    }
+
+    /// Returns the change that the current `m.room.member` event of the given user made.
+    ///
+    /// The change is calculated from the `prev_content` of the event, which is remembered in case
+    /// the event is redacted later.
+    pub fn membership_change(&self, user_id: &UserId) -> Option<MembershipChange<'_>> {
+        match self.member(user_id)? {
+            SyncStateEvent::Original(ev) => Some(ev.membership_change()),
+            SyncStateEvent::Redacted(ev) => Some(ev.membership_change(
+                self.prev_member_contents.get(user_id).map(|content| content.details()),
+            )),
+        }
+    }
+
+    /// Returns the display name of the given user, if they have an unredacted `m.room.member`
+    /// event with a `displayname`.
+    pub fn member_display_name(&self, user_id: &UserId) -> Option<&str> {
+        match self.member(user_id)? {
+            SyncStateEvent::Original(ev) => ev.content.displayname.as_deref(),
+            SyncStateEvent::Redacted(_) => None,
+        }
+    }
+
+    /// Returns the avatar URL of the given user, if they have an unredacted `m.room.member` event
+    /// with an `avatar_url`.
+    pub fn member_avatar_url(&self, user_id: &UserId) -> Option<&MxcUri> {
+        match self.member(user_id)? {
+            SyncStateEvent::Original(ev) => ev.content.avatar_url.as_deref(),
+            SyncStateEvent::Redacted(_) => None,
+        }
+    }
+
+    /// Returns the number of joined members.
+    ///
+    /// Uses the room summary if available, otherwise counts the `m.room.member` events.
+    pub fn joined_member_count(&self) -> u64 {
+        self.joined_member_count
+            .map(Into::into)
+            .unwrap_or_else(|| self.count_members(&MembershipState::Join))
+    }
+
+    /// Returns the number of invited members.
+    ///
+    /// Uses the room summary if available, otherwise counts the `m.room.member` events.
+    pub fn invited_member_count(&self) -> u64 {
+        self.invited_member_count
+            .map(Into::into)
+            .unwrap_or_else(|| self.count_members(&MembershipState::Invite))
+    }
+
+    fn count_members(&self, membership: &MembershipState) -> u64 {
+        self.members().filter(|ev| ev.membership() == membership).count() as u64
+    }
+
+    /// Calculates the display name of the room for the given user, according to the spec.
+    ///
+    /// Uses, in order of preference, the name of the room, its canonical alias, and its heroes.
+    /// If the room summary doesn't contain any heroes, up to 5 other members (joined or invited,
+    /// or former members if there are none) are used instead, ordered by user ID. The names of the
+    /// heroes are disambiguated with [`MemberRoster::disambiguated_display_name`].
+    pub fn display_name(&self, own_user_id: &UserId) -> RoomDisplayName {
+        if let Some(name) = self.name().filter(|name| !name.is_empty()) {
+            return RoomDisplayName::Named(name.to_owned());
+        }
+
+        if let Some(alias) = self.canonical_alias() {
+            return RoomDisplayName::Aliased(alias.to_owned());
+        }
+
+        let heroes = match &self.heroes {
+            Some(heroes) => heroes.iter().map(|hero| self.hero_name(hero)).collect(),
+            None => self.calculate_heroes(own_user_id),
+        };
+
+        let members = self.joined_member_count() + self.invited_member_count();
+        if members > 1 {
+            let others = (members - 1).saturating_sub(heroes.len() as u64);
+            RoomDisplayName::Calculated { heroes, others }
+        } else if !heroes.is_empty() {
+            RoomDisplayName::EmptyWas(heroes)
+        } else {
+            RoomDisplayName::Empty
+        }
+    }
+
+    fn hero_name(&self, hero: &str) -> String {
+        match <&UserId>::try_from(hero) {
+            Ok(user_id) => self.roster.disambiguated_display_name(user_id),
+            Err(_) => hero.to_owned(),
+        }
+    }
+
+    fn calculate_heroes(&self, own_user_id: &UserId) -> Vec<String> {
+        let others = || self.members().filter(|ev| ev.state_key() != own_user_id);
+
+        let mut heroes: Vec<_> = others()
+            .filter(|ev| matches!(ev.membership(), MembershipState::Join | MembershipState::Invite))
+            .take(MAX_HEROES)
+            .collect();
+        if heroes.is_empty() {
+            heroes = others()
+                .filter(|ev| {
+                    matches!(ev.membership(), MembershipState::Leave | MembershipState::Ban)
+                })
+                .take(MAX_HEROES)
+                .collect();
+        }
+
+        heroes.into_iter().map(|ev| self.hero_name(ev.state_key().as_str())).collect()
+    }
+}
+
+/// The display name of a room, as calculated by [`RoomState::display_name`].
+///
+/// The `Display` implementation formats the name in English, like the examples in the spec.
+/// Clients that need localized names should match on the variants instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RoomDisplayName {
+    /// The room has an `m.room.name`.
+    Named(String),
+
+    /// The room has a canonical alias.
+    Aliased(OwnedRoomAliasId),
+
+    /// The name is calculated from the names of other members of the room.
+    Calculated {
+        /// The names of the heroes of the room.
+        heroes: Vec<String>,
+
+        /// The number of joined or invited members that are not part of `heroes`.
+        others: u64,
+    },
+
+    /// The room is empty, but was shared with the given former members.
+    EmptyWas(Vec<String>),
+
+    /// The room is empty.
+    Empty,
+}
+
+impl fmt::Display for RoomDisplayName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(name) => f.write_str(name),
+            Self::Aliased(alias) => f.write_str(alias.as_str()),
+            Self::Calculated { heroes, others } => {
+                if *others > 0 {
+                    write!(f, "{}, and {} others", heroes.join(", "), others)
+                } else {
+                    write_name_list(f, heroes)
+                }
+            }
+            Self::EmptyWas(heroes) => {
+                f.write_str("Empty Room (was ")?;
+                write_name_list(f, heroes)?;
+                f.write_str(")")
+            }
+            Self::Empty => f.write_str("Empty Room"),
+        }
+    }
+}
+
+fn write_name_list(f: &mut fmt::Formatter<'_>, names: &[String]) -> fmt::Result {
+    match names {
+        [] => Ok(()),
+        [name] => f.write_str(name),
+        [first, second] => write!(f, "{} and {}", first, second),
+        [names @ .., last] => write!(f, "{}, and {}", names.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use js_int::uint;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::{RoomDisplayName, RoomState};
+    use crate::{
+        events::{
+            room::member::{MembershipChange, MembershipState},
+            AnySyncRoomEvent,
+        },
+        room_alias_id,
+        serde::Raw,
+        server_name, user_id,
+    };
+
+    fn state_event(event_type: &str, state_key: &str, content: JsonValue) -> JsonValue {
+        json!({
+            "type": event_type,
+            "state_key": state_key,
+            "content": content,
+            "event_id": format!("${}{}", event_type, state_key.replace(':', "_")),
+            "sender": "@alice:localhost",
+            "origin_server_ts": 1,
+        })
+    }
+
+    fn member_event(user_id: &str, membership: &str, displayname: Option<&str>) -> JsonValue {
+        let mut content = json!({ "membership": membership });
+        if let Some(displayname) = displayname {
+            content["displayname"] = displayname.into();
+        }
+        state_event("m.room.member", user_id, content)
+    }
+
+    fn room_state(events: Vec<JsonValue>) -> RoomState {
+        let mut state = RoomState::new();
+        for event in events {
+            state.ingest(&Raw::new(&event).unwrap().cast()).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn name_and_alias() {
+        let state = room_state(vec![
+            state_event("m.room.name", "", json!({ "name": "Room" })),
+            state_event("m.room.canonical_alias", "", json!({ "alias": "#room:localhost" })),
+            state_event("m.room.topic", "", json!({ "topic": "Things" })),
+        ]);
+
+        assert_eq!(state.name(), Some("Room"));
+        assert_eq!(state.canonical_alias(), Some(room_alias_id!("#room:localhost")));
+        assert_eq!(state.topic(), Some("Things"));
+        assert_eq!(
+            state.display_name(user_id!("@alice:localhost")),
+            RoomDisplayName::Named("Room".to_owned())
+        );
+
+        let state = room_state(vec![state_event(
+            "m.room.canonical_alias",
+            "",
+            json!({ "alias": "#room:localhost" }),
+        )]);
+        assert_eq!(state.display_name(user_id!("@alice:localhost")).to_string(), "#room:localhost");
+    }
+
+    #[test]
+    fn via_servers() {
+        let state = room_state(vec![
+            member_event("@alice:one.localhost", "join", None),
+            member_event("@bob:two.localhost", "join", None),
+            member_event("@carl:two.localhost", "join", None),
+            member_event("@dan:three.localhost", "leave", None),
+            member_event("@eve:evil.localhost", "join", None),
+            member_event("@frank:evil.localhost", "join", None),
+            state_event(
+                "m.room.power_levels",
+                "",
+                json!({ "users": { "@alice:one.localhost": 100, "@dan:three.localhost": 100 } }),
+            ),
+            state_event("m.room.server_acl", "", json!({ "allow": ["*"], "deny": ["evil.*"] })),
+        ]);
+
+        assert_eq!(
+            state.via_servers(),
+            [server_name!("one.localhost"), server_name!("two.localhost")]
+        );
+    }
+
+    #[test]
+    fn via_servers_without_power_levels() {
+        let state = room_state(vec![
+            state_event("m.room.create", "", json!({ "creator": "@alice:one.localhost" })),
+            member_event("@alice:one.localhost", "join", None),
+            member_event("@bob:two.localhost", "join", None),
+            member_event("@carl:two.localhost", "join", None),
+        ]);
+
+        assert_eq!(
+            state.via_servers(),
+            [server_name!("one.localhost"), server_name!("two.localhost")]
+        );
+
+        let state = room_state(vec![
+            member_event("@alice:one.localhost", "join", None),
+            member_event("@bob:two.localhost", "join", None),
+            member_event("@carl:two.localhost", "join", None),
+        ]);
+
+        assert_eq!(
+            state.via_servers(),
+            [server_name!("two.localhost"), server_name!("one.localhost")]
+        );
+    }
+
+    #[test]
+    fn members() {
+        let state = room_state(vec![
+            member_event("@alice:localhost", "join", Some("Alice")),
+            member_event("@bob:localhost", "invite", None),
+            member_event("@carl:localhost", "leave", Some("Carl")),
+            state_event("m.room.topic", "", json!({ "topic": "Things" })),
+        ]);
+
+        assert_eq!(state.members().count(), 3);
+        assert_eq!(state.joined_member_count(), 1);
+        assert_eq!(state.invited_member_count(), 1);
+        assert_eq!(state.membership(user_id!("@bob:localhost")), Some(&MembershipState::Invite));
+        assert_eq!(state.member_display_name(user_id!("@alice:localhost")), Some("Alice"));
+        assert_eq!(state.member_display_name(user_id!("@bob:localhost")), None);
+    }
+
+    #[test]
+    fn display_name_from_members() {
+        let own_user_id = user_id!("@alice:localhost");
+
+        let mut state = room_state(vec![member_event("@alice:localhost", "join", Some("Alice"))]);
+        assert_eq!(state.display_name(own_user_id), RoomDisplayName::Empty);
+
+        state
+            .ingest(&Raw::new(&member_event("@bob:localhost", "join", Some("Bob"))).unwrap().cast())
+            .unwrap();
+        assert_eq!(state.display_name(own_user_id).to_string(), "Bob");
+
+        state
+            .ingest(&Raw::new(&member_event("@carl:localhost", "invite", None)).unwrap().cast())
+            .unwrap();
+        assert_eq!(state.display_name(own_user_id).to_string(), "Bob and @carl:localhost");
+
+        state
+            .ingest(
+                &Raw::new(&member_event("@bob:localhost", "leave", Some("Bob"))).unwrap().cast(),
+            )
+            .unwrap();
+        state
+            .ingest(&Raw::new(&member_event("@carl:localhost", "leave", None)).unwrap().cast())
+            .unwrap();
+        assert_eq!(
+            state.display_name(own_user_id).to_string(),
+            "Empty Room (was Bob and @carl:localhost)"
+        );
+    }
+
+    #[test]
+    fn display_name_from_summary() {
+        let mut state = room_state(vec![member_event("@bob:localhost", "join", Some("Bob"))]);
+        state.update_summary(
+            vec!["@bob:localhost".to_owned(), "@carl:localhost".to_owned()],
+            Some(uint!(20)),
+            Some(uint!(2)),
+        );
+
+        assert_eq!(
+            state.display_name(user_id!("@alice:localhost")),
+            RoomDisplayName::Calculated {
+                heroes: vec!["Bob".to_owned(), "@carl:localhost".to_owned()],
+                others: 19,
+            }
+        );
+        assert_eq!(
+            state.display_name(user_id!("@alice:localhost")).to_string(),
+            "Bob, @carl:localhost, and 19 others"
+        );
+    }
+
+    #[test]
+    fn redaction() {
+        let mut state = room_state(vec![
+            state_event("m.room.name", "", json!({ "name": "Room" })),
+            member_event("@bob:localhost", "join", Some("Bob")),
+        ]);
+
+        let redaction = |redacts: &str| -> Raw<AnySyncRoomEvent> {
+            Raw::new(&json!({
+                "type": "m.room.redaction",
+                "redacts": redacts,
+                "content": {},
+                "event_id": format!("$redaction{}", redacts),
+                "sender": "@alice:localhost",
+                "origin_server_ts": 2,
+            }))
+            .unwrap()
+            .cast()
+        };
+
+        state.ingest_timeline(&redaction("$m.room.name")).unwrap();
+        assert_eq!(state.name(), None);
+        assert!(state.get_by_event_id("$m.room.name".try_into().unwrap()).is_some());
+
+        state.ingest_timeline(&redaction("$m.room.member@bob_localhost")).unwrap();
+        assert_eq!(state.member_display_name(user_id!("@bob:localhost")), None);
+        assert_eq!(state.membership(user_id!("@bob:localhost")), Some(&MembershipState::Join));
+        assert_matches!(
+            state.membership_change(user_id!("@bob:localhost")),
+            Some(MembershipChange::Joined)
+        );
+    }
+
+    #[test]
+    fn redaction_after_reapply() {
+        let name = state_event("m.room.name", "", json!({ "name": "Room" }));
+        let mut state = room_state(vec![name.clone(), name]);
+        assert!(state.get_by_event_id("$m.room.name".try_into().unwrap()).is_some());
+
+        let redaction = json!({
+            "type": "m.room.redaction",
+            "redacts": "$m.room.name",
+            "content": {},
+            "event_id": "$redaction",
+            "sender": "@alice:localhost",
+            "origin_server_ts": 2,
+        });
+        state.ingest_timeline(&Raw::new(&redaction).unwrap().cast()).unwrap();
+        assert_eq!(state.name(), None);
+    }
+}
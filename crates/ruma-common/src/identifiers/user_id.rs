@@ -152,9 +152,90 @@ impl UserId {
 pub use ruma_identifiers_validation::user_id::localpart_is_fully_conforming;
 use ruma_macros::IdZst;
 
+/// Encodes a name from another character set into a fully conforming user ID localpart.
+///
+/// This implements the [mapping from other character sets] of the spec: uppercase letters are
+/// encoded as `_` followed by the lowercase letter, `_` as `__`, and the other bytes that are not
+/// allowed in a localpart, as well as `=`, as `=` followed by their lowercase hexadecimal value.
+/// The mapping is reversible with [`decode_localpart`].
+///
+/// ```
+/// use ruma_common::user_id::encode_localpart;
+///
+/// assert_eq!(encode_localpart("Alice_Smith"), "_alice___smith");
+/// assert_eq!(encode_localpart("ÿ #1"), "=c3=bf=20=231");
+/// ```
+///
+/// [mapping from other character sets]: https://spec.matrix.org/v1.2/appendices/#mapping-from-other-character-sets
+pub fn encode_localpart(name: &str) -> String {
+    let mut localpart = String::with_capacity(name.len());
+
+    for b in name.bytes() {
+        match b {
+            b'A'..=b'Z' => {
+                localpart.push('_');
+                localpart.push(b.to_ascii_lowercase().into());
+            }
+            b'_' => localpart.push_str("__"),
+            b'0'..=b'9' | b'a'..=b'z' | b'-' | b'.' | b'/' => localpart.push(b.into()),
+            _ => localpart.push_str(&format!("={:02x}", b)),
+        }
+    }
+
+    localpart
+}
+
+/// Decodes a user ID localpart that was encoded with [`encode_localpart`].
+///
+/// Returns an error if the localpart is not a valid encoding of a name, for example because it
+/// contains characters that are not fully conforming, or if the decoded name is not valid UTF-8.
+///
+/// ```
+/// use ruma_common::user_id::decode_localpart;
+///
+/// assert_eq!(decode_localpart("_alice___smith").unwrap(), "Alice_Smith");
+/// assert!(decode_localpart("_1").is_err());
+/// ```
+pub fn decode_localpart(localpart: &str) -> Result<String, IdParseError> {
+    let mut bytes = localpart.bytes();
+    let mut name = Vec::with_capacity(localpart.len());
+
+    while let Some(b) = bytes.next() {
+        match b {
+            b'_' => match bytes.next() {
+                Some(b'_') => name.push(b'_'),
+                Some(b @ b'a'..=b'z') => name.push(b.to_ascii_uppercase()),
+                _ => return Err(IdParseError::InvalidCharacters),
+            },
+            b'=' => {
+                let high = bytes.next().and_then(lowercase_hex_digit);
+                let low = bytes.next().and_then(lowercase_hex_digit);
+
+                match (high, low) {
+                    (Some(high), Some(low)) => name.push(high << 4 | low),
+                    _ => return Err(IdParseError::InvalidCharacters),
+                }
+            }
+            b'0'..=b'9' | b'a'..=b'z' | b'-' | b'.' | b'/' => name.push(b),
+            _ => return Err(IdParseError::InvalidCharacters),
+        }
+    }
+
+    String::from_utf8(name).map_err(|_| IdParseError::InvalidUtf8)
+}
+
+/// The value of the given lowercase hexadecimal digit.
+fn lowercase_hex_digit(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{OwnedUserId, UserId};
+    use super::{decode_localpart, encode_localpart, OwnedUserId, UserId};
     use crate::{server_name, IdParseError};
 
     #[test]
@@ -331,4 +412,38 @@ mod tests {
             IdParseError::InvalidServerName
         );
     }
+
+    #[test]
+    fn encode_localpart_from_other_character_sets() {
+        assert_eq!(encode_localpart("carl"), "carl");
+        assert_eq!(encode_localpart("Carl"), "_carl");
+        assert_eq!(encode_localpart("carl_2"), "carl__2");
+        assert_eq!(encode_localpart("carl=2"), "carl=3d2");
+        assert_eq!(encode_localpart("carl [irc]"), "carl=20=5birc=5d");
+        assert_eq!(encode_localpart("Ωmega"), "=ce=a9mega");
+
+        let user_id = UserId::parse_with_server_name(
+            encode_localpart("Ωmega Carl_2"),
+            server_name!("example.com"),
+        )
+        .unwrap();
+        assert!(!user_id.is_historical());
+    }
+
+    #[test]
+    fn decode_localpart_to_other_character_sets() {
+        for name in ["carl", "Carl", "carl_2", "carl=2", "carl [irc]", "Ωmega", "", "_=_"] {
+            assert_eq!(decode_localpart(&encode_localpart(name)).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn decode_invalid_localpart() {
+        assert_eq!(decode_localpart("carl_"), Err(IdParseError::InvalidCharacters));
+        assert_eq!(decode_localpart("_1"), Err(IdParseError::InvalidCharacters));
+        assert_eq!(decode_localpart("=2"), Err(IdParseError::InvalidCharacters));
+        assert_eq!(decode_localpart("=5B"), Err(IdParseError::InvalidCharacters));
+        assert_eq!(decode_localpart("Carl"), Err(IdParseError::InvalidCharacters));
+        assert_eq!(decode_localpart("=ff"), Err(IdParseError::InvalidUtf8));
+    }
 }
@@ -24,20 +24,32 @@ use tracing::instrument;
 
 use crate::{
     serde::{Raw, StringEnum},
-    PrivOwnedStr,
+    PrivOwnedStr, RoomId, UserId,
 };
 
 mod action;
+mod compiled;
 mod condition;
+mod error;
+mod evaluation;
 mod iter;
 mod predefined;
+mod unread;
 
+#[cfg(feature = "events")]
+pub(crate) use self::condition::Glob;
 pub use self::{
     action::{Action, Tweak},
+    compiled::CompiledRuleset,
     condition::{
-        ComparisonOperator, FlattenedJson, PushCondition, PushConditionRoomCtx, RoomMemberCountIs,
+        ComparisonOperator, FlattenedJson, FlattenedJsonValue, PushCondition, PushConditionRoomCtx,
+        RoomMemberCountIs, ScalarJsonValue,
     },
+    error::{InsertPushRuleError, RemovePushRuleError, RuleNotFoundError},
+    evaluation::{PushConditionEvaluation, PushRuleEvaluation, PushRulesEvaluation},
     iter::{AnyPushRule, AnyPushRuleRef, RulesetIntoIter, RulesetIter},
+    predefined::ServerDefaultRulesUpdate,
+    unread::{UnreadCounter, UnreadCounts},
 };
 
 /// A push ruleset scopes a set of rules according to some criteria.
@@ -96,6 +108,152 @@ impl Ruleset {
         }
     }
 
+    /// Get the rule of the given kind with the given ID, if any.
+    pub fn get(&self, kind: RuleKind, rule_id: &str) -> Option<AnyPushRuleRef<'_>> {
+        match kind {
+            RuleKind::Override => self.override_.get(rule_id).map(AnyPushRuleRef::Override),
+            RuleKind::Underride => self.underride.get(rule_id).map(AnyPushRuleRef::Underride),
+            RuleKind::Sender => self.sender.get(rule_id).map(AnyPushRuleRef::Sender),
+            RuleKind::Room => self.room.get(rule_id).map(AnyPushRuleRef::Room),
+            RuleKind::Content => self.content.get(rule_id).map(AnyPushRuleRef::Content),
+            RuleKind::_Custom(_) => None,
+        }
+    }
+
+    /// Inserts a user-defined rule in the rule set.
+    ///
+    /// If a rule of the same kind with the same ID already exists, it is replaced.
+    ///
+    /// The rule is placed right before the rule with the ID `before`, or right after the rule with
+    /// the ID `after`. If both are set, `before` must have a lower priority than `after` and takes
+    /// precedence. If neither is set, an existing rule keeps its priority and a new rule is given
+    /// the highest priority of its kind, below `.m.rule.master`.
+    ///
+    /// Returns an error if the ID of the rule is reserved for server-default rules, or is not a
+    /// valid room ID for room rules or a valid user ID for sender rules, or if `before` or `after`
+    /// are not user-defined rules of the same kind.
+    pub fn insert(
+        &mut self,
+        rule: AnyPushRule,
+        after: Option<&str>,
+        before: Option<&str>,
+    ) -> Result<(), InsertPushRuleError> {
+        let rule_id = rule.rule_id().to_owned();
+
+        if rule_id.starts_with('.') {
+            return Err(InsertPushRuleError::ServerDefaultRuleId);
+        }
+
+        match rule {
+            AnyPushRule::Override(r) => {
+                insert_rule(&mut self.override_, r, &rule_id, after, before)
+            }
+            AnyPushRule::Underride(r) => {
+                insert_rule(&mut self.underride, r, &rule_id, after, before)
+            }
+            AnyPushRule::Content(r) => insert_rule(&mut self.content, r, &rule_id, after, before),
+            AnyPushRule::Room(r) => {
+                if <&RoomId>::try_from(rule_id.as_str()).is_err() {
+                    return Err(InsertPushRuleError::InvalidRuleId);
+                }
+
+                insert_rule(&mut self.room, r, &rule_id, after, before)
+            }
+            AnyPushRule::Sender(r) => {
+                if <&UserId>::try_from(rule_id.as_str()).is_err() {
+                    return Err(InsertPushRuleError::InvalidRuleId);
+                }
+
+                insert_rule(&mut self.sender, r, &rule_id, after, before)
+            }
+        }
+    }
+
+    /// Enables or disables the rule of the given kind with the given ID.
+    ///
+    /// Returns an error if the rule doesn't exist.
+    pub fn set_enabled(
+        &mut self,
+        kind: RuleKind,
+        rule_id: &str,
+        enabled: bool,
+    ) -> Result<(), RuleNotFoundError> {
+        match kind {
+            RuleKind::Override => {
+                update_rule(&mut self.override_, rule_id, |r| r.enabled = enabled)
+            }
+            RuleKind::Underride => {
+                update_rule(&mut self.underride, rule_id, |r| r.enabled = enabled)
+            }
+            RuleKind::Sender => update_rule(&mut self.sender, rule_id, |r| r.enabled = enabled),
+            RuleKind::Room => update_rule(&mut self.room, rule_id, |r| r.enabled = enabled),
+            RuleKind::Content => update_rule(&mut self.content, rule_id, |r| r.enabled = enabled),
+            RuleKind::_Custom(_) => Err(RuleNotFoundError),
+        }
+    }
+
+    /// Replaces the actions of the rule of the given kind with the given ID.
+    ///
+    /// Returns an error if the rule doesn't exist.
+    pub fn set_actions(
+        &mut self,
+        kind: RuleKind,
+        rule_id: &str,
+        actions: Vec<Action>,
+    ) -> Result<(), RuleNotFoundError> {
+        match kind {
+            RuleKind::Override => {
+                update_rule(&mut self.override_, rule_id, |r| r.actions = actions)
+            }
+            RuleKind::Underride => {
+                update_rule(&mut self.underride, rule_id, |r| r.actions = actions)
+            }
+            RuleKind::Sender => update_rule(&mut self.sender, rule_id, |r| r.actions = actions),
+            RuleKind::Room => update_rule(&mut self.room, rule_id, |r| r.actions = actions),
+            RuleKind::Content => update_rule(&mut self.content, rule_id, |r| r.actions = actions),
+            RuleKind::_Custom(_) => Err(RuleNotFoundError),
+        }
+    }
+
+    /// Removes the user-defined rule of the given kind with the given ID.
+    ///
+    /// Returns the removed rule, or an error if the rule doesn't exist or is a server-default
+    /// rule.
+    pub fn remove(
+        &mut self,
+        kind: RuleKind,
+        rule_id: &str,
+    ) -> Result<AnyPushRule, RemovePushRuleError> {
+        if self.get(kind.clone(), rule_id).is_none() {
+            return Err(RemovePushRuleError::NotFound);
+        }
+
+        if rule_id.starts_with('.') {
+            return Err(RemovePushRuleError::ServerDefault);
+        }
+
+        let rule = match kind {
+            RuleKind::Override => {
+                self.override_.shift_remove_full(rule_id).map(|(_, r)| AnyPushRule::Override(r))
+            }
+            RuleKind::Underride => {
+                self.underride.shift_remove_full(rule_id).map(|(_, r)| AnyPushRule::Underride(r))
+            }
+            RuleKind::Sender => {
+                self.sender.shift_remove_full(rule_id).map(|(_, r)| AnyPushRule::Sender(r))
+            }
+            RuleKind::Room => {
+                self.room.shift_remove_full(rule_id).map(|(_, r)| AnyPushRule::Room(r))
+            }
+            RuleKind::Content => {
+                self.content.shift_remove_full(rule_id).map(|(_, r)| AnyPushRule::Content(r))
+            }
+            RuleKind::_Custom(_) => None,
+        };
+
+        rule.ok_or(RemovePushRuleError::NotFound)
+    }
+
     /// Get the first push rule that applies to this event, if any.
     ///
     /// # Arguments
@@ -130,6 +288,100 @@ impl Ruleset {
     pub fn get_actions<T>(&self, event: &Raw<T>, context: &PushConditionRoomCtx) -> &[Action] {
         self.get_match(event, context).map(|rule| rule.actions()).unwrap_or(&[])
     }
+
+    /// Evaluate all the push rules against this event, to find out why it triggers a
+    /// notification or not.
+    ///
+    /// To only get the push actions that apply to the event, [`Ruleset::get_actions`] is more
+    /// efficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The raw JSON of a room message event.
+    /// * `context` - The context of the message and room at the time of the event.
+    #[instrument(skip_all, fields(context.room_id = %context.room_id))]
+    pub fn evaluate<T>(
+        &self,
+        event: &Raw<T>,
+        context: &PushConditionRoomCtx,
+    ) -> PushRulesEvaluation<'_> {
+        let event = FlattenedJson::from_raw(event);
+
+        if event.get("sender").map_or(false, |sender| sender == context.user_id) {
+            return PushRulesEvaluation { own_event: true, rules: Vec::new() };
+        }
+
+        let rules =
+            self.iter().map(|rule| PushRuleEvaluation::new(rule, &event, context)).collect();
+
+        PushRulesEvaluation { own_event: false, rules }
+    }
+}
+
+/// Inserts `rule` in `set` at the position described by `after` and `before`.
+///
+/// See `Ruleset::insert` for the details.
+fn insert_rule<T>(
+    set: &mut IndexSet<T>,
+    rule: T,
+    rule_id: &str,
+    after: Option<&str>,
+    before: Option<&str>,
+) -> Result<(), InsertPushRuleError>
+where
+    T: Hash + Eq,
+    str: Equivalent<T>,
+{
+    // Check the position before modifying the set.
+    let index_of = |id: &str| {
+        if id.starts_with('.') {
+            return Err(InsertPushRuleError::RelativeToServerDefaultRule);
+        }
+
+        set.get_index_of(id).filter(|_| id != rule_id).ok_or(InsertPushRuleError::UnknownRuleId)
+    };
+    let after_index = after.map(index_of).transpose()?;
+    let before_index = before.map(index_of).transpose()?;
+
+    if let (Some(after_index), Some(before_index)) = (after_index, before_index) {
+        if before_index <= after_index {
+            return Err(InsertPushRuleError::BeforeHigherThanAfter);
+        }
+    }
+
+    let existing_index = set.shift_remove_full(rule_id).map(|(index, _)| index);
+
+    // The indexes might have changed if the rule was removed.
+    let index = match (before, after, existing_index) {
+        (Some(before), _, _) => set.get_index_of(before).expect("rule should still exist"),
+        (None, Some(after), _) => set.get_index_of(after).expect("rule should still exist") + 1,
+        (None, None, Some(index)) => index,
+        (None, None, None) => usize::from(set.get_index_of(".m.rule.master") == Some(0)),
+    };
+
+    let (new_index, _) = set.insert_full(rule);
+    set.move_index(new_index, index);
+
+    Ok(())
+}
+
+/// Applies `f` to the rule with the given ID in `set`.
+fn update_rule<T>(
+    set: &mut IndexSet<T>,
+    rule_id: &str,
+    f: impl FnOnce(&mut T),
+) -> Result<(), RuleNotFoundError>
+where
+    T: Clone + Hash + Eq,
+    str: Equivalent<T>,
+{
+    let mut rule = set.get(rule_id).ok_or(RuleNotFoundError)?.clone();
+    f(&mut rule);
+
+    // Replacing the rule keeps its position.
+    set.replace(rule);
+
+    Ok(())
 }
 
 /// A push rule is a single rule that states under what conditions an event should be passed onto a
@@ -447,6 +699,38 @@ impl PusherData {
     }
 }
 
+/// The kinds of push rules that are available.
+#[doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/src/doc/string_enum.md"))]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, StringEnum)]
+#[ruma_enum(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RuleKind {
+    /// User-configured rules that override all other kinds.
+    Override,
+
+    /// Lowest priority user-defined rules.
+    Underride,
+
+    /// Sender-specific rules.
+    Sender,
+
+    /// Room-specific rules.
+    Room,
+
+    /// Content-specific rules.
+    Content,
+
+    #[doc(hidden)]
+    _Custom(PrivOwnedStr),
+}
+
+impl RuleKind {
+    /// Creates a string slice from this `RuleKind`.
+    pub fn as_str(&self) -> &str {
+        self.as_ref()
+    }
+}
+
 /// A special format that the homeserver should use when sending notifications to a Push Gateway.
 /// Currently, only "event_id_only" is supported as of [Push Gateway API r0.1.1][spec].
 ///
@@ -484,7 +768,8 @@ mod tests {
     use super::{
         action::{Action, Tweak},
         condition::{PushCondition, PushConditionRoomCtx, RoomMemberCountIs},
-        AnyPushRule, ConditionalPushRule, PatternedPushRule, Ruleset, SimplePushRule,
+        AnyPushRule, ConditionalPushRule, InsertPushRuleError, PatternedPushRule,
+        RemovePushRuleError, RuleKind, RuleNotFoundError, Ruleset, SimplePushRule,
     };
     use crate::{power_levels::NotificationPowerLevels, room_id, serde::Raw, user_id};
 
@@ -535,6 +820,109 @@ mod tests {
         assert!(added);
     }
 
+    fn user_override(rule_id: &str) -> AnyPushRule {
+        AnyPushRule::Override(ConditionalPushRule {
+            conditions: vec![],
+            actions: vec![Action::DontNotify],
+            rule_id: rule_id.into(),
+            enabled: true,
+            default: false,
+        })
+    }
+
+    fn override_ids(set: &Ruleset) -> Vec<&str> {
+        set.override_.iter().map(|rule| rule.rule_id.as_str()).collect()
+    }
+
+    #[test]
+    fn insert_rules() {
+        let mut set = Ruleset::server_default(user_id!("@jj:server.name"));
+        let default_count = set.override_.len();
+
+        set.insert(user_override("a"), None, None).unwrap();
+        set.insert(user_override("b"), None, None).unwrap();
+        assert_eq!(override_ids(&set)[..3], [".m.rule.master", "b", "a"]);
+
+        set.insert(user_override("c"), Some("a"), None).unwrap();
+        set.insert(user_override("d"), None, Some("b")).unwrap();
+        set.insert(user_override("e"), Some("d"), Some("b")).unwrap();
+        assert_eq!(override_ids(&set)[..6], [".m.rule.master", "d", "e", "b", "a", "c"]);
+
+        // Updating a rule keeps its position, unless it is moved.
+        let mut rule = user_override("b");
+        if let AnyPushRule::Override(rule) = &mut rule {
+            rule.actions = vec![Action::Notify];
+        }
+        set.insert(rule, None, None).unwrap();
+        assert_eq!(override_ids(&set)[..6], [".m.rule.master", "d", "e", "b", "a", "c"]);
+        assert_matches!(set.get(RuleKind::Override, "b").unwrap().actions(), [Action::Notify]);
+
+        set.insert(user_override("b"), Some("c"), None).unwrap();
+        assert_eq!(override_ids(&set)[..6], [".m.rule.master", "d", "e", "a", "c", "b"]);
+        assert_eq!(set.override_.len(), default_count + 5);
+
+        assert_matches!(
+            set.insert(user_override(".m.rule.mine"), None, None),
+            Err(InsertPushRuleError::ServerDefaultRuleId)
+        );
+        assert_matches!(
+            set.insert(user_override("f"), Some(".m.rule.master"), None),
+            Err(InsertPushRuleError::RelativeToServerDefaultRule)
+        );
+        assert_matches!(
+            set.insert(user_override("f"), None, Some("unknown")),
+            Err(InsertPushRuleError::UnknownRuleId)
+        );
+        assert_matches!(
+            set.insert(user_override("f"), Some("c"), Some("d")),
+            Err(InsertPushRuleError::BeforeHigherThanAfter)
+        );
+        assert_matches!(
+            set.insert(
+                AnyPushRule::Room(SimplePushRule {
+                    actions: vec![],
+                    default: false,
+                    enabled: true,
+                    rule_id: "not_a_room_id".into(),
+                }),
+                None,
+                None
+            ),
+            Err(InsertPushRuleError::InvalidRuleId)
+        );
+        assert_eq!(set.override_.len(), default_count + 5);
+    }
+
+    #[test]
+    fn edit_rules() {
+        let mut set = Ruleset::server_default(user_id!("@jj:server.name"));
+        set.insert(user_override("a"), None, None).unwrap();
+
+        set.set_enabled(RuleKind::Override, ".m.rule.master", true).unwrap();
+        assert!(set.get(RuleKind::Override, ".m.rule.master").unwrap().enabled());
+        assert_eq!(override_ids(&set)[0], ".m.rule.master");
+
+        set.set_actions(RuleKind::Override, "a", vec![Action::Notify]).unwrap();
+        assert_matches!(set.get(RuleKind::Override, "a").unwrap().actions(), [Action::Notify]);
+
+        assert_matches!(set.set_enabled(RuleKind::Underride, "a", false), Err(RuleNotFoundError));
+        assert_matches!(
+            set.set_actions(RuleKind::Override, "unknown", vec![]),
+            Err(RuleNotFoundError)
+        );
+
+        assert_matches!(
+            set.remove(RuleKind::Override, ".m.rule.master"),
+            Err(RemovePushRuleError::ServerDefault)
+        );
+        assert_matches!(set.remove(RuleKind::Room, "a"), Err(RemovePushRuleError::NotFound));
+        assert_matches!(
+            set.remove(RuleKind::Override, "a"),
+            Ok(AnyPushRule::Override(ConditionalPushRule { rule_id, .. })) if rule_id == "a"
+        );
+        assert!(set.get(RuleKind::Override, "a").is_none());
+    }
+
     #[test]
     fn get_by_rule_id() {
         let set = example_ruleset();
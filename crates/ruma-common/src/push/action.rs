@@ -24,6 +24,26 @@ pub enum Action {
     SetTweak(Tweak),
 }
 
+impl Action {
+    /// Whether this action causes matching events to generate a notification.
+    pub fn should_notify(&self) -> bool {
+        matches!(self, Self::Notify | Self::Coalesce)
+    }
+
+    /// Whether this action sets the `highlight` tweak to `true`.
+    pub fn is_highlight(&self) -> bool {
+        matches!(self, Self::SetTweak(Tweak::Highlight(true)))
+    }
+
+    /// The sound set by this action, if any.
+    pub fn sound(&self) -> Option<&str> {
+        match self {
+            Self::SetTweak(Tweak::Sound(sound)) => Some(sound),
+            _ => None,
+        }
+    }
+}
+
 /// The `set_tweak` action.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
@@ -0,0 +1,374 @@
+use std::ops::RangeBounds;
+
+use indexmap::IndexSet;
+
+#[cfg(any(feature = "unstable-msc3758", feature = "unstable-msc3966"))]
+use super::ScalarJsonValue;
+use super::{
+    condition::{self, Pattern},
+    Action, AnyPushRuleRef, ConditionalPushRule, FlattenedJson, PushCondition,
+    PushConditionRoomCtx, RoomMemberCountIs, Ruleset,
+};
+
+/// A [`Ruleset`] that was compiled to be evaluated efficiently.
+///
+/// [`Ruleset::get_match`] flattens the event and interprets the patterns of the rules on every
+/// call. `CompiledRuleset` compiles the patterns once and works with an event that was already
+/// flattened, so an event can be flattened once with [`FlattenedJson::from_raw`] and evaluated
+/// against the rulesets of all the users that receive it.
+///
+/// Disabled rules are left out when compiling. To modify the ruleset, get it back with
+/// [`into_inner`](Self::into_inner) and compile it again.
+///
+/// To create an instance of this type, use `CompiledRuleset::from` / `.into()`.
+#[derive(Clone, Debug)]
+pub struct CompiledRuleset {
+    ruleset: Ruleset,
+    rules: Vec<CompiledPushRule>,
+}
+
+impl CompiledRuleset {
+    /// The `Ruleset` that was compiled.
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    /// Consumes this `CompiledRuleset` and returns the `Ruleset` that was compiled.
+    pub fn into_inner(self) -> Ruleset {
+        self.ruleset
+    }
+
+    /// Get the first push rule that applies to this event, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The flattened JSON representation of a room message event.
+    /// * `context` - The context of the message and room at the time of the event.
+    pub fn get_match(
+        &self,
+        event: &FlattenedJson,
+        context: &PushConditionRoomCtx,
+    ) -> Option<AnyPushRuleRef<'_>> {
+        if event.get("sender").map_or(false, |sender| sender == context.user_id) {
+            // no need to look at the rules if the event was by the user themselves
+            return None;
+        }
+
+        self.rules
+            .iter()
+            .find(|rule| rule.applies(event, context))
+            .map(|rule| rule.slot.get(&self.ruleset))
+    }
+
+    /// Get the push actions that apply to this event.
+    ///
+    /// Returns an empty slice if no push rule applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The flattened JSON representation of a room message event.
+    /// * `context` - The context of the message and room at the time of the event.
+    pub fn get_actions(&self, event: &FlattenedJson, context: &PushConditionRoomCtx) -> &[Action] {
+        self.get_match(event, context).map(|rule| rule.actions()).unwrap_or(&[])
+    }
+}
+
+impl From<Ruleset> for CompiledRuleset {
+    fn from(ruleset: Ruleset) -> Self {
+        let Ruleset { override_, content, room, sender, underride } = &ruleset;
+        let mut rules = Vec::new();
+
+        compile_conditional(override_, RuleSlot::Override, &mut rules);
+        rules.extend(content.iter().enumerate().filter(|(_, rule)| rule.enabled).map(
+            |(i, rule)| CompiledPushRule {
+                slot: RuleSlot::Content(i),
+                conditions: vec![CompiledCondition::event_match("content.body", &rule.pattern)],
+            },
+        ));
+        rules.extend(room.iter().enumerate().filter(|(_, rule)| rule.enabled).map(|(i, rule)| {
+            CompiledPushRule {
+                slot: RuleSlot::Room(i),
+                conditions: vec![CompiledCondition::event_match("room_id", &rule.rule_id)],
+            }
+        }));
+        rules.extend(sender.iter().enumerate().filter(|(_, rule)| rule.enabled).map(
+            |(i, rule)| CompiledPushRule {
+                slot: RuleSlot::Sender(i),
+                conditions: vec![CompiledCondition::event_match("sender", &rule.rule_id)],
+            },
+        ));
+        compile_conditional(underride, RuleSlot::Underride, &mut rules);
+
+        Self { ruleset, rules }
+    }
+}
+
+fn compile_conditional(
+    set: &IndexSet<ConditionalPushRule>,
+    slot: fn(usize) -> RuleSlot,
+    rules: &mut Vec<CompiledPushRule>,
+) {
+    rules.extend(set.iter().enumerate().filter(|(_, rule)| rule.enabled).map(|(i, rule)| {
+        CompiledPushRule {
+            slot: slot(i),
+            conditions: rule.conditions.iter().map(CompiledCondition::new).collect(),
+        }
+    }));
+}
+
+/// The position of a push rule in its `Ruleset`.
+#[derive(Clone, Copy, Debug)]
+enum RuleSlot {
+    Override(usize),
+    Content(usize),
+    Room(usize),
+    Sender(usize),
+    Underride(usize),
+}
+
+impl RuleSlot {
+    fn get(self, ruleset: &Ruleset) -> AnyPushRuleRef<'_> {
+        match self {
+            Self::Override(i) => AnyPushRuleRef::Override(&ruleset.override_[i]),
+            Self::Content(i) => AnyPushRuleRef::Content(&ruleset.content[i]),
+            Self::Room(i) => AnyPushRuleRef::Room(&ruleset.room[i]),
+            Self::Sender(i) => AnyPushRuleRef::Sender(&ruleset.sender[i]),
+            Self::Underride(i) => AnyPushRuleRef::Underride(&ruleset.underride[i]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CompiledPushRule {
+    slot: RuleSlot,
+    conditions: Vec<CompiledCondition>,
+}
+
+impl CompiledPushRule {
+    fn applies(&self, event: &FlattenedJson, context: &PushConditionRoomCtx) -> bool {
+        self.conditions.iter().all(|cond| cond.applies(event, context))
+    }
+}
+
+/// A `PushCondition` with its pattern compiled.
+#[derive(Clone, Debug)]
+enum CompiledCondition {
+    EventMatch {
+        key: String,
+        pattern: Pattern,
+    },
+    ContainsDisplayName,
+    RoomMemberCount {
+        is: RoomMemberCountIs,
+    },
+    SenderNotificationPermission {
+        key: String,
+    },
+    #[cfg(feature = "unstable-msc3758")]
+    EventPropertyIs {
+        key: String,
+        value: ScalarJsonValue,
+    },
+    #[cfg(feature = "unstable-msc3966")]
+    EventPropertyContains {
+        key: String,
+        value: ScalarJsonValue,
+    },
+    #[cfg(feature = "unstable-msc3664")]
+    RelatedEventMatch {
+        key: Option<String>,
+        pattern: Option<String>,
+        rel_type: String,
+        include_fallbacks: bool,
+    },
+}
+
+impl CompiledCondition {
+    fn new(condition: &PushCondition) -> Self {
+        match condition {
+            PushCondition::EventMatch { key, pattern } => Self::event_match(key, pattern),
+            PushCondition::ContainsDisplayName => Self::ContainsDisplayName,
+            PushCondition::RoomMemberCount { is } => Self::RoomMemberCount { is: *is },
+            PushCondition::SenderNotificationPermission { key } => {
+                Self::SenderNotificationPermission { key: key.clone() }
+            }
+            #[cfg(feature = "unstable-msc3758")]
+            PushCondition::EventPropertyIs { key, value } => {
+                Self::EventPropertyIs { key: key.clone(), value: value.clone() }
+            }
+            #[cfg(feature = "unstable-msc3966")]
+            PushCondition::EventPropertyContains { key, value } => {
+                Self::EventPropertyContains { key: key.clone(), value: value.clone() }
+            }
+            #[cfg(feature = "unstable-msc3664")]
+            PushCondition::RelatedEventMatch { key, pattern, rel_type, include_fallbacks } => {
+                Self::RelatedEventMatch {
+                    key: key.clone(),
+                    pattern: pattern.clone(),
+                    rel_type: rel_type.clone(),
+                    include_fallbacks: *include_fallbacks,
+                }
+            }
+        }
+    }
+
+    fn event_match(key: &str, pattern: &str) -> Self {
+        Self::EventMatch {
+            key: key.to_owned(),
+            pattern: Pattern::new(pattern, key == "content.body"),
+        }
+    }
+
+    fn applies(&self, event: &FlattenedJson, context: &PushConditionRoomCtx) -> bool {
+        match self {
+            Self::EventMatch { key, pattern } => condition::event_match_value(event, key, context)
+                .map_or(false, |value| pattern.matches_lowercase(&value)),
+            Self::ContainsDisplayName => condition::check_contains_display_name(event, context),
+            Self::RoomMemberCount { is } => is.contains(&context.member_count),
+            Self::SenderNotificationPermission { key } => {
+                condition::check_sender_notification_permission(event, key, context)
+            }
+            #[cfg(feature = "unstable-msc3758")]
+            Self::EventPropertyIs { key, value } => {
+                condition::check_event_property_is(event, key, value)
+            }
+            #[cfg(feature = "unstable-msc3966")]
+            Self::EventPropertyContains { key, value } => {
+                condition::check_event_property_contains(event, key, value)
+            }
+            #[cfg(feature = "unstable-msc3664")]
+            Self::RelatedEventMatch { key, pattern, rel_type, include_fallbacks } => {
+                condition::check_related_event_match(
+                    event,
+                    key.as_deref(),
+                    pattern.as_deref(),
+                    rel_type,
+                    *include_fallbacks,
+                    context,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use assert_matches::assert_matches;
+    use js_int::int;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::CompiledRuleset;
+    use crate::{
+        power_levels::NotificationPowerLevels,
+        push::{
+            Action, AnyPushRuleRef, FlattenedJson, PushConditionRoomCtx, Ruleset, SimplePushRule,
+            SimplePushRuleInit,
+        },
+        room_id,
+        serde::Raw,
+        user_id,
+    };
+
+    fn context(member_count: u32) -> PushConditionRoomCtx {
+        PushConditionRoomCtx {
+            room_id: room_id!("!far_west:server.name").to_owned(),
+            member_count: member_count.into(),
+            user_id: user_id!("@jj:server.name").to_owned(),
+            user_display_name: "Jolly Jumper".into(),
+            users_power_levels: BTreeMap::new(),
+            default_power_level: int!(50),
+            notification_power_levels: NotificationPowerLevels { room: int!(50) },
+        }
+    }
+
+    fn events() -> Vec<Raw<JsonValue>> {
+        [
+            json!({ "type": "m.room.message", "sender": "@rantanplan:server.name" }),
+            json!({
+                "type": "m.room.message",
+                "sender": "@rantanplan:server.name",
+                "content": { "body": "Hi jj!", "msgtype": "m.text" },
+            }),
+            json!({
+                "type": "m.room.message",
+                "sender": "@rantanplan:server.name",
+                "content": { "body": "Hey JOLLY JUMPER", "msgtype": "m.text" },
+            }),
+            json!({
+                "type": "m.room.message",
+                "sender": "@rantanplan:server.name",
+                "content": { "body": "@room Attention please!", "msgtype": "m.text" },
+            }),
+            json!({
+                "type": "m.room.message",
+                "sender": "@rantanplan:server.name",
+                "content": { "msgtype": "m.notice" },
+            }),
+            json!({
+                "type": "m.room.member",
+                "sender": "@rantanplan:server.name",
+                "state_key": "@jj:server.name",
+                "content": { "membership": "invite" },
+            }),
+            json!({ "type": "m.room.message", "sender": "@jj:server.name" }),
+            json!({}),
+        ]
+        .iter()
+        .map(|json| Raw::new(json).unwrap().cast())
+        .collect()
+    }
+
+    #[test]
+    fn same_actions_as_ruleset() {
+        let mut set = Ruleset::server_default(user_id!("@jj:server.name"));
+        set.sender.insert(
+            SimplePushRuleInit {
+                actions: vec![Action::DontNotify],
+                default: false,
+                enabled: false,
+                rule_id: "@rantanplan:server.name".into(),
+            }
+            .into(),
+        );
+        let compiled = CompiledRuleset::from(set.clone());
+
+        for context in [context(2), context(100)] {
+            for event in events() {
+                let flattened = FlattenedJson::from_raw(&event);
+                assert_eq!(
+                    compiled.get_match(&flattened, &context).map(|rule| rule.rule_id()),
+                    set.get_match(&event, &context).map(|rule| rule.rule_id()),
+                    "{}",
+                    event.json()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn simple_rules() {
+        let mut set = Ruleset::new();
+        set.room.insert(SimplePushRule::from(SimplePushRuleInit {
+            actions: vec![Action::DontNotify],
+            default: false,
+            enabled: true,
+            rule_id: "!far_west:server.name".into(),
+        }));
+        let compiled = CompiledRuleset::from(set);
+
+        let event = Raw::new(&json!({ "sender": "@rantanplan:server.name" })).unwrap();
+        let event = FlattenedJson::from_raw(&event);
+
+        assert_matches!(
+            compiled.get_match(&event, &context(2)),
+            Some(AnyPushRuleRef::Room(rule)) if rule.rule_id == "!far_west:server.name"
+        );
+        assert_matches!(compiled.get_actions(&event, &context(2)), [Action::DontNotify]);
+
+        let mut context = context(2);
+        context.room_id = room_id!("!dm:server.name").to_owned();
+        assert_matches!(compiled.get_actions(&event, &context), []);
+    }
+}
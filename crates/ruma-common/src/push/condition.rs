@@ -1,15 +1,15 @@
-use std::{collections::BTreeMap, ops::RangeBounds, str::FromStr};
+use std::{borrow::Cow, collections::BTreeMap, ops::RangeBounds, str::FromStr};
 
 use js_int::{Int, UInt};
 use serde::{Deserialize, Serialize};
-use serde_json::{to_value as to_json_value, value::Value as JsonValue};
-use tracing::{instrument, warn};
 use wildmatch::WildMatch;
 
-use crate::{power_levels::NotificationPowerLevels, serde::Raw, OwnedRoomId, OwnedUserId, UserId};
+use crate::{power_levels::NotificationPowerLevels, OwnedRoomId, OwnedUserId, UserId};
 
+mod flattened_json;
 mod room_member_count_is;
 
+pub use flattened_json::{FlattenedJson, FlattenedJsonValue, ScalarJsonValue};
 pub use room_member_count_is::{ComparisonOperator, RoomMemberCountIs};
 
 /// A condition that must apply for an associated push rule's action to be taken.
@@ -48,6 +48,60 @@ pub enum PushCondition {
         /// `content`.
         key: String,
     },
+
+    /// Exact value match on a property of the event.
+    #[cfg(feature = "unstable-msc3758")]
+    #[serde(rename = "com.beeper.msc3758.exact_event_match")]
+    EventPropertyIs {
+        /// The dot-separated path of the property of the event to match.
+        key: String,
+
+        /// The value to match against.
+        value: ScalarJsonValue,
+    },
+
+    /// Exact value match on a value in an array property of the event.
+    #[cfg(feature = "unstable-msc3966")]
+    #[serde(rename = "org.matrix.msc3966.exact_event_property_contains")]
+    EventPropertyContains {
+        /// The dot-separated path of the array property of the event to match.
+        key: String,
+
+        /// The value that the array must contain.
+        value: ScalarJsonValue,
+    },
+
+    /// A glob pattern match on a field of an event that the event relates to.
+    ///
+    /// The related events must be added to the [`FlattenedJson`] of the event with
+    /// [`FlattenedJson::add_related_event`], otherwise this condition never holds.
+    #[cfg(feature = "unstable-msc3664")]
+    #[serde(rename = "im.nheko.msc3664.related_event_match")]
+    RelatedEventMatch {
+        /// The dot-separated field of the related event to match.
+        ///
+        /// If this is `None`, the condition holds if there is a related event with the relation
+        /// type.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+
+        /// The glob-style pattern to match against.
+        ///
+        /// If this is `None`, the condition holds if there is a related event with the relation
+        /// type.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pattern: Option<String>,
+
+        /// The relation type of the related event.
+        rel_type: String,
+
+        /// Whether a reply relation that is only a fallback, like in threads, should be taken
+        /// into account.
+        ///
+        /// Defaults to `false`.
+        #[serde(default, skip_serializing_if = "crate::serde::is_default")]
+        include_fallbacks: bool,
+    },
 }
 
 pub(super) fn check_event_match(
@@ -56,15 +110,108 @@ pub(super) fn check_event_match(
     pattern: &str,
     context: &PushConditionRoomCtx,
 ) -> bool {
-    let value = match key {
-        "room_id" => context.room_id.as_str(),
-        _ => match event.get(key) {
-            Some(v) => v,
-            None => return false,
+    match event_match_value(event, key, context) {
+        Some(value) => Pattern::new(pattern, key == "content.body").matches_lowercase(&value),
+        None => false,
+    }
+}
+
+/// The lowercased value of the given `key` to match the pattern of an `event_match` condition
+/// against.
+pub(super) fn event_match_value<'a>(
+    event: &'a FlattenedJson,
+    key: &str,
+    context: &PushConditionRoomCtx,
+) -> Option<Cow<'a, str>> {
+    match key {
+        "room_id" => Some(context.room_id.as_str().to_lowercase().into()),
+        _ => event.get_lowercase(key).map(Cow::Borrowed),
+    }
+}
+
+pub(super) fn check_contains_display_name(
+    event: &FlattenedJson,
+    context: &PushConditionRoomCtx,
+) -> bool {
+    match event.get_lowercase("content.body") {
+        Some(value) => Pattern::new(&context.user_display_name, true).matches_lowercase(value),
+        None => false,
+    }
+}
+
+pub(super) fn check_sender_notification_permission(
+    event: &FlattenedJson,
+    key: &str,
+    context: &PushConditionRoomCtx,
+) -> bool {
+    let sender_id = match event.get("sender") {
+        Some(v) => match <&UserId>::try_from(v) {
+            Ok(u) => u,
+            Err(_) => return false,
         },
+        None => return false,
     };
 
-    value.matches_pattern(pattern, key == "content.body")
+    let sender_level =
+        context.users_power_levels.get(sender_id).unwrap_or(&context.default_power_level);
+
+    match context.notification_power_levels.get(key) {
+        Some(l) => sender_level >= l,
+        None => false,
+    }
+}
+
+#[cfg(feature = "unstable-msc3758")]
+pub(super) fn check_event_property_is(
+    event: &FlattenedJson,
+    key: &str,
+    value: &ScalarJsonValue,
+) -> bool {
+    event.get_value(key).map_or(false, |v| v == value)
+}
+
+#[cfg(feature = "unstable-msc3966")]
+pub(super) fn check_event_property_contains(
+    event: &FlattenedJson,
+    key: &str,
+    value: &ScalarJsonValue,
+) -> bool {
+    match event.get_value(key) {
+        Some(FlattenedJsonValue::Array(values)) => values.contains(value),
+        _ => false,
+    }
+}
+
+#[cfg(feature = "unstable-msc3664")]
+pub(super) fn check_related_event_match(
+    event: &FlattenedJson,
+    key: Option<&str>,
+    pattern: Option<&str>,
+    rel_type: &str,
+    include_fallbacks: bool,
+    context: &PushConditionRoomCtx,
+) -> bool {
+    #[cfg(not(feature = "unstable-msc3873"))]
+    const IS_FALLING_BACK: &str = "content.m.relates_to.is_falling_back";
+    #[cfg(feature = "unstable-msc3873")]
+    const IS_FALLING_BACK: &str = r"content.m\.relates_to.is_falling_back";
+
+    let related_event = match event.related_event(rel_type) {
+        Some(related_event) => related_event,
+        None => return false,
+    };
+
+    if rel_type == "m.in_reply_to"
+        && !include_fallbacks
+        && event.get_value(IS_FALLING_BACK) == Some(&FlattenedJsonValue::Bool(true))
+    {
+        return false;
+    }
+
+    match (key, pattern) {
+        (Some(key), Some(pattern)) => check_event_match(related_event, key, pattern, context),
+        _ => true,
+    }
 }
 
 impl PushCondition {
@@ -81,33 +228,27 @@ impl PushCondition {
 
         match self {
             Self::EventMatch { key, pattern } => check_event_match(event, key, pattern, context),
-            Self::ContainsDisplayName => {
-                let value = match event.get("content.body") {
-                    Some(v) => v,
-                    None => return false,
-                };
-
-                value.matches_pattern(&context.user_display_name, true)
-            }
+            Self::ContainsDisplayName => check_contains_display_name(event, context),
             Self::RoomMemberCount { is } => is.contains(&context.member_count),
             Self::SenderNotificationPermission { key } => {
-                let sender_id = match event.get("sender") {
-                    Some(v) => match <&UserId>::try_from(v) {
-                        Ok(u) => u,
-                        Err(_) => return false,
-                    },
-                    None => return false,
-                };
-
-                let sender_level = context
-                    .users_power_levels
-                    .get(sender_id)
-                    .unwrap_or(&context.default_power_level);
-
-                match context.notification_power_levels.get(key) {
-                    Some(l) => sender_level >= l,
-                    None => false,
-                }
+                check_sender_notification_permission(event, key, context)
+            }
+            #[cfg(feature = "unstable-msc3758")]
+            Self::EventPropertyIs { key, value } => check_event_property_is(event, key, value),
+            #[cfg(feature = "unstable-msc3966")]
+            Self::EventPropertyContains { key, value } => {
+                check_event_property_contains(event, key, value)
+            }
+            #[cfg(feature = "unstable-msc3664")]
+            Self::RelatedEventMatch { key, pattern, rel_type, include_fallbacks } => {
+                check_related_event_match(
+                    event,
+                    key.as_deref(),
+                    pattern.as_deref(),
+                    rel_type,
+                    *include_fallbacks,
+                    context,
+                )
             }
         }
     }
@@ -139,6 +280,63 @@ pub struct PushConditionRoomCtx {
     pub notification_power_levels: NotificationPowerLevels,
 }
 
+/// A case-insensitive glob pattern with wildcards `*` and `?`.
+///
+/// The pattern is compiled once, so it can be matched against many values efficiently.
+#[derive(Clone, Debug)]
+pub(crate) struct Glob(WildMatch);
+
+impl Glob {
+    /// Compiles the given glob pattern.
+    pub(crate) fn new(pattern: &str) -> Self {
+        Self(WildMatch::new(&pattern.to_lowercase()))
+    }
+
+    /// Whether the given value matches this pattern.
+    pub(crate) fn matches(&self, value: &str) -> bool {
+        self.matches_lowercase(&value.to_lowercase())
+    }
+
+    /// Whether the given value, that must already be lowercased, matches this pattern.
+    pub(crate) fn matches_lowercase(&self, value: &str) -> bool {
+        self.0.matches(value)
+    }
+}
+
+/// A compiled pattern of an `event_match` condition or a content rule.
+#[derive(Clone, Debug)]
+pub(super) enum Pattern {
+    /// A glob pattern.
+    Glob(Glob),
+
+    /// A lowercased pattern to look for as a word, for matches against `content.body`.
+    Words(String),
+}
+
+impl Pattern {
+    /// Compiles the given pattern.
+    ///
+    /// If `match_words` is `true`, the pattern is looked for as a word in the values, otherwise
+    /// it is used as a glob with wildcards `*` and `?`.
+    pub(super) fn new(pattern: &str, match_words: bool) -> Self {
+        if match_words {
+            Self::Words(pattern.to_lowercase())
+        } else {
+            Self::Glob(Glob::new(pattern))
+        }
+    }
+
+    /// Whether the given value, that must already be lowercased, matches this pattern.
+    ///
+    /// Since the pattern is lowercased too, the match is case insensitive.
+    pub(super) fn matches_lowercase(&self, value: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.matches_lowercase(value),
+            Self::Words(pattern) => value.matches_word(pattern),
+        }
+    }
+}
+
 /// Additional functions for character matching.
 trait CharExt {
     /// Whether or not this char can be part of a word.
@@ -167,15 +365,6 @@ trait StrExt {
     /// Returns `None` if there's no previous char. Otherwise, returns the char.
     fn find_prev_char(&self, index: usize) -> Option<char>;
 
-    /// Matches this string against `pattern`.
-    ///
-    /// The match is case insensitive.
-    ///
-    /// If `match_words` is `true`, looks for `pattern` as a substring of `self`,
-    /// and checks that it is separated from other words. Otherwise, checks
-    /// `pattern` as a glob with wildcards `*` and `?`.
-    fn matches_pattern(&self, pattern: &str, match_words: bool) -> bool;
-
     /// Matches this string against `pattern`, with word boundaries.
     ///
     /// The match is case sensitive.
@@ -210,17 +399,6 @@ impl StrExt for str {
         Some(self.char_at(pos))
     }
 
-    fn matches_pattern(&self, pattern: &str, match_words: bool) -> bool {
-        let value = &self.to_lowercase();
-        let pattern = &pattern.to_lowercase();
-
-        if match_words {
-            value.matches_word(pattern)
-        } else {
-            WildMatch::new(pattern).matches(value)
-        }
-    }
-
     fn matches_word(&self, pattern: &str) -> bool {
         if self == pattern {
             return true;
@@ -267,46 +445,6 @@ impl StrExt for str {
     }
 }
 
-/// The flattened representation of a JSON object.
-#[derive(Clone, Debug)]
-pub struct FlattenedJson {
-    /// The internal map containing the flattened JSON as a pair path, value.
-    map: BTreeMap<String, String>,
-}
-
-impl FlattenedJson {
-    /// Create a `FlattenedJson` from `Raw`.
-    pub fn from_raw<T>(raw: &Raw<T>) -> Self {
-        let mut s = Self { map: BTreeMap::new() };
-        s.flatten_value(to_json_value(raw).unwrap(), "".into());
-        s
-    }
-
-    /// Flatten and insert the `value` at `path`.
-    #[instrument(skip(self, value))]
-    fn flatten_value(&mut self, value: JsonValue, path: String) {
-        match value {
-            JsonValue::Object(fields) => {
-                for (key, value) in fields {
-                    let path = if path.is_empty() { key } else { format!("{}.{}", path, key) };
-                    self.flatten_value(value, path);
-                }
-            }
-            JsonValue::String(s) => {
-                if self.map.insert(path.clone(), s).is_some() {
-                    warn!("Duplicate path in flattened JSON: {}", path);
-                }
-            }
-            JsonValue::Number(_) | JsonValue::Bool(_) | JsonValue::Array(_) | JsonValue::Null => {}
-        }
-    }
-
-    /// Value associated with the given `path`.
-    pub fn get(&self, path: &str) -> Option<&str> {
-        self.map.get(path).map(|s| s.as_str())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -314,14 +452,26 @@ mod tests {
     use crate::{room_id, serde::Raw, user_id};
     use assert_matches::assert_matches;
     use js_int::{int, uint};
-    use maplit::btreemap;
     use serde_json::{
         from_value as from_json_value, json, to_value as to_json_value, Value as JsonValue,
     };
 
-    use super::{FlattenedJson, PushCondition, PushConditionRoomCtx, RoomMemberCountIs, StrExt};
+    use super::{
+        FlattenedJson, Pattern, PushCondition, PushConditionRoomCtx, RoomMemberCountIs, StrExt,
+    };
     use crate::power_levels::NotificationPowerLevels;
 
+    trait PatternExt {
+        /// Matches this string against `pattern`, case insensitively.
+        fn matches_pattern(&self, pattern: &str, match_words: bool) -> bool;
+    }
+
+    impl PatternExt for str {
+        fn matches_pattern(&self, pattern: &str, match_words: bool) -> bool {
+            Pattern::new(pattern, match_words).matches_lowercase(&self.to_lowercase())
+        }
+    }
+
     #[test]
     fn serialize_event_match_condition() {
         let json_data = json!({
@@ -559,45 +709,147 @@ mod tests {
     }
 
     #[test]
-    fn flattened_json_values() {
-        let raw = serde_json::from_str::<Raw<JsonValue>>(
-            r#"{
-                "string": "Hello World",
-                "number": 10,
-                "array": [1, 2],
-                "boolean": true,
-                "null": null
-            }"#,
-        )
-        .unwrap();
+    #[cfg(feature = "unstable-msc3758")]
+    fn event_property_is_condition() {
+        use super::ScalarJsonValue;
+
+        let condition = PushCondition::EventPropertyIs {
+            key: "content.is_urgent".into(),
+            value: ScalarJsonValue::Bool(true),
+        };
+        let json_data = json!({
+            "kind": "com.beeper.msc3758.exact_event_match",
+            "key": "content.is_urgent",
+            "value": true,
+        });
+        assert_eq!(to_json_value(&condition).unwrap(), json_data);
+        assert_matches!(
+            from_json_value::<PushCondition>(json_data).unwrap(),
+            PushCondition::EventPropertyIs { key, value: ScalarJsonValue::Bool(true) }
+            if key == "content.is_urgent"
+        );
+
+        let context = context();
+        let urgent = flattened(
+            json!({ "sender": "@worthy_whale:server.name", "content": { "is_urgent": true } }),
+        );
+        let not_urgent = flattened(
+            json!({ "sender": "@worthy_whale:server.name", "content": { "is_urgent": "true" } }),
+        );
+        assert!(condition.applies(&urgent, &context));
+        assert!(!condition.applies(&not_urgent, &context));
 
-        let flattened = FlattenedJson::from_raw(&raw);
-        assert_eq!(flattened.map, btreemap! { "string".into() => "Hello World".into() });
+        let null = PushCondition::EventPropertyIs {
+            key: "content.topic".into(),
+            value: ScalarJsonValue::Null,
+        };
+        assert!(null.applies(&flattened(json!({ "content": { "topic": null } })), &context));
+        assert!(!null.applies(&urgent, &context));
     }
 
     #[test]
-    fn flattened_json_nested() {
-        let raw = serde_json::from_str::<Raw<JsonValue>>(
-            r#"{
-                "desc": "Level 0",
-                "up": {
-                    "desc": "Level 1",
-                    "up": {
-                        "desc": "Level 2"
-                    }
-                }
-            }"#,
-        )
-        .unwrap();
+    #[cfg(feature = "unstable-msc3966")]
+    fn event_property_contains_condition() {
+        use super::ScalarJsonValue;
 
-        let flattened = FlattenedJson::from_raw(&raw);
-        assert_eq!(
-            flattened.map,
-            btreemap! {
-                "desc".into() => "Level 0".into(),
-                "up.desc".into() => "Level 1".into(),
-                "up.up.desc".into() => "Level 2".into(),
-            },
+        let condition = PushCondition::EventPropertyContains {
+            key: "content.alias".into(),
+            value: "#room:server.name".into(),
+        };
+        let json_data = json!({
+            "kind": "org.matrix.msc3966.exact_event_property_contains",
+            "key": "content.alias",
+            "value": "#room:server.name",
+        });
+        assert_eq!(to_json_value(&condition).unwrap(), json_data);
+        assert_matches!(
+            from_json_value::<PushCondition>(json_data).unwrap(),
+            PushCondition::EventPropertyContains { key, value: ScalarJsonValue::String(value) }
+            if key == "content.alias" && value == "#room:server.name"
+        );
+
+        let context = context();
+        assert!(condition.applies(
+            &flattened(
+                json!({ "content": { "alias": ["#other:server.name", "#room:server.name"] } })
+            ),
+            &context
+        ));
+        assert!(!condition
+            .applies(&flattened(json!({ "content": { "alias": "#room:server.name" } })), &context));
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-msc3664")]
+    fn related_event_match_condition() {
+        let condition = PushCondition::RelatedEventMatch {
+            key: Some("sender".into()),
+            pattern: Some("@gorilla:server.name".into()),
+            rel_type: "m.in_reply_to".into(),
+            include_fallbacks: false,
+        };
+        let json_data = json!({
+            "kind": "im.nheko.msc3664.related_event_match",
+            "key": "sender",
+            "pattern": "@gorilla:server.name",
+            "rel_type": "m.in_reply_to",
+        });
+        assert_eq!(to_json_value(&condition).unwrap(), json_data);
+        assert_matches!(
+            from_json_value::<PushCondition>(json_data).unwrap(),
+            PushCondition::RelatedEventMatch { key: Some(_), pattern: Some(_), rel_type, include_fallbacks: false }
+            if rel_type == "m.in_reply_to"
         );
+
+        let context = context();
+        let own_event = flattened(json!({ "sender": "@gorilla:server.name" }));
+
+        let mut reply = flattened(json!({ "sender": "@worthy_whale:server.name" }));
+        assert!(!condition.applies(&reply, &context));
+        reply.add_related_event("m.in_reply_to", own_event.clone());
+        assert!(condition.applies(&reply, &context));
+
+        let mut fallback = flattened(json!({
+            "sender": "@worthy_whale:server.name",
+            "content": {
+                "m.relates_to": { "rel_type": "m.thread", "is_falling_back": true },
+            },
+        }));
+        fallback.add_related_event("m.in_reply_to", own_event);
+        assert!(!condition.applies(&fallback, &context));
+
+        let with_fallbacks = PushCondition::RelatedEventMatch {
+            key: None,
+            pattern: None,
+            rel_type: "m.in_reply_to".into(),
+            include_fallbacks: true,
+        };
+        assert!(with_fallbacks.applies(&fallback, &context));
+    }
+
+    #[cfg(any(
+        feature = "unstable-msc3664",
+        feature = "unstable-msc3758",
+        feature = "unstable-msc3966"
+    ))]
+    fn context() -> PushConditionRoomCtx {
+        PushConditionRoomCtx {
+            room_id: room_id!("!room:server.name").to_owned(),
+            member_count: uint!(3),
+            user_id: user_id!("@gorilla:server.name").to_owned(),
+            user_display_name: "Groovy Gorilla".into(),
+            users_power_levels: BTreeMap::new(),
+            default_power_level: int!(50),
+            notification_power_levels: NotificationPowerLevels { room: int!(50) },
+        }
+    }
+
+    #[cfg(any(
+        feature = "unstable-msc3664",
+        feature = "unstable-msc3758",
+        feature = "unstable-msc3966"
+    ))]
+    fn flattened(json: JsonValue) -> FlattenedJson {
+        FlattenedJson::from_raw(&Raw::new(&json).unwrap())
     }
 }
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+
+use js_int::Int;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{to_value as to_json_value, value::Value as JsonValue};
+use tracing::{instrument, warn};
+
+use crate::serde::Raw;
+
+/// The flattened representation of a JSON object.
+///
+/// The keys are the dot-separated paths of the leaves of the object. With the
+/// `unstable-msc3873` feature, dots and backslashes in the names of the fields are escaped with a
+/// backslash, so `{ "m.relates_to": { "rel_type": "m.thread" } }` has the key
+/// `m\.relates_to.rel_type`.
+///
+/// Objects are never stored as values, and only the scalar values of arrays are kept. Numbers that
+/// are not integers in the range of an [`Int`] are left out.
+#[derive(Clone, Debug)]
+pub struct FlattenedJson {
+    /// The internal map containing the flattened JSON as a pair path, value.
+    map: BTreeMap<String, FlattenedJsonValue>,
+
+    /// The lowercased string values that are not lowercase already, for case-insensitive matching.
+    lowercase: BTreeMap<String, String>,
+
+    /// The events related to this one, by relation type.
+    #[cfg(feature = "unstable-msc3664")]
+    related_events: BTreeMap<String, FlattenedJson>,
+}
+
+impl FlattenedJson {
+    /// Create a `FlattenedJson` from `Raw`.
+    pub fn from_raw<T>(raw: &Raw<T>) -> Self {
+        let mut s = Self {
+            map: BTreeMap::new(),
+            lowercase: BTreeMap::new(),
+            #[cfg(feature = "unstable-msc3664")]
+            related_events: BTreeMap::new(),
+        };
+        s.flatten_value(to_json_value(raw).unwrap(), "".into());
+        s
+    }
+
+    /// Flatten and insert the `value` at `path`.
+    #[instrument(skip(self, value))]
+    fn flatten_value(&mut self, value: JsonValue, path: String) {
+        let value = match value {
+            JsonValue::Object(fields) => {
+                for (key, value) in fields {
+                    #[cfg(feature = "unstable-msc3873")]
+                    let key = escape_key(&key);
+
+                    let path = if path.is_empty() { key } else { format!("{}.{}", path, key) };
+                    self.flatten_value(value, path);
+                }
+                return;
+            }
+            JsonValue::Array(values) => FlattenedJsonValue::Array(
+                values.into_iter().filter_map(ScalarJsonValue::from_json).collect(),
+            ),
+            value => match ScalarJsonValue::from_json(value) {
+                Some(value) => value.into(),
+                None => return,
+            },
+        };
+
+        if let FlattenedJsonValue::String(s) = &value {
+            let lowercase = s.to_lowercase();
+            if lowercase != *s {
+                self.lowercase.insert(path.clone(), lowercase);
+            }
+        }
+
+        if self.map.insert(path.clone(), value).is_some() {
+            warn!("Duplicate path in flattened JSON: {}", path);
+        }
+    }
+
+    /// String value associated with the given `path`.
+    ///
+    /// Returns `None` if there is no value at this path or if it is not a string.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        match self.map.get(path)? {
+            FlattenedJsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Lowercased string value associated with the given `path`.
+    ///
+    /// Returns `None` if there is no value at this path or if it is not a string.
+    pub(crate) fn get_lowercase(&self, path: &str) -> Option<&str> {
+        self.lowercase.get(path).map(String::as_str).or_else(|| self.get(path))
+    }
+
+    /// Value associated with the given `path`.
+    pub fn get_value(&self, path: &str) -> Option<&FlattenedJsonValue> {
+        self.map.get(path)
+    }
+
+    /// Adds the event that this event relates to with the given relation type.
+    ///
+    /// `related_event_match` push conditions are evaluated against those events. For replies, the
+    /// relation type is `m.in_reply_to`.
+    #[cfg(feature = "unstable-msc3664")]
+    pub fn add_related_event(&mut self, rel_type: impl Into<String>, event: FlattenedJson) {
+        self.related_events.insert(rel_type.into(), event);
+    }
+
+    /// The event that this event relates to with the given relation type, if it was added.
+    #[cfg(feature = "unstable-msc3664")]
+    pub fn related_event(&self, rel_type: &str) -> Option<&FlattenedJson> {
+        self.related_events.get(rel_type)
+    }
+}
+
+/// Escapes the dots and backslashes in the given field name.
+#[cfg(feature = "unstable-msc3873")]
+fn escape_key(key: &str) -> String {
+    if key.contains(['.', '\\']) {
+        key.replace('\\', r"\\").replace('.', r"\.")
+    } else {
+        key.to_owned()
+    }
+}
+
+/// A value of a [`FlattenedJson`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub enum FlattenedJsonValue {
+    /// A string.
+    String(String),
+
+    /// An integer.
+    Integer(Int),
+
+    /// A boolean.
+    Bool(bool),
+
+    /// `null`.
+    Null,
+
+    /// An array, with only its scalar values.
+    Array(Vec<ScalarJsonValue>),
+}
+
+impl From<ScalarJsonValue> for FlattenedJsonValue {
+    fn from(value: ScalarJsonValue) -> Self {
+        match value {
+            ScalarJsonValue::String(s) => Self::String(s),
+            ScalarJsonValue::Integer(i) => Self::Integer(i),
+            ScalarJsonValue::Bool(b) => Self::Bool(b),
+            ScalarJsonValue::Null => Self::Null,
+        }
+    }
+}
+
+impl PartialEq<ScalarJsonValue> for FlattenedJsonValue {
+    fn eq(&self, other: &ScalarJsonValue) -> bool {
+        match (self, other) {
+            (Self::String(a), ScalarJsonValue::String(b)) => a == b,
+            (Self::Integer(a), ScalarJsonValue::Integer(b)) => a == b,
+            (Self::Bool(a), ScalarJsonValue::Bool(b)) => a == b,
+            (Self::Null, ScalarJsonValue::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A JSON value that is not a compound type, like the values that push conditions can compare
+/// event properties to.
+///
+/// Only integers are allowed as numbers, like in [canonical JSON].
+///
+/// [canonical JSON]: https://spec.matrix.org/v1.2/appendices/#canonical-json
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::exhaustive_enums)]
+pub enum ScalarJsonValue {
+    /// A string.
+    String(String),
+
+    /// An integer.
+    Integer(Int),
+
+    /// A boolean.
+    Bool(bool),
+
+    /// `null`.
+    Null,
+}
+
+impl ScalarJsonValue {
+    /// Converts the given JSON value, if it is a scalar with a valid type.
+    fn from_json(value: JsonValue) -> Option<Self> {
+        match value {
+            JsonValue::String(s) => Some(Self::String(s)),
+            JsonValue::Number(n) => {
+                n.as_i64().and_then(|n| Int::try_from(n).ok()).map(Self::Integer)
+            }
+            JsonValue::Bool(b) => Some(Self::Bool(b)),
+            JsonValue::Null => Some(Self::Null),
+            JsonValue::Array(_) | JsonValue::Object(_) => None,
+        }
+    }
+}
+
+impl From<&str> for ScalarJsonValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for ScalarJsonValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<Int> for ScalarJsonValue {
+    fn from(value: Int) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<bool> for ScalarJsonValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl Serialize for ScalarJsonValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Integer(i) => i.serialize(serializer),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ScalarJsonValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = JsonValue::deserialize(deserializer)?;
+        Self::from_json(value)
+            .ok_or_else(|| de::Error::custom("expected a string, an integer, a boolean or null"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use js_int::int;
+    use maplit::btreemap;
+    use serde_json::{from_value as from_json_value, json, Value as JsonValue};
+
+    use super::{FlattenedJson, FlattenedJsonValue, ScalarJsonValue};
+    use crate::serde::Raw;
+
+    #[test]
+    fn flattened_json_values() {
+        let raw = serde_json::from_str::<Raw<JsonValue>>(
+            r#"{
+                "string": "Hello World",
+                "number": 10,
+                "float": 1.5,
+                "array": [1, "two", 3.5, [4], { "five": 5 }],
+                "boolean": true,
+                "null": null
+            }"#,
+        )
+        .unwrap();
+
+        let flattened = FlattenedJson::from_raw(&raw);
+        assert_eq!(
+            flattened.map,
+            btreemap! {
+                "string".into() => FlattenedJsonValue::String("Hello World".into()),
+                "number".into() => FlattenedJsonValue::Integer(int!(10)),
+                "array".into() => FlattenedJsonValue::Array(vec![
+                    ScalarJsonValue::Integer(int!(1)),
+                    ScalarJsonValue::String("two".into()),
+                ]),
+                "boolean".into() => FlattenedJsonValue::Bool(true),
+                "null".into() => FlattenedJsonValue::Null,
+            }
+        );
+        assert_eq!(flattened.get("string"), Some("Hello World"));
+        assert_eq!(flattened.get("number"), None);
+        assert_eq!(flattened.get_value("boolean"), Some(&FlattenedJsonValue::Bool(true)));
+
+        assert_eq!(flattened.get_lowercase("string"), Some("hello world"));
+        assert_eq!(flattened.get_lowercase("number"), None);
+        assert_eq!(flattened.lowercase.len(), 1);
+    }
+
+    #[test]
+    fn flattened_json_nested() {
+        let raw = serde_json::from_str::<Raw<JsonValue>>(
+            r#"{
+                "desc": "Level 0",
+                "up": {
+                    "desc": "Level 1",
+                    "up": {
+                        "desc": "Level 2"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let flattened = FlattenedJson::from_raw(&raw);
+        assert_eq!(
+            flattened.map,
+            btreemap! {
+                "desc".into() => FlattenedJsonValue::String("Level 0".into()),
+                "up.desc".into() => FlattenedJsonValue::String("Level 1".into()),
+                "up.up.desc".into() => FlattenedJsonValue::String("Level 2".into()),
+            },
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-msc3873")]
+    fn flattened_json_escaped_keys() {
+        let raw = serde_json::from_str::<Raw<JsonValue>>(
+            r#"{
+                "m.relates_to": {
+                    "rel_type": "m.thread"
+                },
+                "back\\slash": true
+            }"#,
+        )
+        .unwrap();
+
+        let flattened = FlattenedJson::from_raw(&raw);
+        assert_eq!(
+            flattened.map,
+            btreemap! {
+                r"m\.relates_to.rel_type".into() => FlattenedJsonValue::String("m.thread".into()),
+                r"back\\slash".into() => FlattenedJsonValue::Bool(true),
+            },
+        );
+    }
+
+    #[test]
+    fn scalar_json_value_serde() {
+        assert_eq!(
+            from_json_value::<ScalarJsonValue>(json!("foo")).unwrap(),
+            ScalarJsonValue::String("foo".into())
+        );
+        assert_eq!(
+            from_json_value::<ScalarJsonValue>(json!(-3)).unwrap(),
+            ScalarJsonValue::Integer(int!(-3))
+        );
+        assert_eq!(from_json_value::<ScalarJsonValue>(json!(null)).unwrap(), ScalarJsonValue::Null);
+        from_json_value::<ScalarJsonValue>(json!(1.5)).unwrap_err();
+        from_json_value::<ScalarJsonValue>(json!([true])).unwrap_err();
+
+        assert_eq!(serde_json::to_value(ScalarJsonValue::Bool(false)).unwrap(), json!(false));
+        assert_eq!(serde_json::to_value(ScalarJsonValue::Null).unwrap(), json!(null));
+    }
+}
@@ -0,0 +1,80 @@
+use std::{error::Error, fmt};
+
+/// An error that happens when inserting a push rule in a `Ruleset`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub enum InsertPushRuleError {
+    /// The rule ID starts with a dot, which is reserved for server-default rules.
+    ServerDefaultRuleId,
+
+    /// The rule ID is not a valid room ID for a room rule, or user ID for a sender rule.
+    InvalidRuleId,
+
+    /// The rule was positioned relative to a server-default rule.
+    RelativeToServerDefaultRule,
+
+    /// The rule was positioned relative to a rule that doesn't exist.
+    UnknownRuleId,
+
+    /// The `before` rule has a higher priority than the `after` rule.
+    BeforeHigherThanAfter,
+}
+
+impl fmt::Display for InsertPushRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::ServerDefaultRuleId => {
+                "rule IDs starting with a dot are reserved for server-default rules"
+            }
+            Self::InvalidRuleId => "invalid rule ID",
+            Self::RelativeToServerDefaultRule => {
+                "can't place a rule relative to a server-default rule"
+            }
+            Self::UnknownRuleId => "the rule to place the rule relative to doesn't exist",
+            Self::BeforeHigherThanAfter => {
+                "the `before` rule has a higher priority than the `after` rule"
+            }
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl Error for InsertPushRuleError {}
+
+/// An error that happens when removing a push rule from a `Ruleset`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub enum RemovePushRuleError {
+    /// The rule is a server-default rule, which can't be removed.
+    ServerDefault,
+
+    /// The rule doesn't exist.
+    NotFound,
+}
+
+impl fmt::Display for RemovePushRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::ServerDefault => "server-default rules can't be removed",
+            Self::NotFound => "push rule not found",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl Error for RemovePushRuleError {}
+
+/// An error that happens when a push rule is not found in a `Ruleset`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct RuleNotFoundError;
+
+impl fmt::Display for RuleNotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("push rule not found")
+    }
+}
+
+impl Error for RuleNotFoundError {}
@@ -0,0 +1,226 @@
+use super::{Action, AnyPushRuleRef, FlattenedJson, PushCondition, PushConditionRoomCtx};
+
+/// The detailed result of evaluating a [`Ruleset`](super::Ruleset) against an event.
+///
+/// Unlike [`Ruleset::get_match`](super::Ruleset::get_match), all the rules are evaluated, so it is
+/// possible to see which rules would have applied if the matching rule didn't.
+///
+/// To get an instance of this type, use [`Ruleset::evaluate`](super::Ruleset::evaluate).
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct PushRulesEvaluation<'a> {
+    /// Whether the event was sent by the user themselves.
+    ///
+    /// Push rules never apply to those events, so `rules` is empty in this case.
+    pub own_event: bool,
+
+    /// The evaluation of every rule of the ruleset, by order of priority.
+    pub rules: Vec<PushRuleEvaluation<'a>>,
+}
+
+impl<'a> PushRulesEvaluation<'a> {
+    /// The first push rule that applies to the event, if any.
+    ///
+    /// This is the rule returned by [`Ruleset::get_match`](super::Ruleset::get_match).
+    pub fn matched_rule(&self) -> Option<AnyPushRuleRef<'a>> {
+        self.rules.iter().find(|rule| rule.applies).map(|rule| rule.rule)
+    }
+
+    /// The ID of the first push rule that applies to the event, if any.
+    pub fn matched_rule_id(&self) -> Option<&'a str> {
+        self.matched_rule().map(|rule| rule.rule_id())
+    }
+
+    /// The push actions that apply to the event.
+    ///
+    /// Returns an empty slice if no push rule applies.
+    pub fn actions(&self) -> &'a [Action] {
+        self.matched_rule().map(|rule| rule.actions()).unwrap_or(&[])
+    }
+}
+
+/// The result of evaluating a single push rule against an event.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct PushRuleEvaluation<'a> {
+    /// The push rule.
+    pub rule: AnyPushRuleRef<'a>,
+
+    /// The evaluation of each condition of the rule.
+    ///
+    /// Content, room and sender rules are represented with the equivalent `event_match`
+    /// condition.
+    pub conditions: Vec<PushConditionEvaluation>,
+
+    /// Whether the rule applies to the event.
+    ///
+    /// This is `true` if the rule is enabled and all of its conditions hold.
+    pub applies: bool,
+}
+
+impl<'a> PushRuleEvaluation<'a> {
+    pub(super) fn new(
+        rule: AnyPushRuleRef<'a>,
+        event: &FlattenedJson,
+        context: &PushConditionRoomCtx,
+    ) -> Self {
+        let conditions: Vec<_> = match rule {
+            AnyPushRuleRef::Override(rule) | AnyPushRuleRef::Underride(rule) => rule
+                .conditions
+                .iter()
+                .map(|condition| PushConditionEvaluation::new(condition.clone(), event, context))
+                .collect(),
+            AnyPushRuleRef::Content(rule) => {
+                vec![PushConditionEvaluation::event_match(
+                    "content.body",
+                    &rule.pattern,
+                    event,
+                    context,
+                )]
+            }
+            AnyPushRuleRef::Room(rule) => {
+                vec![PushConditionEvaluation::event_match("room_id", &rule.rule_id, event, context)]
+            }
+            AnyPushRuleRef::Sender(rule) => {
+                vec![PushConditionEvaluation::event_match("sender", &rule.rule_id, event, context)]
+            }
+        };
+
+        let applies = rule.enabled() && conditions.iter().all(|condition| condition.holds);
+
+        Self { rule, conditions, applies }
+    }
+}
+
+/// The result of evaluating a push condition against an event.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct PushConditionEvaluation {
+    /// The push condition.
+    pub condition: PushCondition,
+
+    /// Whether the condition holds for the event.
+    pub holds: bool,
+}
+
+impl PushConditionEvaluation {
+    fn new(
+        condition: PushCondition,
+        event: &FlattenedJson,
+        context: &PushConditionRoomCtx,
+    ) -> Self {
+        let holds = condition.applies(event, context);
+        Self { condition, holds }
+    }
+
+    fn event_match(
+        key: &str,
+        pattern: &str,
+        event: &FlattenedJson,
+        context: &PushConditionRoomCtx,
+    ) -> Self {
+        let condition =
+            PushCondition::EventMatch { key: key.to_owned(), pattern: pattern.to_owned() };
+        Self::new(condition, event, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use assert_matches::assert_matches;
+    use js_int::{int, uint};
+    use serde_json::{json, Value as JsonValue};
+
+    use crate::{
+        power_levels::NotificationPowerLevels,
+        push::{Action, PushCondition, PushConditionRoomCtx, Ruleset},
+        room_id,
+        serde::Raw,
+        user_id,
+    };
+
+    fn context() -> PushConditionRoomCtx {
+        PushConditionRoomCtx {
+            room_id: room_id!("!far_west:server.name").to_owned(),
+            member_count: uint!(100),
+            user_id: user_id!("@jj:server.name").to_owned(),
+            user_display_name: "Jolly Jumper".into(),
+            users_power_levels: BTreeMap::new(),
+            default_power_level: int!(50),
+            notification_power_levels: NotificationPowerLevels { room: int!(50) },
+        }
+    }
+
+    #[test]
+    fn evaluate_default_ruleset() {
+        let set = Ruleset::server_default(user_id!("@jj:server.name"));
+        let context = context();
+
+        let notice = Raw::<JsonValue>::new(&json!({
+            "type": "m.room.message",
+            "sender": "@rantanplan:server.name",
+            "content": { "body": "Hi jj!", "msgtype": "m.notice" },
+        }))
+        .unwrap();
+
+        let evaluation = set.evaluate(&notice, &context);
+        assert!(!evaluation.own_event);
+        assert_eq!(evaluation.rules.len(), set.iter().count());
+        assert_eq!(evaluation.matched_rule_id(), Some(".m.rule.suppress_notices"));
+        assert_eq!(
+            evaluation.matched_rule_id(),
+            set.get_match(&notice, &context).map(|rule| rule.rule_id())
+        );
+        assert_matches!(evaluation.actions(), [Action::DontNotify]);
+
+        // The master rule is disabled, so it doesn't apply even though it has no conditions.
+        let master = &evaluation.rules[0];
+        assert_eq!(master.rule.rule_id(), ".m.rule.master");
+        assert!(master.conditions.is_empty());
+        assert!(!master.applies);
+
+        // The content rule would have applied too.
+        let user_name = evaluation
+            .rules
+            .iter()
+            .find(|rule| rule.rule.rule_id() == ".m.rule.contains_user_name")
+            .unwrap();
+        assert!(user_name.applies);
+        assert_matches!(
+            user_name.conditions.as_slice(),
+            [condition] if condition.holds && matches!(
+                &condition.condition,
+                PushCondition::EventMatch { key, pattern }
+                    if key == "content.body" && pattern == "jj"
+            )
+        );
+
+        let invite = evaluation
+            .rules
+            .iter()
+            .find(|rule| rule.rule.rule_id() == ".m.rule.invite_for_me")
+            .unwrap();
+        assert!(!invite.applies);
+        assert!(invite.conditions.iter().all(|condition| !condition.holds));
+    }
+
+    #[test]
+    fn evaluate_own_event() {
+        let set = Ruleset::server_default(user_id!("@jj:server.name"));
+
+        let message = Raw::<JsonValue>::new(&json!({
+            "type": "m.room.message",
+            "sender": "@jj:server.name",
+            "content": { "body": "Hi!", "msgtype": "m.text" },
+        }))
+        .unwrap();
+
+        let evaluation = set.evaluate(&message, &context());
+        assert!(evaluation.own_event);
+        assert!(evaluation.rules.is_empty());
+        assert_eq!(evaluation.matched_rule_id(), None);
+        assert_matches!(evaluation.actions(), []);
+    }
+}
@@ -1,11 +1,14 @@
 ///! Constructors for [predefined push rules].
 ///!
 ///! [predefined push rules]: https://spec.matrix.org/v1.2/client-server-api/#predefined-rules
-use indexmap::indexset;
+use std::{hash::Hash, mem};
+
+use indexmap::{indexset, Equivalent, IndexSet};
+use serde_json::to_value as to_json_value;
 
 use super::{
-    Action::*, ConditionalPushRule, PatternedPushRule, PushCondition::*, RoomMemberCountIs,
-    Ruleset, Tweak,
+    Action, Action::*, ConditionalPushRule, PatternedPushRule, PushCondition::*, RoomMemberCountIs,
+    RuleKind, Ruleset, SimplePushRule, Tweak,
 };
 use crate::UserId;
 
@@ -65,6 +68,219 @@ impl Ruleset {
             ..Default::default()
         }
     }
+
+    /// Update the server-default rules of this ruleset to the given ones.
+    ///
+    /// This is meant to be used with [`Ruleset::server_default`] to bring a ruleset that was
+    /// created with an older version of the predefined rules, `previous_server_default`, up to
+    /// date.
+    ///
+    /// The conditions and patterns of the server-default rules are replaced. The `enabled` flags
+    /// and the actions are replaced too, unless the user changed them from their value in
+    /// `previous_server_default`. Server-default rules that are missing are added and those that
+    /// are not in `server_default` anymore are removed. User-defined rules are not modified and
+    /// keep their priority relative to one another.
+    ///
+    /// If the previous server-default rules are not known, an empty ruleset can be used, so the
+    /// `enabled` flags and actions of all the existing rules are kept.
+    ///
+    /// Returns the changes that were made.
+    pub fn update_server_default(
+        &mut self,
+        previous_server_default: &Ruleset,
+        server_default: Ruleset,
+    ) -> ServerDefaultRulesUpdate {
+        let Ruleset { override_, content, room, sender, underride } = server_default;
+        let previous = previous_server_default;
+        let mut update = ServerDefaultRulesUpdate::default();
+
+        merge_server_default(
+            &mut self.override_,
+            &previous.override_,
+            override_,
+            RuleKind::Override,
+            &mut update,
+        );
+        merge_server_default(
+            &mut self.content,
+            &previous.content,
+            content,
+            RuleKind::Content,
+            &mut update,
+        );
+        merge_server_default(&mut self.room, &previous.room, room, RuleKind::Room, &mut update);
+        merge_server_default(
+            &mut self.sender,
+            &previous.sender,
+            sender,
+            RuleKind::Sender,
+            &mut update,
+        );
+        merge_server_default(
+            &mut self.underride,
+            &previous.underride,
+            underride,
+            RuleKind::Underride,
+            &mut update,
+        );
+
+        update
+    }
+}
+
+/// The changes made by [`Ruleset::update_server_default`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct ServerDefaultRulesUpdate {
+    /// The kinds and IDs of the server-default rules that were added.
+    pub added: Vec<(RuleKind, String)>,
+
+    /// The kinds and IDs of the server-default rules whose conditions, pattern, actions or
+    /// `enabled` flag changed.
+    pub updated: Vec<(RuleKind, String)>,
+
+    /// The kinds and IDs of the server-default rules that were removed.
+    pub removed: Vec<(RuleKind, String)>,
+}
+
+impl ServerDefaultRulesUpdate {
+    /// Whether the ruleset was left untouched.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A push rule that can be a server-default rule.
+trait ServerDefaultRule: Hash + Eq {
+    fn rule_id(&self) -> &str;
+
+    /// Whether this rule has the same conditions or pattern as the other one.
+    fn same_definition(&self, other: &Self) -> bool;
+
+    /// The `enabled` flag and the actions of this rule, that can be customized by the user.
+    fn customization(&self) -> (bool, &[Action]);
+
+    fn customization_mut(&mut self) -> (&mut bool, &mut Vec<Action>);
+}
+
+impl ServerDefaultRule for ConditionalPushRule {
+    fn rule_id(&self) -> &str {
+        &self.rule_id
+    }
+
+    fn same_definition(&self, other: &Self) -> bool {
+        // `PushCondition` doesn't implement `PartialEq`.
+        to_json_value(&self.conditions).ok() == to_json_value(&other.conditions).ok()
+    }
+
+    fn customization(&self) -> (bool, &[Action]) {
+        (self.enabled, &self.actions)
+    }
+
+    fn customization_mut(&mut self) -> (&mut bool, &mut Vec<Action>) {
+        (&mut self.enabled, &mut self.actions)
+    }
+}
+
+impl ServerDefaultRule for PatternedPushRule {
+    fn rule_id(&self) -> &str {
+        &self.rule_id
+    }
+
+    fn same_definition(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+
+    fn customization(&self) -> (bool, &[Action]) {
+        (self.enabled, &self.actions)
+    }
+
+    fn customization_mut(&mut self) -> (&mut bool, &mut Vec<Action>) {
+        (&mut self.enabled, &mut self.actions)
+    }
+}
+
+impl ServerDefaultRule for SimplePushRule {
+    fn rule_id(&self) -> &str {
+        &self.rule_id
+    }
+
+    fn same_definition(&self, _other: &Self) -> bool {
+        true
+    }
+
+    fn customization(&self) -> (bool, &[Action]) {
+        (self.enabled, &self.actions)
+    }
+
+    fn customization_mut(&mut self) -> (&mut bool, &mut Vec<Action>) {
+        (&mut self.enabled, &mut self.actions)
+    }
+}
+
+/// Whether the two lists of actions are the same.
+fn same_actions(a: &[Action], b: &[Action]) -> bool {
+    // `Action` doesn't implement `PartialEq`.
+    to_json_value(a).ok() == to_json_value(b).ok()
+}
+
+/// Replace the server-default rules in `set` by `server_default`.
+///
+/// The `enabled` flag and the actions of a rule are kept if they differ from the ones of the rule
+/// in `previous_default`, or if the rule isn't in it.
+///
+/// Like with `Ruleset::insert`, the user-defined rules are placed before the server-default rules
+/// except `.m.rule.master`.
+fn merge_server_default<T>(
+    set: &mut IndexSet<T>,
+    previous_default: &IndexSet<T>,
+    server_default: IndexSet<T>,
+    kind: RuleKind,
+    update: &mut ServerDefaultRulesUpdate,
+) where
+    T: ServerDefaultRule,
+    str: Equivalent<T>,
+{
+    let (mut old_default, user_defined): (IndexSet<_>, IndexSet<_>) =
+        mem::take(set).into_iter().partition(|rule| rule.rule_id().starts_with('.'));
+
+    let mut merge = |mut rule: T| {
+        match old_default.shift_take(rule.rule_id()) {
+            Some(old_rule) => {
+                let (old_enabled, old_actions) = old_rule.customization();
+                let previous = previous_default.get(rule.rule_id()).map(|r| r.customization());
+
+                let keep_enabled = previous.map_or(true, |(enabled, _)| enabled != old_enabled);
+                let keep_actions =
+                    previous.map_or(true, |(_, actions)| !same_actions(actions, old_actions));
+
+                let (enabled, actions) = rule.customization_mut();
+                if keep_enabled {
+                    *enabled = old_enabled;
+                }
+                if keep_actions {
+                    *actions = old_actions.to_owned();
+                }
+
+                let changed = *enabled != old_enabled
+                    || !same_actions(actions, old_actions)
+                    || !rule.same_definition(&old_rule);
+                if changed {
+                    update.updated.push((kind.clone(), rule.rule_id().to_owned()));
+                }
+            }
+            None => update.added.push((kind.clone(), rule.rule_id().to_owned())),
+        }
+
+        rule
+    };
+
+    let mut server_default = server_default.into_iter().peekable();
+    set.extend(server_default.next_if(|rule| rule.rule_id() == ".m.rule.master").map(&mut merge));
+    set.extend(user_defined);
+    set.extend(server_default.map(merge));
+
+    update.removed.extend(old_default.iter().map(|rule| (kind.clone(), rule.rule_id().to_owned())));
 }
 
 /// Default override push rules
@@ -350,3 +566,118 @@ impl ConditionalPushRule {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::{ConditionalPushRule, RuleKind, Ruleset};
+    use crate::{
+        push::{Action, AnyPushRule, PushCondition},
+        user_id,
+    };
+
+    #[test]
+    fn update_server_default() {
+        let user_id = user_id!("@jj:server.name");
+        let mut previous = Ruleset::server_default(user_id);
+
+        // Simulate a ruleset created with older predefined rules.
+        previous.override_.shift_remove(".m.rule.tombstone");
+        previous.override_.replace(ConditionalPushRule {
+            conditions: vec![PushCondition::EventMatch {
+                key: "content.body".into(),
+                pattern: "@room".into(),
+            }],
+            ..ConditionalPushRule::roomnotif()
+        });
+        previous.underride.insert(ConditionalPushRule {
+            rule_id: ".m.rule.old".into(),
+            ..ConditionalPushRule::message()
+        });
+        previous.underride.replace(ConditionalPushRule {
+            actions: vec![Action::DontNotify],
+            ..ConditionalPushRule::encrypted()
+        });
+        previous.underride.replace(ConditionalPushRule {
+            actions: vec![Action::DontNotify],
+            ..ConditionalPushRule::message()
+        });
+        let mut set = previous.clone();
+
+        // Customize the ruleset.
+        set.set_enabled(RuleKind::Override, ".m.rule.suppress_notices", false).unwrap();
+        set.set_actions(RuleKind::Override, ".m.rule.member_event", vec![Action::Notify]).unwrap();
+        set.set_actions(RuleKind::Underride, ".m.rule.message", vec![]).unwrap();
+        set.insert(
+            AnyPushRule::Override(ConditionalPushRule {
+                actions: vec![],
+                default: false,
+                enabled: true,
+                rule_id: "mine".into(),
+                conditions: vec![],
+            }),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let update = set.update_server_default(&previous, Ruleset::server_default(user_id));
+        assert_eq!(update.added, [(RuleKind::Override, ".m.rule.tombstone".to_owned())]);
+        assert_eq!(
+            update.updated,
+            [
+                (RuleKind::Override, ".m.rule.roomnotif".to_owned()),
+                (RuleKind::Underride, ".m.rule.encrypted".to_owned())
+            ]
+        );
+        assert_eq!(update.removed, [(RuleKind::Underride, ".m.rule.old".to_owned())]);
+
+        let rule_ids: Vec<_> = set.override_.iter().map(|rule| rule.rule_id.as_str()).collect();
+        assert_eq!(
+            rule_ids[..4],
+            [".m.rule.master", "mine", ".m.rule.suppress_notices", ".m.rule.invite_for_me"]
+        );
+        assert!(rule_ids.contains(&".m.rule.tombstone"));
+        assert!(set.underride.get(".m.rule.old").is_none());
+
+        assert!(!set.get(RuleKind::Override, ".m.rule.suppress_notices").unwrap().enabled());
+        assert_matches!(
+            set.get(RuleKind::Override, ".m.rule.member_event").unwrap().actions(),
+            [Action::Notify]
+        );
+        assert_matches!(
+            set.override_.get(".m.rule.roomnotif").unwrap().conditions.as_slice(),
+            [PushCondition::EventMatch { .. }, PushCondition::SenderNotificationPermission { .. }]
+        );
+
+        // The actions of the rules that were not customized are updated.
+        assert_matches!(
+            set.get(RuleKind::Underride, ".m.rule.encrypted").unwrap().actions(),
+            [Action::Notify, ..]
+        );
+        assert_matches!(set.get(RuleKind::Underride, ".m.rule.message").unwrap().actions(), []);
+
+        // Nothing changes when the ruleset is already up to date.
+        let server_default = Ruleset::server_default(user_id);
+        assert!(set.update_server_default(&server_default, server_default.clone()).is_empty());
+    }
+
+    #[test]
+    fn update_server_default_unknown_previous() {
+        let user_id = user_id!("@jj:server.name");
+        let mut set = Ruleset::server_default(user_id);
+        set.underride.replace(ConditionalPushRule {
+            actions: vec![Action::DontNotify],
+            ..ConditionalPushRule::message()
+        });
+
+        // Without the previous server-default rules, all actions are considered customized.
+        let update = set.update_server_default(&Ruleset::new(), Ruleset::server_default(user_id));
+        assert!(update.is_empty());
+        assert_matches!(
+            set.get(RuleKind::Underride, ".m.rule.message").unwrap().actions(),
+            [Action::DontNotify]
+        );
+    }
+}
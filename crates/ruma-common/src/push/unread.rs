@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use js_int::UInt;
+use serde::Deserialize;
+
+#[cfg(feature = "events")]
+use crate::events::receipt::ReceiptEventContent;
+use crate::{
+    receipt::ReceiptType, serde::Raw, EventId, OwnedEventId, OwnedRoomId, OwnedUserId, RoomId,
+    UserId,
+};
+
+use super::Action;
+
+/// A counter of the unread notifications and highlights of a user, per room.
+///
+/// The counter is fed with the timeline events of the rooms, with the push actions that apply to
+/// them for the user, and with the read receipts of the user. An event counts as unread until the
+/// user sends a read receipt for it or for a later event, or sends an event in the room.
+///
+/// Only the events after the latest read receipt of a room are kept, so the counter must receive
+/// the events of a room in the order of its timeline.
+#[derive(Clone, Debug)]
+pub struct UnreadCounter {
+    user_id: OwnedUserId,
+    rooms: BTreeMap<OwnedRoomId, Vec<UnreadEvent>>,
+}
+
+impl UnreadCounter {
+    /// Creates a new `UnreadCounter` for the user with the given ID.
+    pub fn new(user_id: OwnedUserId) -> Self {
+        Self { user_id, rooms: BTreeMap::new() }
+    }
+
+    /// The ID of the user whose unread events are counted.
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+
+    /// Adds the given timeline event of the given room.
+    ///
+    /// `actions` are the push actions that apply to the event for the user, as returned by
+    /// [`Ruleset::get_actions`](super::Ruleset::get_actions).
+    ///
+    /// An event sent by the user marks all the previous events of the room as read.
+    ///
+    /// Returns an error if the event doesn't have an `event_id` or a `sender`.
+    pub fn add_event<T>(
+        &mut self,
+        room_id: &RoomId,
+        event: &Raw<T>,
+        actions: &[Action],
+    ) -> serde_json::Result<()> {
+        let event = event.deserialize_as::<EventInfo>()?;
+        let events = self.rooms.entry(room_id.to_owned()).or_default();
+
+        if event.sender == self.user_id {
+            events.clear();
+            return Ok(());
+        }
+
+        // Only notifications can be highlights.
+        let notify = actions.iter().any(Action::should_notify);
+        events.push(UnreadEvent {
+            event_id: event.event_id,
+            #[cfg(feature = "unstable-msc3440")]
+            thread_root: event.content.relates_to.and_then(RelatesToInfo::into_thread_root),
+            notify,
+            highlight: notify && actions.iter().any(Action::is_highlight),
+        });
+
+        Ok(())
+    }
+
+    /// Adds the given receipt of the given user for the given event of the given room.
+    ///
+    /// Receipts of other users or of another type than [`ReceiptType::Read`] are ignored, as well
+    /// as receipts for events that were not added to the counter since the latest read receipt.
+    pub fn add_receipt(
+        &mut self,
+        room_id: &RoomId,
+        receipt_type: &ReceiptType,
+        user_id: &UserId,
+        event_id: &EventId,
+    ) {
+        if *receipt_type != ReceiptType::Read || user_id != self.user_id {
+            return;
+        }
+
+        if let Some(events) = self.rooms.get_mut(room_id) {
+            if let Some(pos) = events.iter().position(|event| event.event_id == event_id) {
+                events.drain(..=pos);
+            }
+        }
+    }
+
+    /// Adds the read receipt of the user in the given receipt event of the given room, if any.
+    #[cfg(feature = "events")]
+    pub fn add_receipt_event(&mut self, room_id: &RoomId, content: &ReceiptEventContent) {
+        if let Some((event_id, _)) = content.user_receipt(&self.user_id, ReceiptType::Read) {
+            let user_id = self.user_id.clone();
+            self.add_receipt(room_id, &ReceiptType::Read, &user_id, event_id);
+        }
+    }
+
+    /// The unread counts of the given room.
+    pub fn room_counts(&self, room_id: &RoomId) -> UnreadCounts {
+        self.counts(room_id, |_| true)
+    }
+
+    /// The unread counts of the thread with the given root in the given room.
+    ///
+    /// The thread root itself is not part of the thread.
+    #[cfg(feature = "unstable-msc3440")]
+    pub fn thread_counts(&self, room_id: &RoomId, thread_root: &EventId) -> UnreadCounts {
+        self.counts(room_id, |event| event.thread_root.as_deref() == Some(thread_root))
+    }
+
+    /// Forgets all the events of the given room.
+    pub fn remove_room(&mut self, room_id: &RoomId) {
+        self.rooms.remove(room_id);
+    }
+
+    fn counts(&self, room_id: &RoomId, filter: impl Fn(&UnreadEvent) -> bool) -> UnreadCounts {
+        let mut counts = UnreadCounts::new();
+
+        for event in self.rooms.get(room_id).into_iter().flatten().filter(|event| filter(event)) {
+            if event.notify {
+                counts.notification_count += UInt::from(1_u32);
+            }
+            if event.highlight {
+                counts.highlight_count += UInt::from(1_u32);
+            }
+        }
+
+        counts
+    }
+}
+
+/// The number of unread notifications and highlights of a room or thread.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(not(feature = "unstable-exhaustive-types"), non_exhaustive)]
+pub struct UnreadCounts {
+    /// The number of unread events that generate a notification.
+    pub notification_count: UInt,
+
+    /// The number of unread notifications with the `highlight` tweak set.
+    pub highlight_count: UInt,
+}
+
+impl UnreadCounts {
+    /// Creates new `UnreadCounts` set to zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether both counts are zero.
+    pub fn is_empty(&self) -> bool {
+        self.notification_count == UInt::MIN && self.highlight_count == UInt::MIN
+    }
+}
+
+#[derive(Clone, Debug)]
+struct UnreadEvent {
+    event_id: OwnedEventId,
+    #[cfg(feature = "unstable-msc3440")]
+    thread_root: Option<OwnedEventId>,
+    notify: bool,
+    highlight: bool,
+}
+
+/// The fields of an event needed by the `UnreadCounter`.
+#[derive(Deserialize)]
+struct EventInfo {
+    event_id: OwnedEventId,
+    sender: OwnedUserId,
+    #[cfg(feature = "unstable-msc3440")]
+    #[serde(default)]
+    content: ContentInfo,
+}
+
+#[cfg(feature = "unstable-msc3440")]
+#[derive(Default, Deserialize)]
+struct ContentInfo {
+    #[serde(rename = "m.relates_to")]
+    relates_to: Option<RelatesToInfo>,
+}
+
+#[cfg(feature = "unstable-msc3440")]
+#[derive(Deserialize)]
+struct RelatesToInfo {
+    rel_type: Option<String>,
+    event_id: Option<OwnedEventId>,
+}
+
+#[cfg(feature = "unstable-msc3440")]
+impl RelatesToInfo {
+    fn into_thread_root(self) -> Option<OwnedEventId> {
+        match self.rel_type.as_deref() {
+            Some("m.thread" | "io.element.thread") => self.event_id,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as JsonValue};
+
+    use super::{UnreadCounter, UnreadCounts};
+    use crate::{
+        event_id,
+        push::{Action, Tweak},
+        receipt::ReceiptType,
+        room_id,
+        serde::Raw,
+        user_id,
+    };
+
+    fn event(event_id: &str, sender: &str) -> Raw<JsonValue> {
+        Raw::new(&json!({
+            "type": "m.room.message",
+            "event_id": event_id,
+            "sender": sender,
+            "content": { "body": "Hello", "msgtype": "m.text" },
+        }))
+        .unwrap()
+    }
+
+    fn counts(notification_count: u32, highlight_count: u32) -> UnreadCounts {
+        UnreadCounts {
+            notification_count: notification_count.into(),
+            highlight_count: highlight_count.into(),
+        }
+    }
+
+    #[test]
+    fn count_unread_events() {
+        let room_id = room_id!("!room:server.name");
+        let other_room_id = room_id!("!other:server.name");
+        let user_id = user_id!("@jj:server.name");
+        let mut counter = UnreadCounter::new(user_id.to_owned());
+
+        let notify = [Action::Notify];
+        let highlight = [Action::Notify, Action::SetTweak(Tweak::Highlight(true))];
+
+        counter.add_event(room_id, &event("$1", "@rantanplan:server.name"), &notify).unwrap();
+        counter.add_event(room_id, &event("$2", "@rantanplan:server.name"), &highlight).unwrap();
+        counter.add_event(room_id, &event("$3", "@rantanplan:server.name"), &[]).unwrap();
+        counter.add_event(room_id, &event("$4", "@rantanplan:server.name"), &notify).unwrap();
+        counter.add_event(other_room_id, &event("$5", "@rantanplan:server.name"), &notify).unwrap();
+
+        assert_eq!(counter.room_counts(room_id), counts(3, 1));
+        assert_eq!(counter.room_counts(other_room_id), counts(1, 0));
+        assert!(counter.room_counts(room_id!("!unknown:server.name")).is_empty());
+
+        // Receipts of other users are ignored.
+        counter.add_receipt(
+            room_id,
+            &ReceiptType::Read,
+            user_id!("@rantanplan:server.name"),
+            event_id!("$4"),
+        );
+        assert_eq!(counter.room_counts(room_id), counts(3, 1));
+
+        counter.add_receipt(room_id, &ReceiptType::Read, user_id, event_id!("$2"));
+        assert_eq!(counter.room_counts(room_id), counts(1, 0));
+
+        // Receipts for events before the latest receipt are ignored.
+        counter.add_receipt(room_id, &ReceiptType::Read, user_id, event_id!("$1"));
+        assert_eq!(counter.room_counts(room_id), counts(1, 0));
+
+        // Sending an event marks the room as read.
+        counter.add_event(room_id, &event("$6", "@jj:server.name"), &[]).unwrap();
+        assert!(counter.room_counts(room_id).is_empty());
+        assert_eq!(counter.room_counts(other_room_id), counts(1, 0));
+
+        assert!(counter.add_event(room_id, &Raw::new(&json!({})).unwrap(), &notify).is_err());
+    }
+
+    #[test]
+    fn highlight_without_notify() {
+        let room_id = room_id!("!room:server.name");
+        let mut counter = UnreadCounter::new(user_id!("@jj:server.name").to_owned());
+
+        let highlight = [Action::SetTweak(Tweak::Highlight(true))];
+        counter.add_event(room_id, &event("$1", "@rantanplan:server.name"), &highlight).unwrap();
+
+        assert!(counter.room_counts(room_id).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "events")]
+    fn receipt_event() {
+        use js_int::uint;
+
+        use crate::events::receipt::ReceiptEventContent;
+
+        let room_id = room_id!("!room:server.name");
+        let mut counter = UnreadCounter::new(user_id!("@jj:server.name").to_owned());
+
+        counter
+            .add_event(room_id, &event("$1", "@rantanplan:server.name"), &[Action::Notify])
+            .unwrap();
+        counter
+            .add_event(room_id, &event("$2", "@rantanplan:server.name"), &[Action::Notify])
+            .unwrap();
+
+        let content = serde_json::from_value::<ReceiptEventContent>(json!({
+            "$1": { "m.read": { "@jj:server.name": { "ts": 1 } } },
+            "$2": { "m.read": { "@rantanplan:server.name": { "ts": 1 } } },
+        }))
+        .unwrap();
+        counter.add_receipt_event(room_id, &content);
+
+        assert_eq!(counter.room_counts(room_id).notification_count, uint!(1));
+    }
+
+    #[test]
+    #[cfg(feature = "unstable-msc3440")]
+    fn count_thread_events() {
+        let room_id = room_id!("!room:server.name");
+        let mut counter = UnreadCounter::new(user_id!("@jj:server.name").to_owned());
+
+        let thread_event = |event_id: &str| {
+            Raw::new(&json!({
+                "type": "m.room.message",
+                "event_id": event_id,
+                "sender": "@rantanplan:server.name",
+                "content": {
+                    "body": "Hello",
+                    "msgtype": "m.text",
+                    "m.relates_to": { "rel_type": "io.element.thread", "event_id": "$root" },
+                },
+            }))
+            .unwrap()
+        };
+
+        counter
+            .add_event(room_id, &event("$root", "@rantanplan:server.name"), &[Action::Notify])
+            .unwrap();
+        counter.add_event(room_id, &thread_event("$1"), &[Action::Notify]).unwrap();
+        counter.add_event(room_id, &thread_event("$2"), &[Action::Notify]).unwrap();
+
+        assert_eq!(counter.room_counts(room_id), counts(3, 0));
+        assert_eq!(counter.thread_counts(room_id, event_id!("$root")), counts(2, 0));
+        assert!(counter.thread_counts(room_id, event_id!("$1")).is_empty());
+    }
+}
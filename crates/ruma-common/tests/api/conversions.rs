@@ -176,3 +176,133 @@ mod without_query {
         assert_eq!(query, "user_id=%40_virtual_%3Aruma.io");
     }
 }
+
+mod streaming_raw_body {
+    use assert_matches::assert_matches;
+    use bytes::Bytes;
+    use futures_util::stream;
+    use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use ruma_common::api::{
+        error::{DeserializationError, FromHttpResponseError, IntoHttpError},
+        IncomingRequest as _, IncomingResponse as _, MatrixVersion, OutgoingRequest as _,
+        OutgoingResponse as _, RawBody, SendAccessToken,
+    };
+
+    use super::ruma_api;
+
+    ruma_api! {
+        metadata: {
+            description: "Uploads and downloads a file.",
+            method: POST,
+            name: "file_endpoint",
+            unstable_path: "/_matrix/foo/file",
+            rate_limited: false,
+            authentication: None,
+        }
+
+        request: {
+            #[ruma_api(header = CONTENT_TYPE)]
+            pub content_type: String,
+            #[ruma_api(raw_body)]
+            pub file: RawBody,
+        }
+
+        response: {
+            #[ruma_api(header = CONTENT_TYPE)]
+            pub content_type: String,
+            #[ruma_api(raw_body)]
+            pub file: RawBody,
+        }
+    }
+
+    const _: () = assert!(<Request as ruma_common::api::IncomingRequest>::STREAMING_BODY);
+    const _: () = assert!(<Response as ruma_common::api::IncomingResponse>::STREAMING_BODY);
+
+    fn file_stream() -> RawBody {
+        let chunks =
+            ["hello", " ", "world"].map(|chunk| Ok::<_, IntoHttpError>(Bytes::from(chunk)));
+        RawBody::from_stream(stream::iter(chunks))
+    }
+
+    #[test]
+    fn request_streaming_body() {
+        let req = Request { content_type: "text/plain".to_owned(), file: file_stream() };
+        let http_req = req
+            .try_into_streaming_http_request(
+                "https://homeserver.tld",
+                SendAccessToken::None,
+                &[MatrixVersion::V1_1],
+            )
+            .unwrap();
+        assert_eq!(http_req.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(http_req.headers().get(CONTENT_LENGTH), None);
+        assert!(http_req.body().is_stream());
+
+        let req = Request::try_from_streaming_http_request::<&str>(http_req, &[]).unwrap();
+        assert_eq!(req.content_type, "text/plain");
+        assert!(req.file.is_stream());
+
+        let req = Request {
+            content_type: "text/plain".to_owned(),
+            file: file_stream().with_content_length(11),
+        };
+        let http_req = req
+            .try_into_streaming_http_request(
+                "https://homeserver.tld",
+                SendAccessToken::None,
+                &[MatrixVersion::V1_1],
+            )
+            .unwrap();
+        assert_eq!(http_req.headers()[CONTENT_LENGTH], "11");
+        assert_eq!(http_req.body().content_length(), Some(11));
+    }
+
+    #[test]
+    fn request_buffered_body() {
+        let req =
+            Request { content_type: "text/plain".to_owned(), file: b"hello world".to_vec().into() };
+        let http_req = req
+            .try_into_http_request::<Vec<u8>>(
+                "https://homeserver.tld",
+                SendAccessToken::None,
+                &[MatrixVersion::V1_1],
+            )
+            .unwrap();
+        assert_eq!(http_req.body(), b"hello world");
+
+        let req = Request::try_from_http_request::<_, &str>(http_req, &[]).unwrap();
+        assert_eq!(req.file.as_bytes(), Some(&b"hello world"[..]));
+
+        let req = Request { content_type: "text/plain".to_owned(), file: file_stream() };
+        let result = req.try_into_http_request::<Vec<u8>>(
+            "https://homeserver.tld",
+            SendAccessToken::None,
+            &[MatrixVersion::V1_1],
+        );
+        assert_matches!(result, Err(IntoHttpError::StreamingBody));
+    }
+
+    #[test]
+    fn response_streaming_body() {
+        let res = Response { content_type: "image/png".to_owned(), file: file_stream() };
+        let http_res = res.try_into_streaming_http_response().unwrap();
+        assert_eq!(http_res.headers()[CONTENT_TYPE], "image/png");
+        assert!(http_res.body().is_stream());
+
+        let res = Response::try_from_streaming_http_response(http_res).unwrap();
+        assert_eq!(res.content_type, "image/png");
+        assert!(res.file.is_stream());
+
+        let res = Response { content_type: "image/png".to_owned(), file: file_stream() };
+        assert_matches!(res.try_into_http_response::<Vec<u8>>(), Err(IntoHttpError::StreamingBody));
+    }
+
+    #[test]
+    fn error_response_streaming_body() {
+        let http_res = http::Response::builder().status(404).body(file_stream()).unwrap();
+        assert_matches!(
+            Response::try_from_streaming_http_response(http_res),
+            Err(FromHttpResponseError::Deserialization(DeserializationError::StreamingBody))
+        );
+    }
+}
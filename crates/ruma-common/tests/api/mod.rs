@@ -9,4 +9,6 @@ mod path_arg_ordering;
 mod ruma_api;
 mod ruma_api_lifetime;
 mod ruma_api_macros;
+mod router;
 mod select_path;
+mod typed_headers;
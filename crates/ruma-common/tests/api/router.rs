@@ -0,0 +1,181 @@
+use assert_matches::assert_matches;
+use http::Method;
+use ruma_common::api::{
+    error::{FromHttpRequestError, RouteError},
+    Router,
+};
+
+mod get_thing {
+    ruma_common::api::ruma_api! {
+        metadata: {
+            description: "Get a thing.",
+            method: GET,
+            name: "get_thing",
+            unstable_path: "/_matrix/unstable/things/:thing_id",
+            r0_path: "/_matrix/r0/things/:thing_id",
+            stable_path: "/_matrix/v3/things/:thing_id",
+            rate_limited: false,
+            authentication: None,
+            added: 1.0,
+        }
+
+        request: {
+            #[ruma_api(path)]
+            pub thing_id: String,
+        }
+
+        response: {}
+    }
+}
+
+mod set_thing {
+    ruma_common::api::ruma_api! {
+        metadata: {
+            description: "Set a thing.",
+            method: PUT,
+            name: "set_thing",
+            stable_path: "/_matrix/v3/things/:thing_id",
+            rate_limited: false,
+            authentication: None,
+            added: 1.1,
+        }
+
+        request: {
+            #[ruma_api(path)]
+            pub thing_id: String,
+
+            pub value: String,
+        }
+
+        response: {}
+    }
+}
+
+mod get_default_thing {
+    ruma_common::api::ruma_api! {
+        metadata: {
+            description: "Get the default thing.",
+            method: GET,
+            name: "get_default_thing",
+            stable_path: "/_matrix/v3/things/default",
+            rate_limited: false,
+            authentication: None,
+            added: 1.1,
+        }
+
+        request: {}
+
+        response: {}
+    }
+}
+
+#[derive(Debug)]
+enum Endpoint {
+    Get(get_thing::Request),
+    Set(set_thing::Request),
+    GetDefault(get_default_thing::Request),
+}
+
+fn router() -> Router<Endpoint> {
+    Router::new()
+        .with_endpoint(Endpoint::Get)
+        .with_endpoint(Endpoint::Set)
+        .with_endpoint(Endpoint::GetDefault)
+}
+
+fn request(method: Method, path: &str, body: &'static [u8]) -> http::Request<&'static [u8]> {
+    http::Request::builder()
+        .method(method)
+        .uri(format!("https://example.org{}", path))
+        .body(body)
+        .unwrap()
+}
+
+#[test]
+fn route_all_paths() {
+    let router = router();
+
+    for path in [
+        "/_matrix/unstable/things/thing",
+        "/_matrix/r0/things/thing",
+        "/_matrix/v3/things/thing",
+        "/_matrix/v3/things/thing?with=query",
+    ] {
+        assert_matches!(
+            router.route(request(Method::GET, path, b"")),
+            Ok(Endpoint::Get(get_thing::Request { thing_id })) if thing_id == "thing"
+        );
+    }
+
+    assert_matches!(
+        router.route(request(Method::PUT, "/_matrix/v3/things/thing", br#"{ "value": "new" }"#)),
+        Ok(Endpoint::Set(set_thing::Request { thing_id, value }))
+        if thing_id == "thing" && value == "new"
+    );
+}
+
+#[test]
+fn route_percent_encoded_path_args() {
+    assert_matches!(
+        router().route(request(Method::GET, "/_matrix/v3/things/%23thing%3Aexample.org", b"")),
+        Ok(Endpoint::Get(get_thing::Request { thing_id })) if thing_id == "#thing:example.org"
+    );
+}
+
+#[test]
+fn route_most_specific_path() {
+    let router = router();
+
+    assert_matches!(
+        router.route(request(Method::GET, "/_matrix/v3/things/default", b"")),
+        Ok(Endpoint::GetDefault(_))
+    );
+    assert_matches!(
+        router.route(request(Method::PUT, "/_matrix/v3/things/default", br#"{ "value": "new" }"#)),
+        Ok(Endpoint::Set(set_thing::Request { thing_id, .. })) if thing_id == "default"
+    );
+}
+
+#[test]
+fn route_errors() {
+    let router = router();
+
+    assert_matches!(
+        router.route(request(Method::GET, "/_matrix/v3/other/thing", b"")),
+        Err(RouteError::NotFound)
+    );
+    assert_matches!(
+        router.route(request(Method::GET, "/_matrix/v3/things/thing/more", b"")),
+        Err(RouteError::NotFound)
+    );
+    assert_matches!(
+        router.route(request(Method::DELETE, "/_matrix/v3/things/thing", b"")),
+        Err(RouteError::MethodNotAllowed)
+    );
+    assert_matches!(
+        router.route(request(Method::PUT, "/_matrix/v3/things/thing", b"{}")),
+        Err(RouteError::Request(FromHttpRequestError::Deserialization(_)))
+    );
+    assert_matches!(
+        router.route(request(Method::GET, "/_matrix/v3/things/%FF", b"")),
+        Err(RouteError::Request(FromHttpRequestError::Deserialization(_)))
+    );
+}
+
+#[test]
+fn check_without_body() {
+    let router = router();
+
+    assert_matches!(
+        router.check(&request(Method::PUT, "/_matrix/v3/things/thing", b"{}")),
+        Ok(metadata) if metadata.name == "set_thing"
+    );
+    assert_matches!(
+        router.check(&request(Method::GET, "/_matrix/v3/other/thing", b"")),
+        Err(RouteError::NotFound)
+    );
+    assert_matches!(
+        router.check(&request(Method::DELETE, "/_matrix/v3/things/thing", b"")),
+        Err(RouteError::MethodNotAllowed)
+    );
+}
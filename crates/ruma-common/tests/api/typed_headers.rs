@@ -0,0 +1,126 @@
+#![allow(clippy::exhaustive_structs)]
+
+use std::time::Duration;
+
+use http::{
+    header::{HeaderValue, CONTENT_TYPE, ETAG, RETRY_AFTER},
+    HeaderMap,
+};
+use js_int::uint;
+use ruma_common::api::{
+    header::RetryAfter, IncomingRequest as _, IncomingResponse as _, MatrixVersion,
+    OutgoingRequest as _, OutgoingResponse as _, SendAccessToken,
+};
+
+mod my_endpoint {
+    use http::HeaderMap;
+    use js_int::UInt;
+    use ruma_common::api::{header::RetryAfter, ruma_api};
+
+    ruma_api! {
+        metadata: {
+            description: "Does something.",
+            method: GET,
+            name: "typed_headers",
+            unstable_path: "/_matrix/my/endpoint",
+            rate_limited: false,
+            authentication: None,
+        }
+
+        request: {
+            #[ruma_api(header = CONTENT_LENGTH)]
+            pub content_length: Option<UInt>,
+
+            #[ruma_api(header = IF_NONE_MATCH)]
+            pub if_none_match: Option<&'a str>,
+
+            #[ruma_api(header_map)]
+            pub extra_headers: HeaderMap,
+        }
+
+        response: {
+            #[ruma_api(header = ETAG)]
+            pub etag: String,
+
+            #[ruma_api(header = RETRY_AFTER)]
+            pub retry_after: Option<RetryAfter>,
+
+            #[ruma_api(header_map)]
+            pub extra_headers: HeaderMap,
+        }
+    }
+}
+
+#[test]
+fn request_typed_headers_roundtrip() {
+    let mut extra_headers = HeaderMap::new();
+    extra_headers.insert("x-custom", HeaderValue::from_static("custom"));
+    extra_headers.insert("if-none-match", HeaderValue::from_static("replaced"));
+
+    let req = my_endpoint::Request {
+        content_length: Some(uint!(42)),
+        if_none_match: Some("\"abc\""),
+        extra_headers,
+    };
+    let http_req = req
+        .try_into_http_request::<Vec<u8>>(
+            "https://homeserver.tld",
+            SendAccessToken::None,
+            &[MatrixVersion::V1_1],
+        )
+        .unwrap();
+
+    assert_eq!(http_req.headers().get("content-length").unwrap(), "42");
+    assert_eq!(http_req.headers().get("if-none-match").unwrap(), "\"abc\"");
+    assert_eq!(http_req.headers().get("x-custom").unwrap(), "custom");
+
+    let req =
+        my_endpoint::IncomingRequest::try_from_http_request(http_req, &[] as &[String]).unwrap();
+    assert_eq!(req.content_length, Some(uint!(42)));
+    assert_eq!(req.if_none_match.as_deref(), Some("\"abc\""));
+    assert_eq!(req.extra_headers.len(), 1);
+    assert_eq!(req.extra_headers.get("x-custom").unwrap(), "custom");
+}
+
+#[test]
+fn request_invalid_typed_header() {
+    let http_req = http::Request::builder()
+        .method("GET")
+        .uri("https://homeserver.tld/_matrix/my/endpoint")
+        .header("content-length", "not a number")
+        .body(Vec::<u8>::new())
+        .unwrap();
+
+    my_endpoint::IncomingRequest::try_from_http_request(http_req, &[] as &[String]).unwrap_err();
+}
+
+#[test]
+fn response_typed_headers_roundtrip() {
+    let mut extra_headers = HeaderMap::new();
+    extra_headers.insert("cross-origin-resource-policy", HeaderValue::from_static("cross-origin"));
+
+    let res = my_endpoint::Response {
+        etag: "\"abc\"".to_owned(),
+        retry_after: Some(RetryAfter(Duration::from_secs(5))),
+        extra_headers,
+    };
+    let http_res = res.try_into_http_response::<Vec<u8>>().unwrap();
+
+    assert_eq!(http_res.headers().get(ETAG).unwrap(), "\"abc\"");
+    assert_eq!(http_res.headers().get(RETRY_AFTER).unwrap(), "5");
+    assert_eq!(http_res.headers().get("cross-origin-resource-policy").unwrap(), "cross-origin");
+
+    let res = my_endpoint::Response::try_from_http_response(http_res).unwrap();
+    assert_eq!(res.etag, "\"abc\"");
+    assert_eq!(res.retry_after, Some(RetryAfter(Duration::from_secs(5))));
+    assert_eq!(res.extra_headers.get(CONTENT_TYPE).unwrap(), "application/json");
+    assert_eq!(res.extra_headers.get("cross-origin-resource-policy").unwrap(), "cross-origin");
+    assert!(res.extra_headers.get(ETAG).is_none());
+}
+
+#[test]
+fn response_missing_required_header() {
+    let http_res = http::Response::new(b"{}".to_vec());
+
+    my_endpoint::Response::try_from_http_response(http_res).unwrap_err();
+}
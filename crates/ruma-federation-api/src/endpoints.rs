@@ -0,0 +1,51 @@
+//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+    endpoints.push(crate::authorization::get_event_authorization::v1::METADATA);
+    endpoints.push(crate::backfill::get_backfill::v1::METADATA);
+    endpoints.push(crate::device::get_devices::v1::METADATA);
+    endpoints.push(crate::directory::get_public_rooms::v1::METADATA);
+    endpoints.push(crate::directory::get_public_rooms_filtered::v1::METADATA);
+    endpoints.push(crate::discovery::discover_homeserver::METADATA);
+    endpoints.push(crate::discovery::get_remote_server_keys::v2::METADATA);
+    endpoints.push(crate::discovery::get_remote_server_keys_batch::v2::METADATA);
+    endpoints.push(crate::discovery::get_server_keys::v2::METADATA);
+    endpoints.push(crate::discovery::get_server_version::v1::METADATA);
+    #[cfg(feature = "unstable-msc3723")]
+    endpoints.push(crate::discovery::get_server_versions::msc3723::METADATA);
+    endpoints.push(crate::event::get_event::v1::METADATA);
+    endpoints.push(crate::event::get_missing_events::v1::METADATA);
+    endpoints.push(crate::event::get_room_state::v1::METADATA);
+    endpoints.push(crate::event::get_room_state_ids::v1::METADATA);
+    endpoints.push(crate::keys::claim_keys::v1::METADATA);
+    endpoints.push(crate::keys::get_keys::v1::METADATA);
+    endpoints.push(crate::knock::create_knock_event_template::v1::METADATA);
+    endpoints.push(crate::knock::send_knock::v1::METADATA);
+    endpoints.push(crate::membership::create_invite::v1::METADATA);
+    endpoints.push(crate::membership::create_invite::v2::METADATA);
+    endpoints.push(crate::membership::create_join_event::v1::METADATA);
+    endpoints.push(crate::membership::create_join_event::v2::METADATA);
+    endpoints.push(crate::membership::create_leave_event::v1::METADATA);
+    endpoints.push(crate::membership::create_leave_event::v2::METADATA);
+    endpoints.push(crate::membership::prepare_join_event::v1::METADATA);
+    endpoints.push(crate::membership::prepare_leave_event::v1::METADATA);
+    endpoints.push(crate::openid::get_openid_userinfo::v1::METADATA);
+    endpoints.push(crate::query::get_custom_information::v1::METADATA);
+    endpoints.push(crate::query::get_profile_information::v1::METADATA);
+    endpoints.push(crate::query::get_room_information::v1::METADATA);
+    endpoints.push(crate::space::get_hierarchy::v1::METADATA);
+    endpoints.push(crate::thirdparty::bind_callback::v1::METADATA);
+    endpoints.push(crate::thirdparty::exchange_invite::v1::METADATA);
+    endpoints.push(crate::transactions::send_transaction_message::v1::METADATA);
+    endpoints
+}
@@ -17,6 +17,7 @@ pub mod backfill;
 pub mod device;
 pub mod directory;
 pub mod discovery;
+pub mod endpoints;
 pub mod event;
 pub mod keys;
 pub mod knock;
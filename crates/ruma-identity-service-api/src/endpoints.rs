@@ -0,0 +1,38 @@
+//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+    endpoints.push(crate::association::bind_3pid::v2::METADATA);
+    endpoints.push(crate::association::check_3pid_validity::v2::METADATA);
+    endpoints.push(crate::association::email::create_email_validation_session::v2::METADATA);
+    endpoints.push(crate::association::email::validate_email::v2::METADATA);
+    endpoints.push(crate::association::email::validate_email_by_end_user::v2::METADATA);
+    endpoints.push(crate::association::msisdn::create_msisdn_validation_session::v2::METADATA);
+    endpoints.push(crate::association::msisdn::validate_msisdn::v2::METADATA);
+    endpoints.push(crate::association::msisdn::validate_msisdn_by_phone_number::v2::METADATA);
+    endpoints.push(crate::association::unbind_3pid::v2::METADATA);
+    endpoints.push(crate::authentication::get_account_information::v2::METADATA);
+    endpoints.push(crate::authentication::logout::v2::METADATA);
+    endpoints.push(crate::authentication::register::v2::METADATA);
+    endpoints.push(crate::discovery::get_server_status::v2::METADATA);
+    endpoints.push(crate::discovery::get_supported_versions::METADATA);
+    endpoints.push(crate::invitation::sign_invitation_ed25519::v2::METADATA);
+    endpoints.push(crate::invitation::store_invitation::v2::METADATA);
+    endpoints.push(crate::keys::check_public_key_validity::v2::METADATA);
+    endpoints.push(crate::keys::get_public_key::v2::METADATA);
+    endpoints.push(crate::keys::validate_ephemeral_key::v2::METADATA);
+    endpoints.push(crate::lookup::get_hash_parameters::v2::METADATA);
+    endpoints.push(crate::lookup::lookup_3pid::v2::METADATA);
+    endpoints.push(crate::tos::accept_terms_of_service::v2::METADATA);
+    endpoints.push(crate::tos::get_terms_of_service::v2::METADATA);
+    endpoints
+}
@@ -12,6 +12,7 @@ use std::fmt;
 pub mod association;
 pub mod authentication;
 pub mod discovery;
+pub mod endpoints;
 pub mod invitation;
 pub mod keys;
 pub mod lookup;
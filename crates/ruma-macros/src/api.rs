@@ -16,6 +16,7 @@ mod api_request;
 mod api_response;
 mod attribute;
 mod auth_scheme;
+mod openapi;
 pub mod request;
 pub mod response;
 mod util;
@@ -67,6 +68,13 @@ impl Api {
             |err_ty| quote! { #err_ty },
         );
 
+        let openapi_schema = openapi::expand_schema(
+            metadata,
+            self.request.as_ref(),
+            self.response.as_ref(),
+            &ruma_common,
+        );
+
         let request = self.request.map(|req| req.expand(metadata, &error_ty, &ruma_common));
         let response = self.response.map(|res| res.expand(metadata, &error_ty, &ruma_common));
 
@@ -88,6 +96,8 @@ impl Api {
                 authentication: #ruma_common::api::AuthScheme::#authentication,
             };
 
+            #openapi_schema
+
             #request
             #response
 
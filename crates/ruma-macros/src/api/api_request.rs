@@ -12,7 +12,7 @@ use syn::{
 use super::{
     api_metadata::Metadata,
     kw,
-    util::{all_cfgs, extract_cfg},
+    util::{all_cfgs, extract_cfg, is_raw_body_type},
 };
 
 /// The result of processing the `request` section of the macro.
@@ -93,10 +93,14 @@ impl Request {
         let lifetimes = lifetimes.iter().map(|(lt, attr)| quote! { #attr #lt });
         let fields = &self.fields;
 
+        // A `RawBody` can be a stream, which can't be cloned.
+        let clone = (!self.fields.iter().any(|field| is_raw_body_type(&field.ty)))
+            .then(|| quote! { Clone, });
+
         quote! {
             #[doc = #docs]
             #[derive(
-                Clone,
+                #clone
                 Debug,
                 #ruma_macros::Request,
                 #ruma_common::serde::Incoming,
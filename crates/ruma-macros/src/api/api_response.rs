@@ -4,7 +4,7 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{punctuated::Punctuated, spanned::Spanned, Attribute, Field, Ident, Token};
 
-use super::{api_metadata::Metadata, kw};
+use super::{api_metadata::Metadata, kw, util::is_raw_body_type};
 
 /// The result of processing the `response` section of the macro.
 pub(crate) struct Response {
@@ -33,10 +33,14 @@ impl Response {
 
         let response_ident = Ident::new("Response", self.response_kw.span());
         let fields = &self.fields;
+
+        // A `RawBody` can be a stream, which can't be cloned.
+        let clone = (!self.fields.iter().any(|field| is_raw_body_type(&field.ty)))
+            .then(|| quote! { Clone, });
         quote! {
             #[doc = #docs]
             #[derive(
-                Clone,
+                #clone
                 Debug,
                 #ruma_macros::Response,
                 #ruma_common::serde::Incoming,
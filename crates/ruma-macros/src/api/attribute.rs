@@ -12,6 +12,7 @@ mod kw {
     syn::custom_keyword!(query);
     syn::custom_keyword!(query_map);
     syn::custom_keyword!(header);
+    syn::custom_keyword!(header_map);
     syn::custom_keyword!(authentication);
     syn::custom_keyword!(method);
     syn::custom_keyword!(error_ty);
@@ -28,6 +29,7 @@ pub enum RequestMeta {
     Query,
     QueryMap,
     Header(Ident),
+    HeaderMap,
 }
 
 impl Parse for RequestMeta {
@@ -52,6 +54,9 @@ impl Parse for RequestMeta {
             let _: kw::header = input.parse()?;
             let _: Token![=] = input.parse()?;
             input.parse().map(Self::Header)
+        } else if lookahead.peek(kw::header_map) {
+            let _: kw::header_map = input.parse()?;
+            Ok(Self::HeaderMap)
         } else {
             Err(lookahead.error())
         }
@@ -104,6 +109,7 @@ pub enum ResponseMeta {
     NewtypeBody,
     RawBody,
     Header(Ident),
+    HeaderMap,
 }
 
 impl Parse for ResponseMeta {
@@ -119,6 +125,9 @@ impl Parse for ResponseMeta {
             let _: kw::header = input.parse()?;
             let _: Token![=] = input.parse()?;
             input.parse().map(Self::Header)
+        } else if lookahead.peek(kw::header_map) {
+            let _: kw::header_map = input.parse()?;
+            Ok(Self::HeaderMap)
         } else {
             Err(lookahead.error())
         }
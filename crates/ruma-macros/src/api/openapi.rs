@@ -0,0 +1,270 @@
+//! Details of the schema of an endpoint, used to generate OpenAPI documents.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::Parse, Attribute, Field, GenericArgument, Lit, Meta, NestedMeta, PathArguments, Type,
+};
+
+use super::{
+    api_metadata::Metadata,
+    api_request::Request,
+    api_response::Response,
+    attribute::{RequestMeta, ResponseMeta},
+    util::all_cfgs,
+};
+
+pub(super) fn expand_schema(
+    metadata: &Metadata,
+    request: Option<&Request>,
+    response: Option<&Response>,
+    ruma_common: &TokenStream,
+) -> TokenStream {
+    let openapi = quote! { #ruma_common::api::openapi };
+    let http = quote! { #ruma_common::exports::http };
+
+    let request_fields = request.into_iter().flat_map(|req| &req.fields).map(|field| {
+        let location = match ruma_api_meta(field) {
+            None if has_serde_flatten(&field.attrs) => quote! { FlattenedBody },
+            None => quote! { Body },
+            Some(RequestMeta::NewtypeBody) => quote! { NewtypeBody },
+            Some(RequestMeta::RawBody) => quote! { RawBody },
+            Some(RequestMeta::Path) => quote! { Path },
+            Some(RequestMeta::Query) => quote! { Query },
+            Some(RequestMeta::QueryMap) => quote! { QueryMap },
+            Some(RequestMeta::Header(header)) => quote! { Header(#http::header::#header) },
+            Some(RequestMeta::HeaderMap) => quote! { HeaderMap },
+        };
+
+        expand_field_schema(field, location, &quote! { request }, &openapi)
+    });
+
+    let response_fields = response.into_iter().flat_map(|res| &res.fields).map(|field| {
+        let location = match ruma_api_meta(field) {
+            None if has_serde_flatten(&field.attrs) => quote! { FlattenedBody },
+            None => quote! { Body },
+            Some(ResponseMeta::NewtypeBody) => quote! { NewtypeBody },
+            Some(ResponseMeta::RawBody) => quote! { RawBody },
+            Some(ResponseMeta::Header(header)) => quote! { Header(#http::header::#header) },
+            Some(ResponseMeta::HeaderMap) => quote! { HeaderMap },
+        };
+
+        expand_field_schema(field, location, &quote! { response }, &openapi)
+    });
+
+    let docs = format!(
+        "Schema of the `{}` API endpoint, used to generate OpenAPI documents.",
+        metadata.name.value()
+    );
+
+    quote! {
+        #ruma_common::api::__openapi_item! {
+            #[doc = #docs]
+            pub fn openapi_schema() -> #openapi::EndpointSchema {
+                #[allow(unused_mut)]
+                let mut request = ::std::vec::Vec::new();
+                #( #request_fields )*
+
+                #[allow(unused_mut)]
+                let mut response = ::std::vec::Vec::new();
+                #( #response_fields )*
+
+                #openapi::EndpointSchema { metadata: METADATA, request, response }
+            }
+        }
+    }
+}
+
+fn expand_field_schema(
+    field: &Field,
+    location: TokenStream,
+    fields_var: &TokenStream,
+    openapi: &TokenStream,
+) -> TokenStream {
+    let cfg_attr = all_cfgs(&field.attrs);
+
+    let name = serde_rename(&field.attrs)
+        .unwrap_or_else(|| field.ident.as_ref().map(ToString::to_string).unwrap_or_default());
+    let (ty, is_option) = match option_inner_type(&field.ty) {
+        Some(inner) => (inner, true),
+        None => (&field.ty, false),
+    };
+    // The fields of a flattened type can't be known from its name, so they are described as
+    // additional properties of the body.
+    let is_flattened = has_serde_flatten(&field.attrs);
+    let ty = if is_flattened {
+        quote! { #openapi::FieldType::Any }
+    } else {
+        expand_field_type(ty, openapi)
+    };
+    let required = !is_option && !is_flattened && !has_serde_default(&field.attrs);
+    let description = doc_string(&field.attrs);
+
+    quote! {
+        #cfg_attr
+        #fields_var.push(#openapi::FieldSchema {
+            name: #name,
+            location: #openapi::FieldLocation::#location,
+            ty: #ty,
+            required: #required,
+            description: #description,
+        });
+    }
+}
+
+/// The kind of the `#[ruma_api(...)]` attribute of the field, if any.
+///
+/// Invalid attributes are ignored, since they are reported by the `Request` and `Response`
+/// derives.
+fn ruma_api_meta<T: Parse>(field: &Field) -> Option<T> {
+    field.attrs.iter().find(|attr| attr.path.is_ident("ruma_api"))?.parse_args().ok()
+}
+
+/// The type of the JSON value of a field of the given type.
+///
+/// This is a best effort based on the name of the type, types that are not recognized are
+/// described as any JSON value.
+fn expand_field_type(ty: &Type, openapi: &TokenStream) -> TokenStream {
+    match ty {
+        Type::Reference(reference) => expand_field_type(&reference.elem, openapi),
+        Type::Group(group) => expand_field_type(&group.elem, openapi),
+        Type::Paren(paren) => expand_field_type(&paren.elem, openapi),
+        Type::Slice(slice) => {
+            let item = expand_field_type(&slice.elem, openapi);
+            quote! { #openapi::FieldType::Array(&#item) }
+        }
+        Type::Array(array) => {
+            let item = expand_field_type(&array.elem, openapi);
+            quote! { #openapi::FieldType::Array(&#item) }
+        }
+        Type::Path(path) => {
+            let segment = path.path.segments.last().expect("type paths have a segment");
+            let ident = segment.ident.to_string();
+            let type_arg = first_type_arg(&segment.arguments);
+
+            match (ident.as_str(), type_arg) {
+                ("Option" | "Box" | "Cow" | "Arc", Some(inner)) => {
+                    expand_field_type(inner, openapi)
+                }
+                ("Vec" | "BTreeSet" | "HashSet", Some(inner)) => {
+                    let item = expand_field_type(inner, openapi);
+                    quote! { #openapi::FieldType::Array(&#item) }
+                }
+                ("BTreeMap" | "HashMap" | "HeaderMap" | "Raw" | "JsonObject" | "Map", _) => {
+                    quote! { #openapi::FieldType::Object }
+                }
+                ("bool", _) => quote! { #openapi::FieldType::Boolean },
+                (
+                    "UInt"
+                    | "Int"
+                    | "u8"
+                    | "u16"
+                    | "u32"
+                    | "u64"
+                    | "usize"
+                    | "i8"
+                    | "i16"
+                    | "i32"
+                    | "i64"
+                    | "isize"
+                    | "Duration"
+                    | "MilliSecondsSinceUnixEpoch"
+                    | "SecondsSinceUnixEpoch"
+                    | "RetryAfter",
+                    _,
+                ) => quote! { #openapi::FieldType::Integer },
+                ("f32" | "f64", _) => quote! { #openapi::FieldType::Number },
+                ("String" | "str" | "Base64" | "HeaderValue", _) => {
+                    quote! { #openapi::FieldType::String }
+                }
+                (ident, _)
+                    if ident.starts_with("Owned")
+                        || ident.ends_with("Id")
+                        || ident.ends_with("Uri")
+                        || ident.ends_with("Name") =>
+                {
+                    quote! { #openapi::FieldType::String }
+                }
+                _ => quote! { #openapi::FieldType::Any },
+            }
+        }
+        _ => quote! { #openapi::FieldType::Any },
+    }
+}
+
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(path) => path,
+        _ => return None,
+    };
+
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    first_type_arg(&segment.arguments)
+}
+
+fn first_type_arg(arguments: &PathArguments) -> Option<&Type> {
+    let arguments = match arguments {
+        PathArguments::AngleBracketed(arguments) => arguments,
+        _ => return None,
+    };
+
+    arguments.args.iter().find_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+/// The nested metas of the `#[serde(...)]` attributes.
+fn serde_metas(attrs: &[Attribute]) -> impl Iterator<Item = Meta> + '_ {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|nested| match nested {
+            NestedMeta::Meta(meta) => Some(meta),
+            NestedMeta::Lit(_) => None,
+        })
+}
+
+fn serde_rename(attrs: &[Attribute]) -> Option<String> {
+    serde_metas(attrs).find_map(|meta| match meta {
+        Meta::NameValue(name_value) if name_value.path.is_ident("rename") => match name_value.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn has_serde_default(attrs: &[Attribute]) -> bool {
+    serde_metas(attrs).any(|meta| meta.path().is_ident("default"))
+}
+
+fn has_serde_flatten(attrs: &[Attribute]) -> bool {
+    serde_metas(attrs).any(|meta| meta.path().is_ident("flatten"))
+}
+
+fn doc_string(attrs: &[Attribute]) -> String {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(name_value)) => match name_value.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(ToOwned::to_owned).unwrap_or(line))
+        .collect();
+
+    lines.join("\n").trim().to_owned()
+}
@@ -12,7 +12,7 @@ use syn::{
 use super::{
     attribute::{DeriveRequestMeta, RequestMeta},
     auth_scheme::AuthScheme,
-    util::collect_lifetime_idents,
+    util::{collect_lifetime_idents, is_raw_body_type},
 };
 use crate::util::import_ruma_common;
 
@@ -34,6 +34,7 @@ pub fn expand_derive_request(input: DeriveInput) -> syn::Result<TokenStream> {
 
             match &f {
                 RequestField::Header(..) => collect_lifetime_idents(&mut lifetimes.header, ty),
+                RequestField::HeaderMap(_) => collect_lifetime_idents(&mut lifetimes.header, ty),
                 RequestField::Body(_) => collect_lifetime_idents(&mut lifetimes.body, ty),
                 RequestField::NewtypeBody(_) => collect_lifetime_idents(&mut lifetimes.body, ty),
                 RequestField::RawBody(_) => collect_lifetime_idents(&mut lifetimes.body, ty),
@@ -127,7 +128,9 @@ impl Request {
     }
 
     fn has_header_fields(&self) -> bool {
-        self.fields.iter().any(|f| matches!(f, RequestField::Header(..)))
+        self.fields
+            .iter()
+            .any(|f| matches!(f, RequestField::Header(..) | RequestField::HeaderMap(_)))
     }
 
     fn has_path_fields(&self) -> bool {
@@ -176,10 +179,19 @@ impl Request {
         self.fields.iter().find_map(RequestField::as_raw_body_field)
     }
 
+    /// The raw body field, if its type is `RawBody`, which can be a stream.
+    fn streaming_raw_body_field(&self) -> Option<&Field> {
+        self.raw_body_field().filter(|field| is_raw_body_type(&field.ty))
+    }
+
     fn query_map_field(&self) -> Option<&Field> {
         self.fields.iter().find_map(RequestField::as_query_map_field)
     }
 
+    fn header_map_field(&self) -> Option<&Field> {
+        self.fields.iter().find_map(RequestField::as_header_map_field)
+    }
+
     fn expand_all(&self) -> TokenStream {
         let ruma_common = import_ruma_common();
         let ruma_macros = quote! { #ruma_common::exports::ruma_macros };
@@ -285,6 +297,15 @@ impl Request {
             }
         };
 
+        let header_map_fields =
+            self.fields.iter().filter(|f| matches!(f, RequestField::HeaderMap(_)));
+        if header_map_fields.count() > 1 {
+            return Err(syn::Error::new_spanned(
+                &self.ident,
+                "Can't have more than one header_map field",
+            ));
+        }
+
         let has_body_fields = self.fields.iter().any(|f| matches!(f, RequestField::Body(_)));
         let has_query_fields = self.fields.iter().any(|f| matches!(f, RequestField::Query(_)));
 
@@ -371,6 +392,9 @@ enum RequestField {
     /// Data in an HTTP header.
     Header(Field, Ident),
 
+    /// The HTTP headers that are not in another field.
+    HeaderMap(Field),
+
     /// A specific data type in the body of the request.
     NewtypeBody(Field),
 
@@ -398,6 +422,7 @@ impl RequestField {
                 RequestMeta::Query => RequestField::Query(field),
                 RequestMeta::QueryMap => RequestField::QueryMap(field),
                 RequestMeta::Header(header) => RequestField::Header(field, header),
+                RequestMeta::HeaderMap => RequestField::HeaderMap(field),
             }
         } else {
             RequestField::Body(field)
@@ -444,11 +469,20 @@ impl RequestField {
         }
     }
 
+    /// Return the contained field if this request field is a header map kind.
+    pub fn as_header_map_field(&self) -> Option<&Field> {
+        match self {
+            RequestField::HeaderMap(field) => Some(field),
+            _ => None,
+        }
+    }
+
     /// Gets the inner `Field` value.
     pub fn field(&self) -> &Field {
         match self {
             RequestField::Body(field)
             | RequestField::Header(field, _)
+            | RequestField::HeaderMap(field)
             | RequestField::NewtypeBody(field)
             | RequestField::RawBody(field)
             | RequestField::Path(field)
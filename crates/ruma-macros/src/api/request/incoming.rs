@@ -84,7 +84,10 @@ impl Request {
         };
 
         let (parse_headers, header_vars) = if self.has_header_fields() {
-            let (decls, names): (TokenStream, Vec<_>) = self
+            let from_header_value =
+                quote! { <_ as #ruma_common::api::header::FromHeaderValue>::from_header_value };
+
+            let (mut decls, mut names): (TokenStream, Vec<_>) = self
                 .header_fields()
                 .map(|request_field| {
                     let (field, header_name) = match request_field {
@@ -102,10 +105,10 @@ impl Request {
                         syn::Type::Path(syn::TypePath {
                             path: syn::Path { segments, .. }, ..
                         }) if segments.last().unwrap().ident == "Option" => {
-                            (quote! { Some(str_value.to_owned()) }, quote! { None })
+                            (quote! { Some(#from_header_value(header_value)?) }, quote! { None })
                         }
                         _ => (
-                            quote! { str_value.to_owned() },
+                            quote! { #from_header_value(header_value)? },
                             quote! {
                                 return Err(
                                     #ruma_common::api::error::HeaderDeserializationError::MissingHeader(
@@ -119,10 +122,7 @@ impl Request {
                     let decl = quote! {
                         #( #cfg_attrs )*
                         let #field_name = match headers.get(#http::header::#header_name) {
-                            Some(header_value) => {
-                                let str_value = header_value.to_str()?;
-                                #some_case
-                            }
+                            Some(header_value) => #some_case,
                             None => #none_case,
                         };
                     };
@@ -137,6 +137,30 @@ impl Request {
                 })
                 .unzip();
 
+            if let Some(field) = self.header_map_field() {
+                let cfg_attrs =
+                    field.attrs.iter().filter(|a| a.path.is_ident("cfg")).collect::<Vec<_>>();
+                let field_name = &field.ident;
+                let header_names = self.header_fields().map(|request_field| match request_field {
+                    RequestField::Header(_, header_name) => header_name,
+                    _ => panic!("expected request field to be header variant"),
+                });
+
+                // The header map contains the headers that are not in another field.
+                decls.extend(quote! {
+                    #( #cfg_attrs )*
+                    let #field_name = {
+                        let mut headers = headers.clone();
+                        #( headers.remove(#http::header::#header_names); )*
+                        headers
+                    };
+                });
+                names.push(quote! {
+                    #( #cfg_attrs )*
+                    #field_name
+                });
+            }
+
             let parse = quote! {
                 let headers = request.headers();
 
@@ -172,7 +196,16 @@ impl Request {
             }
         });
 
-        let (parse_body, body_vars) = if let Some(field) = self.raw_body_field() {
+        let (parse_body, body_vars) = if let Some(field) = self.streaming_raw_body_field() {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+            let parse = quote! {
+                let #field_name = #ruma_common::api::RawBody::from(
+                    ::std::convert::AsRef::<[u8]>::as_ref(request.body()).to_vec(),
+                );
+            };
+
+            (parse, quote! { #field_name, })
+        } else if let Some(field) = self.raw_body_field() {
             let field_name = field.ident.as_ref().expect("expected field to have an identifier");
             let parse = quote! {
                 let #field_name =
@@ -184,6 +217,42 @@ impl Request {
             vars(self.body_fields(), quote! { request_body })
         };
 
+        // A stream can't be given to `try_from_http_request`, so the request is converted with an
+        // empty body and the raw body is set afterwards.
+        let streaming_impl = self.streaming_raw_body_field().map(|field| {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+
+            quote! {
+                const STREAMING_BODY: ::std::primitive::bool = true;
+
+                fn try_from_streaming_http_request<S>(
+                    request: #http::Request<#ruma_common::api::RawBody>,
+                    path_args: &[S],
+                ) -> ::std::result::Result<Self, #ruma_common::api::error::FromHttpRequestError>
+                where
+                    S: ::std::convert::AsRef<::std::primitive::str>,
+                {
+                    let (parts, body) = request.into_parts();
+
+                    match body.into_bytes() {
+                        Ok(bytes) => Self::try_from_http_request(
+                            #http::Request::from_parts(parts, bytes),
+                            path_args,
+                        ),
+                        Err(body) => {
+                            let mut request = Self::try_from_http_request(
+                                #http::Request::from_parts(parts, &[] as &[::std::primitive::u8]),
+                                path_args,
+                            )?;
+                            request.#field_name = body;
+
+                            Ok(request)
+                        }
+                    }
+                }
+            }
+        });
+
         let non_auth_impl = matches!(self.authentication, AuthScheme::None(_)).then(|| {
             quote! {
                 #[automatically_derived]
@@ -230,6 +299,8 @@ impl Request {
                         #body_vars
                     })
                 }
+
+                #streaming_impl
             }
 
             #non_auth_impl
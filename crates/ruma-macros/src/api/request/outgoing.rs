@@ -100,6 +100,21 @@ impl Request {
             TokenStream::new()
         };
 
+        // The headers of the header map are added first, so the ones of the other fields replace
+        // them.
+        if let Some(field) = self.header_map_field() {
+            let field_name = &field.ident;
+            header_kvs = quote! {
+                for (name, value) in &self.#field_name {
+                    req_headers.append(name, value.clone());
+                }
+
+                #header_kvs
+            };
+        }
+
+        let to_header_value = quote! { #ruma_common::api::header::ToHeaderValue::to_header_value };
+
         header_kvs.extend(self.header_fields().map(|request_field| {
             let (field, header_name) = match request_field {
                 RequestField::Header(field, header_name) => (field, header_name),
@@ -116,7 +131,7 @@ impl Request {
                         if let Some(header_val) = self.#field_name.as_ref() {
                             req_headers.insert(
                                 #http::header::#header_name,
-                                #http::header::HeaderValue::from_str(header_val)?,
+                                #to_header_value(header_val)?,
                             );
                         }
                     }
@@ -124,7 +139,7 @@ impl Request {
                 _ => quote! {
                     req_headers.insert(
                         #http::header::#header_name,
-                        #http::header::HeaderValue::from_str(self.#field_name.as_ref())?,
+                        #to_header_value(&self.#field_name)?,
                     );
                 },
             }
@@ -155,7 +170,16 @@ impl Request {
             AuthScheme::QueryOnlyAccessToken(_) | AuthScheme::ServerSignatures(_) => quote! {},
         });
 
-        let request_body = if let Some(field) = self.raw_body_field() {
+        let request_body = if let Some(field) = self.streaming_raw_body_field() {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+            quote! {
+                #ruma_common::serde::slice_to_buf(
+                    &self.#field_name.into_bytes().map_err(|_| {
+                        #ruma_common::api::error::IntoHttpError::StreamingBody
+                    })?,
+                )
+            }
+        } else if let Some(field) = self.raw_body_field() {
             let field_name = field.ident.as_ref().expect("expected field to have an identifier");
             quote! { #ruma_common::serde::slice_to_buf(&self.#field_name) }
         } else if self.has_body_fields() {
@@ -170,6 +194,42 @@ impl Request {
             quote! { #ruma_common::serde::slice_to_buf(b"{}") }
         };
 
+        // The raw body is taken out of the request before it is converted, so the other fields
+        // are converted like with a buffered body.
+        let streaming_impl = self.streaming_raw_body_field().map(|field| {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+
+            quote! {
+                fn try_into_streaming_http_request(
+                    mut self,
+                    base_url: &::std::primitive::str,
+                    access_token: #ruma_common::api::SendAccessToken<'_>,
+                    considering_versions: &'_ [#ruma_common::api::MatrixVersion],
+                ) -> ::std::result::Result<
+                    #http::Request<#ruma_common::api::RawBody>,
+                    #ruma_common::api::error::IntoHttpError,
+                > {
+                    let body = ::std::mem::take(&mut self.#field_name);
+                    let mut http_request = self
+                        .try_into_http_request::<#bytes::BytesMut>(
+                            base_url,
+                            access_token,
+                            considering_versions,
+                        )?
+                        .map(|_| body);
+
+                    if let Some(content_length) = http_request.body().content_length() {
+                        http_request.headers_mut().insert(
+                            #http::header::CONTENT_LENGTH,
+                            #http::header::HeaderValue::from(content_length),
+                        );
+                    }
+
+                    Ok(http_request)
+                }
+            }
+        });
+
         let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
 
         let non_auth_impl = matches!(self.authentication, AuthScheme::None(_)).then(|| {
@@ -215,6 +275,8 @@ impl Request {
 
                     Ok(http_request)
                 }
+
+                #streaming_impl
             }
 
             #non_auth_impl
@@ -9,7 +9,10 @@ use syn::{
     DeriveInput, Field, Generics, Ident, Lifetime, Token, Type,
 };
 
-use super::attribute::{DeriveResponseMeta, ResponseMeta};
+use super::{
+    attribute::{DeriveResponseMeta, ResponseMeta},
+    util::is_raw_body_type,
+};
 use crate::util::import_ruma_common;
 
 mod incoming;
@@ -77,9 +80,19 @@ impl Response {
         self.fields.iter().any(|f| matches!(f, ResponseField::RawBody(_)))
     }
 
+    /// The raw body field, if its type is `RawBody`, which can be a stream.
+    fn streaming_raw_body_field(&self) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find_map(ResponseField::as_raw_body_field)
+            .filter(|field| is_raw_body_type(&field.ty))
+    }
+
     /// Whether or not this request has any data in the URL path.
     fn has_header_fields(&self) -> bool {
-        self.fields.iter().any(|f| matches!(f, &ResponseField::Header(..)))
+        self.fields
+            .iter()
+            .any(|f| matches!(f, &ResponseField::Header(..) | &ResponseField::HeaderMap(_)))
     }
 
     fn expand_all(&self) -> TokenStream {
@@ -142,6 +155,15 @@ impl Response {
             }
         };
 
+        let header_map_fields =
+            self.fields.iter().filter(|f| matches!(f, ResponseField::HeaderMap(_)));
+        if header_map_fields.count() > 1 {
+            return Err(syn::Error::new_spanned(
+                &self.ident,
+                "Can't have more than one header_map field",
+            ));
+        }
+
         let has_body_fields = self.fields.iter().any(|f| matches!(f, ResponseField::Body(_)));
         if has_newtype_body_field && has_body_fields {
             return Err(syn::Error::new_spanned(
@@ -162,6 +184,9 @@ enum ResponseField {
     /// Data in an HTTP header.
     Header(Field, Ident),
 
+    /// The HTTP headers that are not in another field.
+    HeaderMap(Field),
+
     /// A specific data type in the body of the response.
     NewtypeBody(Field),
 
@@ -177,6 +202,7 @@ impl ResponseField {
                 ResponseMeta::NewtypeBody => ResponseField::NewtypeBody(field),
                 ResponseMeta::RawBody => ResponseField::RawBody(field),
                 ResponseMeta::Header(header) => ResponseField::Header(field, header),
+                ResponseMeta::HeaderMap => ResponseField::HeaderMap(field),
             }
         } else {
             ResponseField::Body(field)
@@ -188,6 +214,7 @@ impl ResponseField {
         match self {
             ResponseField::Body(field)
             | ResponseField::Header(field, _)
+            | ResponseField::HeaderMap(field)
             | ResponseField::NewtypeBody(field)
             | ResponseField::RawBody(field) => field,
         }
@@ -209,6 +236,14 @@ impl ResponseField {
         }
     }
 
+    /// Return the contained field if this response field is a header map kind.
+    fn as_header_map_field(&self) -> Option<&Field> {
+        match self {
+            ResponseField::HeaderMap(field) => Some(field),
+            _ => None,
+        }
+    }
+
     /// Return the contained field and HTTP header ident if this response field is a header kind.
     fn as_header_field(&self) -> Option<(&Field, &Ident)> {
         match self {
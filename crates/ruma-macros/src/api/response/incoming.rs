@@ -3,6 +3,7 @@ use quote::quote;
 use syn::Type;
 
 use super::{Response, ResponseField};
+use crate::api::util::is_raw_body_type;
 
 impl Response {
     pub fn expand_incoming(&self, error_ty: &Type, ruma_common: &TokenStream) -> TokenStream {
@@ -11,10 +12,14 @@ impl Response {
 
         let extract_response_headers = self.has_header_fields().then(|| {
             quote! {
+                #[allow(unused_mut)]
                 let mut headers = response.headers().clone();
             }
         });
 
+        let from_header_value =
+            quote! { <_ as #ruma_common::api::header::FromHeaderValue>::from_header_value };
+
         let typed_response_body_decl = self.has_body_fields().then(|| {
             quote! {
                 let response_body: ResponseBody = {
@@ -35,6 +40,7 @@ impl Response {
 
         let response_init_fields = {
             let mut fields = vec![];
+            let mut header_map = None;
             let mut raw_body = None;
 
             for response_field in &self.fields {
@@ -61,35 +67,59 @@ impl Response {
                                     #( #cfg_attrs )*
                                     #field_name: {
                                         headers.remove(#http::header::#header_name)
-                                            .map(|h| h.to_str().map(|s| s.to_owned()))
+                                            .map(|h| #from_header_value(&h))
                                             .transpose()?
                                     }
                                 }
                             }
-                            _ => quote! {
-                                #( #cfg_attrs )*
-                                #field_name: {
-                                    headers.remove(#http::header::#header_name)
-                                        .expect("response missing expected header")
-                                        .to_str()?
-                                        .to_owned()
+                            _ => {
+                                let header_name_string = header_name.to_string();
+
+                                quote! {
+                                    #( #cfg_attrs )*
+                                    #field_name: match headers.remove(#http::header::#header_name) {
+                                        Some(h) => #from_header_value(&h)?,
+                                        None => return Err(
+                                            #ruma_common::api::error::DeserializationError::Header(
+                                                #ruma_common::api::error::HeaderDeserializationError::MissingHeader(
+                                                    #header_name_string.into()
+                                                )
+                                            ).into()
+                                        ),
+                                    }
                                 }
-                            },
+                            }
                         };
                         quote! { #optional_header }
                     }
+                    // The header map contains the headers that are not in another field, so it
+                    // must be instantiated after the other header fields.
+                    ResponseField::HeaderMap(_) => {
+                        header_map = Some(quote! {
+                            #( #cfg_attrs )*
+                            #field_name: headers
+                        });
+                        continue;
+                    }
                     // This field must be instantiated last to avoid `use of move value` error.
                     // We are guaranteed only one new body field because of a check in
                     // `parse_response`.
-                    ResponseField::RawBody(_) => {
+                    ResponseField::RawBody(field) => {
+                        let body = quote! {
+                            ::std::convert::AsRef::<[::std::primitive::u8]>::as_ref(
+                                response.body(),
+                            )
+                            .to_vec()
+                        };
+                        let body = if is_raw_body_type(&field.ty) {
+                            quote! { #ruma_common::api::RawBody::from(#body) }
+                        } else {
+                            body
+                        };
+
                         raw_body = Some(quote! {
                             #( #cfg_attrs )*
-                            #field_name: {
-                                ::std::convert::AsRef::<[::std::primitive::u8]>::as_ref(
-                                    response.body(),
-                                )
-                                .to_vec()
-                            }
+                            #field_name: #body
                         });
                         // skip adding to the vec
                         continue;
@@ -97,6 +127,7 @@ impl Response {
                 });
             }
 
+            fields.extend(header_map);
             fields.extend(raw_body);
 
             quote! {
@@ -104,12 +135,52 @@ impl Response {
             }
         };
 
+        // A stream can't be given to `try_from_http_response`, so a successful response is
+        // converted with an empty body and the raw body is set afterwards.
+        let streaming_impl = self.streaming_raw_body_field().map(|field| {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+
+            quote! {
+                const STREAMING_BODY: ::std::primitive::bool = true;
+
+                fn try_from_streaming_http_response(
+                    response: #http::Response<#ruma_common::api::RawBody>,
+                ) -> ::std::result::Result<
+                    Self,
+                    #ruma_common::api::error::FromHttpResponseError<#error_ty>,
+                > {
+                    let (parts, body) = response.into_parts();
+
+                    match body.into_bytes() {
+                        Ok(bytes) => {
+                            Self::try_from_http_response(#http::Response::from_parts(parts, bytes))
+                        }
+                        Err(_) if parts.status.as_u16() >= 400 => {
+                            Err(#ruma_common::api::error::FromHttpResponseError::Deserialization(
+                                #ruma_common::api::error::DeserializationError::StreamingBody,
+                            ))
+                        }
+                        Err(body) => {
+                            let mut response = Self::try_from_http_response(
+                                #http::Response::from_parts(parts, &[] as &[::std::primitive::u8]),
+                            )?;
+                            response.#field_name = body;
+
+                            Ok(response)
+                        }
+                    }
+                }
+            }
+        });
+
         quote! {
             #[automatically_derived]
             #[cfg(feature = "client")]
             impl #ruma_common::api::IncomingResponse for Response {
                 type EndpointError = #error_ty;
 
+                #streaming_impl
+
                 fn try_from_http_response<T: ::std::convert::AsRef<[::std::primitive::u8]>>(
                     response: #http::Response<T>,
                 ) -> ::std::result::Result<
@@ -8,6 +8,19 @@ impl Response {
         let bytes = quote! { #ruma_common::exports::bytes };
         let http = quote! { #ruma_common::exports::http };
 
+        let to_header_value = quote! { #ruma_common::api::header::ToHeaderValue::to_header_value };
+
+        // The headers of the header map are added first, so the ones of the other fields replace
+        // them.
+        let serialize_header_map =
+            self.fields.iter().find_map(ResponseField::as_header_map_field).map(|field| {
+                let field_name =
+                    field.ident.as_ref().expect("expected field to have an identifier");
+                quote! {
+                    headers.extend(self.#field_name);
+                }
+            });
+
         let serialize_response_headers = self.fields.iter().filter_map(|response_field| {
             response_field.as_header_field().map(|(field, header_name)| {
                 let field_name =
@@ -21,7 +34,7 @@ impl Response {
                             if let Some(header) = self.#field_name {
                                 headers.insert(
                                     #http::header::#header_name,
-                                    header.parse()?,
+                                    #to_header_value(&header)?,
                                 );
                             }
                         }
@@ -29,14 +42,23 @@ impl Response {
                     _ => quote! {
                         headers.insert(
                             #http::header::#header_name,
-                            self.#field_name.parse()?,
+                            #to_header_value(&self.#field_name)?,
                         );
                     },
                 }
             })
         });
 
-        let body = if let Some(field) =
+        let body = if let Some(field) = self.streaming_raw_body_field() {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+            quote! {
+                #ruma_common::serde::slice_to_buf(
+                    &self.#field_name.into_bytes().map_err(|_| {
+                        #ruma_common::api::error::IntoHttpError::StreamingBody
+                    })?,
+                )
+            }
+        } else if let Some(field) =
             self.fields.iter().find_map(ResponseField::as_raw_body_field)
         {
             let field_name = field.ident.as_ref().expect("expected field to have an identifier");
@@ -60,6 +82,34 @@ impl Response {
             }
         };
 
+        // The raw body is taken out of the response before it is converted, so the other fields
+        // are converted like with a buffered body.
+        let streaming_impl = self.streaming_raw_body_field().map(|field| {
+            let field_name = field.ident.as_ref().expect("expected field to have an identifier");
+
+            quote! {
+                fn try_into_streaming_http_response(
+                    mut self,
+                ) -> ::std::result::Result<
+                    #http::Response<#ruma_common::api::RawBody>,
+                    #ruma_common::api::error::IntoHttpError,
+                > {
+                    let body = ::std::mem::take(&mut self.#field_name);
+                    let mut http_response =
+                        self.try_into_http_response::<#bytes::BytesMut>()?.map(|_| body);
+
+                    if let Some(content_length) = http_response.body().content_length() {
+                        http_response.headers_mut().insert(
+                            #http::header::CONTENT_LENGTH,
+                            #http::header::HeaderValue::from(content_length),
+                        );
+                    }
+
+                    Ok(http_response)
+                }
+            }
+        });
+
         quote! {
             #[automatically_derived]
             #[cfg(feature = "server")]
@@ -71,11 +121,14 @@ impl Response {
                         .header(#http::header::CONTENT_TYPE, "application/json");
 
                     if let Some(mut headers) = resp_builder.headers_mut() {
+                        #serialize_header_map
                         #(#serialize_response_headers)*
                     }
 
                     ::std::result::Result::Ok(resp_builder.body(#body)?)
                 }
+
+                #streaming_impl
             }
         }
     }
@@ -28,6 +28,16 @@ pub fn collect_lifetime_idents(lifetimes: &mut BTreeSet<Lifetime>, ty: &Type) {
     Visitor(lifetimes).visit_type(ty)
 }
 
+/// Whether the given type is `RawBody`, whose value can be a stream.
+pub fn is_raw_body_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(syn::TypePath { path, .. }) => {
+            path.segments.last().map_or(false, |segment| segment.ident == "RawBody")
+        }
+        _ => false,
+    }
+}
+
 pub fn all_cfgs_expr(cfgs: &[Attribute]) -> Option<TokenStream> {
     let sub_cfgs: Vec<_> = cfgs.iter().filter_map(extract_cfg).collect();
     (!sub_cfgs.is_empty()).then(|| quote! { all( #(#sub_cfgs),* ) })
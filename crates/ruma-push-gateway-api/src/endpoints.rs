@@ -0,0 +1,16 @@
+//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+    endpoints.push(crate::send_event_notification::v1::METADATA);
+    endpoints
+}
@@ -0,0 +1,325 @@
+//! A framework to implement a push gateway.
+//!
+//! [`PushGateway`] handles the requests of homeservers to the `/_matrix/push/v1/notify` endpoint
+//! and forwards each device of the notification to the [`PushProvider`] registered for its
+//! `app_id`. The providers, like Firebase Cloud Messaging, the Apple Push Notification service
+//! or UnifiedPush, are implemented outside of this crate.
+
+use std::{collections::BTreeMap, error::Error as StdError, fmt};
+
+use async_trait::async_trait;
+use http::StatusCode;
+use ruma_common::api::{
+    error::{ErrorKind, MatrixError},
+    IncomingRequest as _, OutgoingResponse as _,
+};
+use tracing::warn;
+
+use crate::send_event_notification::v1::{Device, IncomingNotification, IncomingRequest, Response};
+
+/// A service that delivers notifications to devices.
+#[async_trait]
+pub trait PushProvider: Send + Sync {
+    /// Delivers the given notification to the given device.
+    ///
+    /// The device is one of the devices of the notification, whose `app_id` this provider was
+    /// registered for.
+    async fn send(
+        &self,
+        notification: &IncomingNotification,
+        device: &Device,
+    ) -> Result<(), PushError>;
+}
+
+/// An error when a [`PushProvider`] fails to deliver a notification.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PushError {
+    /// The pushkey of the device is not valid, because it has expired or has never been valid.
+    ///
+    /// The homeserver will stop sending notifications to this pushkey.
+    Rejected,
+
+    /// The notification could not be delivered for another reason.
+    ///
+    /// The homeserver might retry to send the notification later.
+    Other(Box<dyn StdError + Send + Sync>),
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected => write!(f, "The pushkey was rejected."),
+            Self::Other(e) => write!(f, "Failed to deliver notification: {}", e),
+        }
+    }
+}
+
+impl StdError for PushError {}
+
+/// A push gateway dispatching notifications to [`PushProvider`]s according to the `app_id` of the
+/// devices.
+#[derive(Default)]
+pub struct PushGateway {
+    providers: BTreeMap<String, Box<dyn PushProvider>>,
+}
+
+impl PushGateway {
+    /// Creates a new `PushGateway` without any provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the given provider for the given `app_id`.
+    ///
+    /// If a provider was already registered for this `app_id`, it is replaced.
+    pub fn with_provider(
+        mut self,
+        app_id: impl Into<String>,
+        provider: impl PushProvider + 'static,
+    ) -> Self {
+        self.providers.insert(app_id.into(), Box::new(provider));
+        self
+    }
+
+    /// Forwards the given notification to the providers of its devices.
+    ///
+    /// Devices whose `app_id` has no registered provider are rejected, like the ones whose
+    /// provider returned [`PushError::Rejected`].
+    ///
+    /// Returns the response to send to the homeserver. If the notification could not be delivered
+    /// to any of the devices, and none of them was rejected, the first error of the providers is
+    /// returned instead, so the homeserver can try again later. Otherwise, the errors are only
+    /// logged, to avoid sending the notification again to the devices that received it.
+    pub async fn notify(&self, notification: &IncomingNotification) -> Result<Response, PushError> {
+        let mut rejected = Vec::new();
+        let mut error = None;
+        let mut failed = 0;
+
+        for device in &notification.devices {
+            let provider = match self.providers.get(&device.app_id) {
+                Some(provider) => provider,
+                None => {
+                    warn!("No push provider for app ID `{}`", device.app_id);
+                    rejected.push(device.pushkey.clone());
+                    continue;
+                }
+            };
+
+            match provider.send(notification, device).await {
+                Ok(()) => {}
+                Err(PushError::Rejected) => rejected.push(device.pushkey.clone()),
+                Err(e) => {
+                    warn!("Failed to send notification to app ID `{}`: {}", device.app_id, e);
+                    error.get_or_insert(e);
+                    failed += 1;
+                }
+            }
+        }
+
+        match error {
+            Some(e) if failed == notification.devices.len() => Err(e),
+            _ => Ok(Response::new(rejected)),
+        }
+    }
+
+    /// Handles the given `http::Request` sent by a homeserver.
+    ///
+    /// Requests to other endpoints than `/_matrix/push/v1/notify` get a `404 Not Found` response,
+    /// invalid requests get a `400 Bad Request` response and, if the notification could not be
+    /// delivered to any of the devices, a `502 Bad Gateway` response is returned so the
+    /// homeserver tries again later.
+    pub async fn handle<B: AsRef<[u8]>>(
+        &self,
+        request: http::Request<B>,
+    ) -> http::Response<Vec<u8>> {
+        if request.uri().path() != IncomingRequest::METADATA.stable_path.unwrap_or_default() {
+            return error_response(
+                StatusCode::NOT_FOUND,
+                ErrorKind::Unrecognized,
+                "Unrecognized request",
+            );
+        }
+
+        if request.method() != IncomingRequest::METADATA.method {
+            return error_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                ErrorKind::Unrecognized,
+                "Unrecognized request method",
+            );
+        }
+
+        let request = match IncomingRequest::try_from_http_request(request, &[] as &[&str]) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, ErrorKind::BadJson, &e.to_string())
+            }
+        };
+
+        let response = match self.notify(&request.notification).await {
+            Ok(response) => response.try_into_http_response(),
+            Err(e) => MatrixError::new(StatusCode::BAD_GATEWAY, ErrorKind::Unknown, e.to_string())
+                .try_into_http_response(),
+        };
+
+        response.expect("response should serialize")
+    }
+}
+
+impl fmt::Debug for PushGateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushGateway").field("app_ids", &self.providers.keys()).finish()
+    }
+}
+
+fn error_response(
+    status_code: StatusCode,
+    kind: ErrorKind,
+    error: &str,
+) -> http::Response<Vec<u8>> {
+    MatrixError::new(status_code, kind, error.to_owned())
+        .try_into_http_response()
+        .expect("error response should serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use http::StatusCode;
+    use serde_json::{from_slice as from_json_slice, json, Value as JsonValue};
+
+    use super::{PushError, PushGateway, PushProvider};
+    use crate::send_event_notification::v1::{Device, IncomingNotification};
+
+    #[derive(Default)]
+    struct MockProvider {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl PushProvider for &'static MockProvider {
+        async fn send(
+            &self,
+            _notification: &IncomingNotification,
+            device: &Device,
+        ) -> Result<(), PushError> {
+            match device.pushkey.as_str() {
+                "expired" => Err(PushError::Rejected),
+                "unavailable" => Err(PushError::Other("service unavailable".into())),
+                pushkey => {
+                    self.sent.lock().unwrap().push(pushkey.to_owned());
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn notify_request(devices: JsonValue) -> http::Request<Vec<u8>> {
+        http::Request::post("https://push.example.org/_matrix/push/v1/notify")
+            .body(
+                serde_json::to_vec(&json!({
+                    "notification": {
+                        "event_id": "$event",
+                        "room_id": "!room:example.org",
+                        "devices": devices,
+                    }
+                }))
+                .unwrap(),
+            )
+            .unwrap()
+    }
+
+    fn device(app_id: &str, pushkey: &str) -> JsonValue {
+        json!({ "app_id": app_id, "pushkey": pushkey })
+    }
+
+    #[tokio::test]
+    async fn dispatch_to_providers() {
+        let provider: &'static MockProvider = Box::leak(Box::default());
+        let gateway = PushGateway::new().with_provider("org.example.app", provider);
+
+        let response = gateway
+            .handle(notify_request(json!([
+                device("org.example.app", "valid"),
+                device("org.example.app", "expired"),
+                device("org.example.unknown", "unknown"),
+            ])))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            from_json_slice::<JsonValue>(response.body()).unwrap(),
+            json!({ "rejected": ["expired", "unknown"] })
+        );
+        assert_eq!(*provider.sent.lock().unwrap(), ["valid"]);
+
+        let response =
+            gateway.handle(notify_request(json!([device("org.example.app", "unavailable")]))).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn partial_failure() {
+        let provider: &'static MockProvider = Box::leak(Box::default());
+        let gateway = PushGateway::new().with_provider("org.example.app", provider);
+
+        let response = gateway
+            .handle(notify_request(json!([
+                device("org.example.app", "valid"),
+                device("org.example.app", "unavailable"),
+                device("org.example.app", "expired"),
+            ])))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            from_json_slice::<JsonValue>(response.body()).unwrap(),
+            json!({ "rejected": ["expired"] })
+        );
+        assert_eq!(*provider.sent.lock().unwrap(), ["valid"]);
+
+        let response = gateway
+            .handle(notify_request(json!([
+                device("org.example.app", "unavailable"),
+                device("org.example.app", "expired"),
+            ])))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            from_json_slice::<JsonValue>(response.body()).unwrap(),
+            json!({ "rejected": ["expired"] })
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_requests() {
+        let gateway = PushGateway::new();
+
+        let response = gateway
+            .handle(
+                http::Request::get("https://push.example.org/_matrix/push/v1/notify")
+                    .body(b"")
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let response = gateway
+            .handle(
+                http::Request::post("https://push.example.org/_matrix/push/v2/notify")
+                    .body(b"")
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = gateway
+            .handle(
+                http::Request::post("https://push.example.org/_matrix/push/v1/notify")
+                    .body(b"{}")
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -9,6 +9,9 @@
 
 use std::fmt;
 
+pub mod endpoints;
+#[cfg(feature = "gateway")]
+pub mod gateway;
 pub mod send_event_notification;
 
 // Wrapper around `Box<str>` that cannot be used in a meaningful way outside of
@@ -8,13 +8,16 @@ pub mod v1 {
     //!
     //! [spec]: https://spec.matrix.org/v1.2/push-gateway-api/#post_matrixpushv1notify
 
+    use std::slice;
+
     use js_int::{uint, UInt};
     use ruma_common::{
         api::ruma_api,
-        events::RoomEventType,
-        push::{PushFormat, Tweak},
-        serde::{Incoming, StringEnum},
-        EventId, RoomAliasId, RoomId, RoomName, SecondsSinceUnixEpoch, UserId,
+        events::{AnySyncRoomEvent, RoomEventType},
+        push::{Action, PushConditionRoomCtx, PushFormat, Tweak},
+        serde::{Incoming, Raw, StringEnum},
+        EventId, OwnedEventId, OwnedRoomAliasId, OwnedRoomId, OwnedRoomName, OwnedUserId,
+        RoomAliasId, RoomId, RoomName, SecondsSinceUnixEpoch, UserId,
     };
     use serde::{Deserialize, Serialize};
     use serde_json::value::RawValue as RawJsonValue;
@@ -227,7 +230,7 @@ pub mod v1 {
         /// A dictionary of customisations made to the way this notification is to be presented.
         ///
         /// These are added by push rules.
-        #[serde(with = "tweak_serde", skip_serializing_if = "Vec::is_empty")]
+        #[serde(default, with = "tweak_serde", skip_serializing_if = "Vec::is_empty")]
         pub tweaks: Vec<Tweak>,
     }
 
@@ -305,6 +308,160 @@ pub mod v1 {
         }
     }
 
+    /// A helper to build the [`Notification`]s a homeserver sends for an event.
+    ///
+    /// It extracts the fields of the notification from the event, and the priority and the tweaks
+    /// of the devices from the actions of the push rule that matched the event.
+    #[derive(Clone, Debug)]
+    pub struct NotificationBuilder {
+        event_id: OwnedEventId,
+        room_id: OwnedRoomId,
+        event_type: RoomEventType,
+        sender: OwnedUserId,
+        sender_display_name: Option<String>,
+        room_name: Option<OwnedRoomName>,
+        room_alias: Option<OwnedRoomAliasId>,
+        user_is_target: bool,
+        prio: NotificationPriority,
+        content: Option<Box<RawJsonValue>>,
+        counts: NotificationCounts,
+        tweaks: Vec<Tweak>,
+    }
+
+    impl NotificationBuilder {
+        /// Creates a new `NotificationBuilder` for the given event.
+        ///
+        /// `context` is the context that was used to evaluate the push rules and `actions` are
+        /// the actions of the rule that matched, as returned by `Ruleset::get_actions`.
+        ///
+        /// Returns `Ok(None)` if the actions don't generate a notification, and an error if the
+        /// event is not a valid room event.
+        pub fn new(
+            event: &Raw<AnySyncRoomEvent>,
+            context: &PushConditionRoomCtx,
+            actions: &[Action],
+        ) -> serde_json::Result<Option<Self>> {
+            #[derive(Deserialize)]
+            struct EventDeHelper {
+                event_id: OwnedEventId,
+                #[serde(rename = "type")]
+                event_type: RoomEventType,
+                sender: OwnedUserId,
+                state_key: Option<String>,
+                content: Option<Box<RawJsonValue>>,
+            }
+
+            if !actions.iter().any(Action::should_notify) {
+                return Ok(None);
+            }
+
+            let EventDeHelper { event_id, event_type, sender, state_key, content } =
+                event.deserialize_as()?;
+
+            let tweaks: Vec<_> = actions
+                .iter()
+                .filter_map(|action| match action {
+                    Action::SetTweak(tweak) => Some(tweak.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            // Notifications that don't make noise are less urgent, but encrypted events might
+            // need to, we can't know.
+            let prio = if event_type == RoomEventType::RoomEncrypted
+                || actions.iter().any(|action| action.is_highlight() || action.sound().is_some())
+            {
+                NotificationPriority::High
+            } else {
+                NotificationPriority::Low
+            };
+
+            Ok(Some(Self {
+                event_id,
+                room_id: context.room_id.clone(),
+                event_type,
+                sender,
+                sender_display_name: None,
+                room_name: None,
+                room_alias: None,
+                user_is_target: state_key.as_deref() == Some(context.user_id.as_str()),
+                prio,
+                content,
+                counts: NotificationCounts::default(),
+                tweaks,
+            }))
+        }
+
+        /// Sets the current display name of the sender in the room.
+        pub fn sender_display_name(mut self, sender_display_name: String) -> Self {
+            self.sender_display_name = Some(sender_display_name);
+            self
+        }
+
+        /// Sets the name of the room.
+        pub fn room_name(mut self, room_name: OwnedRoomName) -> Self {
+            self.room_name = Some(room_name);
+            self
+        }
+
+        /// Sets the alias to display for the room.
+        pub fn room_alias(mut self, room_alias: OwnedRoomAliasId) -> Self {
+            self.room_alias = Some(room_alias);
+            self
+        }
+
+        /// Sets the current numbers of unacknowledged communications of the user.
+        pub fn counts(mut self, counts: NotificationCounts) -> Self {
+            self.counts = counts;
+            self
+        }
+
+        /// Creates the `Device` for the pusher with the given app ID, pushkey and data, with the
+        /// tweaks of the notification.
+        pub fn device(
+            &self,
+            app_id: String,
+            pushkey: String,
+            data: impl Into<PusherData>,
+        ) -> Device {
+            Device {
+                data: data.into(),
+                tweaks: self.tweaks.clone(),
+                ..Device::new(app_id, pushkey)
+            }
+        }
+
+        /// Builds the notification for the given device.
+        ///
+        /// Since the format of the notification depends on the device, a notification is built
+        /// for each device. If the format of the device is `event_id_only`, the notification only
+        /// contains the event ID, the room ID, the counts and the priority.
+        pub fn build<'a>(&'a self, device: &'a Device) -> Notification<'a> {
+            let event_id_only = device.data.format == Some(PushFormat::EventIdOnly);
+
+            let mut notification = Notification {
+                event_id: Some(&self.event_id),
+                room_id: Some(&self.room_id),
+                prio: self.prio.clone(),
+                counts: self.counts.clone(),
+                devices: slice::from_ref(device),
+                ..Default::default()
+            };
+
+            if !event_id_only {
+                notification.event_type = Some(&self.event_type);
+                notification.sender = Some(&self.sender);
+                notification.sender_display_name = self.sender_display_name.as_deref();
+                notification.room_name = self.room_name.as_deref();
+                notification.room_alias = self.room_alias.as_deref();
+                notification.user_is_target = self.user_is_target;
+                notification.content = self.content.as_deref();
+            }
+
+            notification
+        }
+    }
+
     mod tweak_serde {
         use std::fmt;
 
@@ -384,15 +541,26 @@ pub mod v1 {
 
     #[cfg(test)]
     mod tests {
-        use js_int::uint;
+        use std::collections::BTreeMap;
+
+        use js_int::{int, uint};
         use ruma_common::{
-            event_id, events::RoomEventType, room_alias_id, room_id, user_id, SecondsSinceUnixEpoch,
+            event_id,
+            events::RoomEventType,
+            power_levels::NotificationPowerLevels,
+            push::{PushConditionRoomCtx, PushFormat, Ruleset},
+            room_alias_id, room_id,
+            serde::Raw,
+            user_id, SecondsSinceUnixEpoch,
         };
         use serde_json::{
             from_value as from_json_value, json, to_value as to_json_value, Value as JsonValue,
         };
 
-        use super::{Device, Notification, NotificationCounts, NotificationPriority, Tweak};
+        use super::{
+            Device, Notification, NotificationBuilder, NotificationCounts, NotificationPriority,
+            PusherData, Tweak,
+        };
 
         #[test]
         fn serialize_request() {
@@ -462,5 +630,92 @@ pub mod v1 {
 
             assert_eq!(expected, to_json_value(notice).unwrap())
         }
+
+        #[test]
+        fn build_notification() {
+            let context = PushConditionRoomCtx {
+                room_id: room_id!("!far_west:server.name").to_owned(),
+                member_count: uint!(2),
+                user_id: user_id!("@jj:server.name").to_owned(),
+                user_display_name: "Jolly Jumper".into(),
+                users_power_levels: BTreeMap::new(),
+                default_power_level: int!(50),
+                notification_power_levels: NotificationPowerLevels::new(),
+            };
+            let ruleset = Ruleset::server_default(&context.user_id);
+
+            let event = Raw::new(&json!({
+                "type": "m.room.message",
+                "event_id": "$message",
+                "sender": "@rantanplan:server.name",
+                "origin_server_ts": 1,
+                "content": { "body": "Hi jj!", "msgtype": "m.text" },
+            }))
+            .unwrap()
+            .cast();
+            let actions = ruleset.get_actions(&event, &context);
+
+            let builder = NotificationBuilder::new(&event, &context, actions)
+                .unwrap()
+                .unwrap()
+                .sender_display_name("Rantanplan".into())
+                .counts(NotificationCounts::new(uint!(3), uint!(0)));
+
+            let mut data = PusherData::new();
+            data.format = Some(PushFormat::EventIdOnly);
+            let devices = [
+                builder.device("app".into(), "key".into(), PusherData::new()),
+                builder.device("app".into(), "other_key".into(), data),
+            ];
+            assert_eq!(
+                to_json_value(builder.build(&devices[0])).unwrap(),
+                json!({
+                    "event_id": "$message",
+                    "room_id": "!far_west:server.name",
+                    "type": "m.room.message",
+                    "sender": "@rantanplan:server.name",
+                    "sender_display_name": "Rantanplan",
+                    "content": { "body": "Hi jj!", "msgtype": "m.text" },
+                    "counts": { "unread": 3 },
+                    "devices": [
+                        {
+                            "app_id": "app",
+                            "pushkey": "key",
+                            "tweaks": { "sound": "default", "highlight": true },
+                        },
+                    ],
+                })
+            );
+
+            assert_eq!(
+                to_json_value(builder.build(&devices[1])).unwrap(),
+                json!({
+                    "event_id": "$message",
+                    "room_id": "!far_west:server.name",
+                    "counts": { "unread": 3 },
+                    "devices": [
+                        {
+                            "app_id": "app",
+                            "pushkey": "other_key",
+                            "data": { "format": "event_id_only" },
+                            "tweaks": { "sound": "default", "highlight": true },
+                        },
+                    ],
+                })
+            );
+
+            // Events sent by the user don't generate notifications.
+            let own_event = Raw::new(&json!({
+                "type": "m.room.message",
+                "event_id": "$own",
+                "sender": "@jj:server.name",
+                "origin_server_ts": 1,
+                "content": { "body": "Hi!", "msgtype": "m.text" },
+            }))
+            .unwrap()
+            .cast();
+            let actions = ruleset.get_actions(&own_event, &context);
+            assert!(NotificationBuilder::new(&own_event, &context, actions).unwrap().is_none());
+        }
     }
 }
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, collections::BTreeSet};
+use std::borrow::Borrow;
 
 use js_int::{int, Int};
 use ruma_common::{
@@ -7,7 +7,7 @@ use ruma_common::{
             create::RoomCreateEventContent,
             join_rules::{JoinRule, RoomJoinRulesEventContent},
             member::{MembershipState, ThirdPartyInvite},
-            power_levels::RoomPowerLevelsEventContent,
+            power_levels::{PowerLevelTarget, RoomPowerLevelsEventContent},
             third_party_invite::RoomThirdPartyInviteEventContent,
         },
         RoomEventType, StateEventType,
@@ -739,128 +739,40 @@ fn check_power_levels(
 
     // If users key in content is not a dictionary with keys that are valid user IDs
     // with values that are integers (or a string that is an integer), reject.
-    let user_content =
-        from_json_str::<RoomPowerLevelsEventContent>(power_event.content().get()).unwrap();
-
-    let current_content =
-        from_json_str::<RoomPowerLevelsEventContent>(current_state.content().get()).unwrap();
+    let changes = match RoomPowerLevelsEventContent::diff_raw(
+        &Raw::from_json(current_state.content().to_owned()),
+        &Raw::from_json(power_event.content().to_owned()),
+    ) {
+        Ok(changes) => changes,
+        Err(e) => {
+            warn!("invalid m.room.power_levels content: {}", e);
+            return None;
+        }
+    };
 
     // Validation of users is done in Ruma, synapse for loops validating user_ids and integers here
     info!("validation of power event finished");
 
-    let mut user_levels_to_check = BTreeSet::new();
-    let old_list = &current_content.users;
-    let user_list = &user_content.users;
-    for user in old_list.keys().chain(user_list.keys()) {
-        let user: &UserId = user;
-        user_levels_to_check.insert(user);
-    }
-
-    debug!("users to check {:?}", user_levels_to_check);
-
-    let mut event_levels_to_check = BTreeSet::new();
-    let old_list = &current_content.events;
-    let new_list = &user_content.events;
-    for ev_id in old_list.keys().chain(new_list.keys()) {
-        event_levels_to_check.insert(ev_id);
-    }
-
-    debug!("events to check {:?}", event_levels_to_check);
-
-    let old_state = &current_content;
-    let new_state = &user_content;
-
-    // synapse does not have to split up these checks since we can't combine UserIds and
-    // EventTypes we do 2 loops
-
-    // UserId loop
-    for user in user_levels_to_check {
-        let old_level = old_state.users.get(user);
-        let new_level = new_state.users.get(user);
-        if old_level.is_some() && new_level.is_some() && old_level == new_level {
+    for change in changes {
+        // Notifications, currently there is only @room
+        if !room_version.limit_notifications_power_levels
+            && matches!(change.target, PowerLevelTarget::Notification(_))
+        {
             continue;
         }
 
         // If the current value is equal to the sender's current power level, reject
-        if user != power_event.sender() && old_level == Some(&user_level) {
-            warn!("m.room.power_level cannot remove ops == to own");
-            return Some(false); // cannot remove ops level == to own
-        }
-
-        // If the current value is higher than the sender's current power level, reject
-        // If the new value is higher than the sender's current power level, reject
-        let old_level_too_big = old_level > Some(&user_level);
-        let new_level_too_big = new_level > Some(&user_level);
-        if old_level_too_big || new_level_too_big {
-            warn!("m.room.power_level failed to add ops > than own");
-            return Some(false); // cannot add ops greater than own
-        }
-    }
-
-    // EventType loop
-    for ev_type in event_levels_to_check {
-        let old_level = old_state.events.get(ev_type);
-        let new_level = new_state.events.get(ev_type);
-        if old_level.is_some() && new_level.is_some() && old_level == new_level {
-            continue;
-        }
-
         // If the current value is higher than the sender's current power level, reject
         // If the new value is higher than the sender's current power level, reject
-        let old_level_too_big = old_level > Some(&user_level);
-        let new_level_too_big = new_level > Some(&user_level);
-        if old_level_too_big || new_level_too_big {
-            warn!("m.room.power_level failed to add ops > than own");
-            return Some(false); // cannot add ops greater than own
-        }
-    }
-
-    // Notifications, currently there is only @room
-    if room_version.limit_notifications_power_levels {
-        let old_level = old_state.notifications.room;
-        let new_level = new_state.notifications.room;
-        if old_level != new_level {
-            // If the current value is higher than the sender's current power level, reject
-            // If the new value is higher than the sender's current power level, reject
-            let old_level_too_big = old_level > user_level;
-            let new_level_too_big = new_level > user_level;
-            if old_level_too_big || new_level_too_big {
-                warn!("m.room.power_level failed to add ops > than own");
-                return Some(false); // cannot add ops greater than own
-            }
-        }
-    }
-
-    let levels =
-        ["users_default", "events_default", "state_default", "ban", "redact", "kick", "invite"];
-    let old_state = serde_json::to_value(old_state).unwrap();
-    let new_state = serde_json::to_value(new_state).unwrap();
-    for lvl_name in &levels {
-        if let Some((old_lvl, new_lvl)) = get_deserialize_levels(&old_state, &new_state, lvl_name) {
-            let old_level_too_big = old_lvl > user_level;
-            let new_level_too_big = new_lvl > user_level;
-
-            if old_level_too_big || new_level_too_big {
-                warn!("cannot add ops > than own");
-                return Some(false);
-            }
+        if let Err(e) = change.check(power_event.sender(), user_level) {
+            warn!("m.room.power_level change not allowed: {}", e);
+            return Some(false);
         }
     }
 
     Some(true)
 }
 
-fn get_deserialize_levels(
-    old: &serde_json::Value,
-    new: &serde_json::Value,
-    name: &str,
-) -> Option<(Int, Int)> {
-    Some((
-        serde_json::from_value(old.get(name)?.clone()).ok()?,
-        serde_json::from_value(new.get(name)?.clone()).ok()?,
-    ))
-}
-
 /// Does the event redacting come from a user with enough power to redact the given event.
 fn check_redaction(
     _room_version: &RoomVersion,
@@ -149,6 +149,27 @@ pub mod api {
     )]
     #[doc(inline)]
     pub use ruma_push_gateway_api as push_gateway;
+
+    /// The metadata of all the endpoints of the enabled API crates.
+    ///
+    /// The endpoints of each crate can be listed with their `endpoints::all()` function.
+    pub fn endpoints() -> Vec<Metadata> {
+        #[allow(unused_mut)]
+        let mut endpoints = Vec::new();
+
+        #[cfg(feature = "ruma-appservice-api")]
+        endpoints.extend(appservice::endpoints::all());
+        #[cfg(feature = "ruma-client-api")]
+        endpoints.extend(client::endpoints::all());
+        #[cfg(feature = "ruma-federation-api")]
+        endpoints.extend(federation::endpoints::all());
+        #[cfg(feature = "ruma-identity-service-api")]
+        endpoints.extend(identity_service::endpoints::all());
+        #[cfg(feature = "ruma-push-gateway-api")]
+        endpoints.extend(push_gateway::endpoints::all());
+
+        endpoints
+    }
 }
 
 #[doc(no_inline)]
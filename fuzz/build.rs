@@ -0,0 +1,24 @@
+//! Generates the list of the request types of the Client-Server API from the registry of the
+//! endpoints of ruma-client-api, for the `incoming_request` target.
+
+use std::{env, fs, path::Path};
+
+fn main() {
+    let registry = "../crates/ruma-client-api/src/endpoints.rs";
+    println!("cargo:rerun-if-changed={}", registry);
+
+    // The `cfg` attributes of the registry are ignored, since all the unstable features of
+    // ruma-client-api are enabled.
+    let request_types: Vec<_> = fs::read_to_string(registry)
+        .expect("the registry of ruma-client-api should be readable")
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("endpoints.push(crate::"))
+        .filter_map(|line| line.strip_suffix("::METADATA);"))
+        .map(|module| format!("    endpoint!(ruma_client_api::{}::IncomingRequest),\n", module))
+        .collect();
+
+    let endpoints =
+        format!("const ENDPOINTS: &[fn(&RequestInput)] = &[\n{}];\n", request_types.concat());
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("endpoints.rs"), endpoints).unwrap();
+}
@@ -0,0 +1,23 @@
+//! Parses arbitrary JSON as a `CanonicalJsonValue`.
+//!
+//! The canonical serialization of a value must be parsed back to the same value, and serializing
+//! it again must give the same string.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruma_common::serde::CanonicalJsonValue;
+
+fuzz_target!(|data: &[u8]| {
+    let value: CanonicalJsonValue = match serde_json::from_slice(data) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+
+    let canonical = value.to_string();
+    let reparsed: CanonicalJsonValue =
+        serde_json::from_str(&canonical).expect("canonical JSON should be valid JSON");
+
+    assert_eq!(reparsed, value);
+    assert_eq!(reparsed.to_string(), canonical);
+});
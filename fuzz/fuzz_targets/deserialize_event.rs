@@ -0,0 +1,59 @@
+//! Deserializes arbitrary JSON as `Raw<AnyRoomEvent>` and `Raw<AnySyncRoomEvent>`.
+//!
+//! The content of the events that are deserialized must be serialized to JSON that is
+//! deserialized to the same content.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruma_common::{
+    events::{AnyRoomEvent, AnySyncRoomEvent, EventContent},
+    serde::Raw,
+};
+use serde_json::value::to_raw_value as to_raw_json_value;
+
+/// Checks that the given content is the same after a serialization round-trip.
+fn check_content<C>(content: C)
+where
+    C: EventContent,
+    C::EventType: ToString,
+{
+    let event_type = content.event_type().to_string();
+
+    // Custom contents can't be serialized.
+    let json = match to_raw_json_value(&content) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    let reparsed = C::from_parts(&event_type, &json).expect("serialized content should be valid");
+
+    assert_eq!(to_raw_json_value(&reparsed).unwrap().get(), json.get());
+}
+
+fuzz_target!(|data: &[u8]| {
+    let raw = match serde_json::from_slice::<Raw<AnyRoomEvent>>(data) {
+        Ok(raw) => raw,
+        Err(_) => return,
+    };
+
+    if let Ok(event) = raw.deserialize() {
+        let _ = (event.event_id(), event.sender(), event.room_id(), event.origin_server_ts());
+
+        match event {
+            AnyRoomEvent::MessageLike(event) => {
+                if let Some(content) = event.original_content() {
+                    check_content(content);
+                }
+            }
+            AnyRoomEvent::State(event) => {
+                if let Some(content) = event.original_content() {
+                    check_content(content);
+                }
+            }
+        }
+    }
+
+    if let Ok(event) = raw.cast::<AnySyncRoomEvent>().deserialize() {
+        let _ = (event.event_id(), event.sender(), event.origin_server_ts());
+    }
+});
@@ -0,0 +1,107 @@
+//! Converts arbitrary HTTP requests to the `IncomingRequest` types of all the endpoints of the
+//! Client-Server API.
+//!
+//! The input chooses the endpoint, so the fuzzer can explore each of them separately.
+//!
+//! The requests that are parsed are also serialized and parsed back, to check that the same value
+//! is obtained. This is only done for the request types without lifetimes, since the other ones are
+//! not the type of the outgoing requests of their endpoint, and can't be serialized.
+
+#![no_main]
+
+use std::{fmt::Debug, marker::PhantomData};
+
+use arbitrary::Arbitrary;
+use http::header::{HeaderName, HeaderValue};
+use libfuzzer_sys::fuzz_target;
+use ruma_common::api::{IncomingRequest, MatrixVersion, OutgoingRequest, Router, SendAccessToken};
+
+#[derive(Arbitrary, Debug)]
+struct RequestInput {
+    endpoint: u16,
+    path_args: Vec<String>,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// The function checking the requests of the endpoint with the given request type.
+macro_rules! endpoint {
+    ($request:ty) => {
+        |input: &RequestInput| {
+            if let Some(request) = parse_request::<$request>(input) {
+                // Selects `CheckRoundTrip` if the request type implements `OutgoingRequest`,
+                // `SkipRoundTrip` otherwise.
+                (&RoundTrip::<$request>(PhantomData)).check(request);
+            }
+        }
+    };
+}
+
+// The `ENDPOINTS` generated by the build script.
+include!(concat!(env!("OUT_DIR"), "/endpoints.rs"));
+
+fn parse_request<R: IncomingRequest + Debug>(input: &RequestInput) -> Option<R> {
+    let mut request = http::Request::builder().method(R::METADATA.method);
+
+    if let Ok(uri) = format!("/?{}", input.query).parse::<http::Uri>() {
+        request = request.uri(uri);
+    }
+
+    for (name, value) in &input.headers {
+        if let (Ok(name), Ok(value)) =
+            (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value))
+        {
+            request = request.header(name, value);
+        }
+    }
+
+    let request = request.body(input.body.as_slice()).ok()?;
+    let request = R::try_from_http_request(request, &input.path_args).ok()?;
+    let _ = format!("{:?}", request);
+
+    Some(request)
+}
+
+struct RoundTrip<R>(PhantomData<R>);
+
+trait CheckRoundTrip<R> {
+    fn check(&self, request: R);
+}
+
+impl<R> CheckRoundTrip<R> for RoundTrip<R>
+where
+    R: IncomingRequest + OutgoingRequest + Debug + 'static,
+{
+    fn check(&self, request: R) {
+        let debug = format!("{:?}", request);
+
+        // Use the path of the first version supporting the endpoint, or its unstable path.
+        let version = <R as OutgoingRequest>::METADATA.added.unwrap_or(MatrixVersion::V1_0);
+        let http_request = request
+            .try_into_http_request::<Vec<u8>>(
+                "https://fuzz.example",
+                SendAccessToken::IfRequired("access_token"),
+                &[version],
+            )
+            .expect("a parsed request should be serialized");
+
+        // The router extracts the path arguments from the path of the serialized request.
+        let router = Router::new().with_endpoint(|request: R| request);
+        let reparsed = router.route(http_request).expect("a serialized request should be parsed");
+
+        // The request types don't implement `PartialEq`.
+        assert_eq!(format!("{:?}", reparsed), debug);
+    }
+}
+
+trait SkipRoundTrip<R> {
+    fn check(&self, _request: R) {}
+}
+
+impl<R> SkipRoundTrip<R> for &RoundTrip<R> {}
+
+fuzz_target!(|input: RequestInput| {
+    let check = ENDPOINTS[usize::from(input.endpoint) % ENDPOINTS.len()];
+    check(&input);
+});
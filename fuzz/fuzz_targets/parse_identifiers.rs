@@ -0,0 +1,53 @@
+//! Parses arbitrary strings as all the identifier types.
+//!
+//! The identifiers that are valid must keep the string they were parsed from, and the Matrix URIs
+//! must be parsed back to the same value from their string representation.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruma_common::{
+    DeviceKeyId, EventId, MatrixToUri, MatrixUri, MxcUri, RoomAliasId, RoomId, RoomOrAliasId,
+    RoomVersionId, ServerName, ServerSigningKeyId, UserId,
+};
+
+/// Checks that `s` can be parsed as `&T` without modification, if it is valid.
+macro_rules! check_id {
+    ($s:expr, $($ty:ty),* $(,)?) => {
+        $(
+            if let Ok(id) = <&$ty>::try_from($s) {
+                assert_eq!(id.as_str(), $s);
+            }
+        )*
+    };
+}
+
+fuzz_target!(|s: &str| {
+    check_id!(
+        s,
+        DeviceKeyId,
+        EventId,
+        RoomAliasId,
+        RoomId,
+        RoomOrAliasId,
+        ServerName,
+        ServerSigningKeyId,
+        UserId,
+    );
+
+    if let Ok(room_version) = RoomVersionId::try_from(s) {
+        assert_eq!(room_version.as_str(), s);
+    }
+
+    if let Ok((server_name, media_id)) = <&MxcUri>::from(s).parts() {
+        assert_eq!(format!("mxc://{}/{}", server_name, media_id), s);
+    }
+
+    if let Ok(uri) = MatrixToUri::parse(s) {
+        assert_eq!(MatrixToUri::parse(&uri.to_string()).unwrap(), uri);
+    }
+
+    if let Ok(uri) = MatrixUri::parse(s) {
+        assert_eq!(MatrixUri::parse(&uri.to_string()).unwrap(), uri);
+    }
+});
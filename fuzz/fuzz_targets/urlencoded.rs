@@ -0,0 +1,22 @@
+//! Deserializes arbitrary query strings with the `urlencoded` module.
+//!
+//! The pairs of a query string must be serialized to a query string that is deserialized to the
+//! same pairs.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruma_common::serde::urlencoded;
+
+fuzz_target!(|query: &str| {
+    let pairs: Vec<(String, String)> = match urlencoded::from_str(query) {
+        Ok(pairs) => pairs,
+        Err(_) => return,
+    };
+
+    let serialized = urlencoded::to_string(&pairs).expect("string pairs should be serialized");
+    let reparsed: Vec<(String, String)> =
+        urlencoded::from_str(&serialized).expect("serialized query should be deserialized");
+
+    assert_eq!(reparsed, pairs);
+});
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use clap::{Args, Subcommand};
 use xshell::pushd;
 
-use crate::{cmd, Metadata, Result};
+use crate::{cmd, endpoints::check_endpoint_registries, Metadata, Result};
 
 mod spec_links;
 
@@ -60,12 +60,16 @@ pub enum CiCmd {
     ClippyWasm,
     /// Lint almost all features with clippy (nightly)
     ClippyAll,
+    /// Check the fuzz targets compile (nightly)
+    Fuzz,
     /// Run all lints that don't need compilation
     Lint,
     /// Check sorting of dependencies (lint)
     Dependencies,
     /// Check spec links point to a recent version (lint)
     SpecLinks,
+    /// Check the endpoint registries are up-to-date (lint)
+    Endpoints,
     /// Check typos
     Typos,
 }
@@ -109,9 +113,11 @@ impl CiTask {
             Some(CiCmd::ClippyDefault) => self.clippy_default()?,
             Some(CiCmd::ClippyWasm) => self.clippy_wasm()?,
             Some(CiCmd::ClippyAll) => self.clippy_all()?,
+            Some(CiCmd::Fuzz) => self.fuzz()?,
             Some(CiCmd::Lint) => self.lint()?,
             Some(CiCmd::Dependencies) => self.dependencies()?,
             Some(CiCmd::SpecLinks) => check_spec_links(&self.project_root.join("crates"))?,
+            Some(CiCmd::Endpoints) => check_endpoint_registries(&self.project_root.join("crates"))?,
             Some(CiCmd::Typos) => self.typos()?,
             None => {
                 self.msrv()
@@ -210,7 +216,8 @@ impl CiTask {
         self.nightly_full()?;
         self.clippy_default()?;
         self.clippy_wasm()?;
-        self.clippy_all()
+        self.clippy_all()?;
+        self.fuzz()
     }
 
     /// Check the formatting with the nightly version.
@@ -233,6 +240,15 @@ impl CiTask {
             .map_err(Into::into)
     }
 
+    /// Check that the fuzz targets compile with the nightly version.
+    ///
+    /// The fuzz crate is not part of the workspace, it is normally built with `cargo fuzz`.
+    fn fuzz(&self) -> Result<()> {
+        cmd!("rustup run {NIGHTLY} cargo check --manifest-path fuzz/Cargo.toml")
+            .run()
+            .map_err(Into::into)
+    }
+
     /// Check ruma-common with `ruma_identifiers_storage="Box"`
     fn msrv_owned_id_box(&self) -> Result<()> {
         cmd!("rustup run {MSRV} cargo check -p ruma-common")
@@ -294,8 +310,10 @@ impl CiTask {
         let dependencies_res = self.dependencies();
         // Check that all links point to the same version of the spec
         let spec_links_res = check_spec_links(&self.project_root.join("crates"));
+        // Check that the endpoint registries are up-to-date
+        let endpoints_res = check_endpoint_registries(&self.project_root.join("crates"));
 
-        dependencies_res.and(spec_links_res)
+        dependencies_res.and(spec_links_res).and(endpoints_res)
     }
 
     /// Check the sorting of dependencies with the nightly version.
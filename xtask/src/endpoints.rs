@@ -0,0 +1,206 @@
+//! Generation of the registries of the endpoints of the API crates.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+
+use crate::{Metadata, Result};
+
+/// The crates that contain endpoints defined with `ruma_api!`.
+const API_CRATES: &[&str] = &[
+    "ruma-appservice-api",
+    "ruma-client-api",
+    "ruma-federation-api",
+    "ruma-identity-service-api",
+    "ruma-push-gateway-api",
+];
+
+/// The path of the registry in the API crates.
+const REGISTRY_PATH: &str = "src/endpoints.rs";
+
+#[derive(Args)]
+pub struct EndpointsTask {
+    /// Only check that the registries are up-to-date, without writing them.
+    #[clap(long)]
+    pub check: bool,
+}
+
+impl EndpointsTask {
+    pub(crate) fn run(self) -> Result<()> {
+        let crates_dir = Metadata::load()?.workspace_root.join("crates");
+
+        if self.check {
+            check_endpoint_registries(&crates_dir)
+        } else {
+            for name in API_CRATES {
+                let crate_dir = crates_dir.join(name);
+                fs::write(crate_dir.join(REGISTRY_PATH), generate_registry(&crate_dir)?)?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Check that the endpoint registries of the API crates are up-to-date.
+pub(crate) fn check_endpoint_registries(crates_dir: &Path) -> Result<()> {
+    println!("Checking the endpoint registries are up-to-date...");
+
+    for name in API_CRATES {
+        let crate_dir = crates_dir.join(name);
+        let registry = fs::read_to_string(crate_dir.join(REGISTRY_PATH)).unwrap_or_default();
+
+        if registry != generate_registry(&crate_dir)? {
+            return Err(format!(
+                "error: the endpoint registry of {} is outdated, run `cargo xtask endpoints`",
+                name
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// An endpoint found in the sources of a crate.
+struct Endpoint {
+    /// The path of the module of the endpoint, relative to the root of the crate.
+    module_path: Vec<String>,
+
+    /// The `cfg` attributes of the modules containing the endpoint.
+    cfgs: Vec<String>,
+}
+
+/// Generate the content of the registry of the crate in the given directory.
+fn generate_registry(crate_dir: &Path) -> Result<String> {
+    let src_dir = crate_dir.join("src");
+
+    let mut files = Vec::new();
+    collect_files(&src_dir, &mut files)?;
+    files.sort();
+
+    let mut endpoints = Vec::new();
+    for file in files {
+        if let Some(endpoint) = find_endpoint(&src_dir, &file)? {
+            endpoints.push(endpoint);
+        }
+    }
+
+    let mut registry = String::from(
+        "//! The registry of the endpoints of this crate.
+//!
+//! This file is generated by `cargo xtask endpoints`, do not edit it manually.
+
+use ruma_common::api::Metadata;
+
+/// The metadata of all the endpoints of this crate.
+///
+/// The endpoints that are behind disabled cargo features are not included.
+#[allow(clippy::vec_init_then_push)]
+pub fn all() -> Vec<Metadata> {
+    #[allow(unused_mut)]
+    let mut endpoints = Vec::new();
+",
+    );
+
+    for endpoint in endpoints {
+        for cfg in &endpoint.cfgs {
+            registry.push_str(&format!("    {}\n", cfg));
+        }
+        registry.push_str(&format!(
+            "    endpoints.push(crate::{}::METADATA);\n",
+            endpoint.module_path.join("::")
+        ));
+    }
+
+    registry.push_str("    endpoints\n}\n");
+
+    Ok(registry)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the endpoint defined in the given file, if any.
+///
+/// There is at most one `ruma_api!` call per file, either at the root of the file or inside a
+/// versioned module like `pub mod v3 { ... }`.
+fn find_endpoint(src_dir: &Path, file: &Path) -> Result<Option<Endpoint>> {
+    let content = fs::read_to_string(file)?;
+    let lines: Vec<_> = content.lines().collect();
+
+    let macro_line =
+        match lines.iter().position(|line| line.trim_start().starts_with("ruma_api! {")) {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+
+    let relative_path = file.strip_prefix(src_dir)?.with_extension("");
+    let mut module_path: Vec<String> =
+        relative_path.iter().map(|segment| segment.to_string_lossy().into_owned()).collect();
+
+    let mut cfgs = Vec::new();
+    for i in 0..module_path.len() {
+        let parent_file = if i == 0 {
+            src_dir.join("lib.rs")
+        } else {
+            src_dir.join(module_path[..i].join("/")).with_extension("rs")
+        };
+        let parent_content = fs::read_to_string(parent_file)?;
+        let parent_lines: Vec<_> = parent_content.lines().collect();
+
+        let declaration = format!("mod {};", module_path[i]);
+        let pos = parent_lines
+            .iter()
+            .position(|line| {
+                line.trim_start()
+                    .trim_start_matches("pub ")
+                    .trim_start_matches("pub(crate) ")
+                    .starts_with(&declaration)
+            })
+            .ok_or_else(|| format!("could not find the declaration of `{}`", module_path[i]))?;
+        cfgs.extend(cfg_attributes(&parent_lines[..pos]));
+    }
+
+    // The macro is indented when it is inside a versioned module.
+    if lines[macro_line].starts_with(' ') {
+        let pos = lines[..macro_line]
+            .iter()
+            .rposition(|line| line.starts_with("pub mod ") && line.ends_with(" {"))
+            .ok_or_else(|| format!("could not find the module of the endpoint in {:?}", file))?;
+        let name = lines[pos].trim_start_matches("pub mod ").trim_end_matches(" {");
+
+        module_path.push(name.to_owned());
+        cfgs.extend(cfg_attributes(&lines[..pos]));
+    }
+
+    Ok(Some(Endpoint { module_path, cfgs }))
+}
+
+/// The `cfg` attributes at the end of the given lines.
+fn cfg_attributes(lines: &[&str]) -> Vec<String> {
+    let mut cfgs: Vec<_> = lines
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with("#[") || line.starts_with("///"))
+        .filter(|line| line.starts_with("#[cfg("))
+        .map(ToOwned::to_owned)
+        .collect();
+    cfgs.reverse();
+    cfgs
+}
@@ -15,6 +15,7 @@ use serde_json::from_str as from_json_str;
 mod cargo;
 mod ci;
 mod doc;
+mod endpoints;
 #[cfg(feature = "default")]
 mod release;
 #[cfg(feature = "default")]
@@ -22,6 +23,7 @@ mod util;
 
 use ci::{CiArgs, CiTask};
 use doc::DocTask;
+use endpoints::EndpointsTask;
 #[cfg(feature = "default")]
 use release::{ReleaseArgs, ReleaseTask};
 
@@ -39,6 +41,8 @@ enum Command {
     Ci(CiArgs),
     /// Build the docs
     Doc(DocTask),
+    /// Generate the registries of the endpoints of the API crates
+    Endpoints(EndpointsTask),
     /// Publish a new version of a crate on crates.io, `publish` can be used as an alias
     #[cfg(feature = "default")]
     #[clap(alias = "publish")]
@@ -52,6 +56,7 @@ fn main() -> Result<()> {
             ci.run()
         }
         Command::Doc(doc) => doc.run(),
+        Command::Endpoints(endpoints) => endpoints.run(),
         #[cfg(feature = "default")]
         Command::Release(args) => {
             let mut task = ReleaseTask::new(args.package, args.version, args.dry_run)?;